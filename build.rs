@@ -1,5 +1,12 @@
 extern crate tauri_winres as winres;
 
+#[path = "src/pe_version.rs"]
+mod pe_version;
+
+use flate2::{Compression, write::DeflateEncoder};
+use sha2::{Digest, Sha256};
+use std::{env, fs, io::Write, path::PathBuf};
+
 fn main() {
     let time = vergen::BuildBuilder::default()
         .build_timestamp(true)
@@ -13,6 +20,11 @@ fn main() {
         .emit()
         .unwrap();
 
+    if let Err(e) = write_res_manifest() {
+        eprintln!("{e}");
+        std::process::exit(1);
+    }
+
     #[cfg(windows)]
     {
         let mut res = winres::WindowsResource::new();
@@ -28,3 +40,84 @@ fn main() {
         }
     }
 }
+
+/// 生成嵌入资源清单（src/manifest.rs 引用的 res_manifest.rs）
+///
+/// - 计算 res/ScreenCapture.exe 与 res/config.toml 的 SHA-256
+/// - 从 exe 的版本资源读取 ScreenCapture 版本
+/// - 以 deflate 压缩 exe，写入 ScreenCapture.exe.deflate 供 include_bytes! 嵌入
+/// - 与 Cargo.toml `[package.metadata.res]` 中固定的值比较，不一致时返回错误
+fn write_res_manifest() -> Result<(), String> {
+    for file in ["res/ScreenCapture.exe", "res/config.toml", "Cargo.toml"] {
+        println!("cargo:rerun-if-changed={file}");
+    }
+
+    let read = |path: &str| fs::read(path).map_err(|e| format!("Failed to read {path}: {e}"));
+    let exe = read("res/ScreenCapture.exe")?;
+    let conf = read("res/config.toml")?;
+    let exe_sha256 = sha256_hex(&exe);
+    let conf_sha256 = sha256_hex(&conf);
+    let version_number = pe_version::file_version(&exe);
+    let version = match version_number {
+        Some(version) => pe_version::format_version(version),
+        None => {
+            println!("cargo:warning=res/ScreenCapture.exe has no version resource");
+            "unknown".to_string()
+        }
+    };
+
+    let manifest = fs::read_to_string("Cargo.toml").map_err(|e| e.to_string())?;
+    let manifest: toml::Table = manifest.parse().map_err(|e| format!("Cargo.toml: {e}"))?;
+    let pinned = manifest
+        .get("package")
+        .and_then(|p| p.get("metadata"))
+        .and_then(|m| m.get("res"))
+        .and_then(|r| r.as_table())
+        .cloned()
+        .unwrap_or_default();
+    for (key, actual) in [
+        ("screen_capture_version", &version),
+        ("screen_capture_sha256", &exe_sha256),
+        ("config_sha256", &conf_sha256),
+    ] {
+        if let Some(expected) = pinned.get(key).and_then(|v| v.as_str())
+            && !expected.trim().eq_ignore_ascii_case(actual)
+        {
+            return Err(format!(
+                "Embedded resource mismatch: package.metadata.res.{key} = \"{expected}\", \
+                 but the file in res/ gives \"{actual}\""
+            ));
+        }
+    }
+
+    let out = PathBuf::from(env::var("OUT_DIR").map_err(|e| e.to_string())?);
+    let mut encoder = DeflateEncoder::new(Vec::new(), Compression::best());
+    let compressed = encoder
+        .write_all(&exe)
+        .and_then(|_| encoder.finish())
+        .map_err(|e| format!("Failed to compress res/ScreenCapture.exe: {e}"))?;
+    fs::write(out.join("ScreenCapture.exe.deflate"), &compressed).map_err(|e| e.to_string())?;
+
+    let exe_size = exe.len();
+    let content = format!(
+        "/// ScreenCapture 版本\n\
+         pub static RES_VERSION: &str = \"{version}\";\n\
+         /// ScreenCapture 文件版本号，用于与数据目录中的 exe 比较\n\
+         pub static RES_VERSION_NUMBER: Option<[u16; 4]> = {version_number:?};\n\
+         /// ScreenCapture.exe 的 SHA-256\n\
+         pub static RES_EXE_SHA256: &str = \"{exe_sha256}\";\n\
+         /// ScreenCapture.exe 解压后的大小（字节）\n\
+         pub static RES_EXE_SIZE: u64 = {exe_size};\n\
+         /// config.toml 的 SHA-256\n\
+         pub static RES_CONF_SHA256: &str = \"{conf_sha256}\";\n"
+    );
+    fs::write(out.join("res_manifest.rs"), content).map_err(|e| e.to_string())
+}
+
+/// 计算 SHA-256 并转换为小写十六进制字符串
+fn sha256_hex(data: &[u8]) -> String {
+    Sha256::digest(data)
+        .iter()
+        .map(|byte| format!("{byte:02x}"))
+        .collect()
+}
@@ -0,0 +1,643 @@
+//! 截图任务模块
+//!
+//! 本模块负责：
+//! - 记录单次截图任务的上下文信息
+//! - 按日期模板计算并创建保存子目录
+//! - 定位 ScreenCapture 保存的输出文件
+//! - 按文件名模板重命名截图文件
+//! - 维护每日截图计数器
+
+use crate::types::*;
+use crate::window_handle::{foreground_process_name, foreground_window_title};
+use chrono::{DateTime, Datelike, Local, NaiveDate};
+use std::{
+    fmt::Write,
+    fs, io,
+    path::{Path, PathBuf},
+    sync::Mutex,
+    time::{Duration, SystemTime},
+};
+
+/// 每日计数器文件名（位于程序根目录）
+const COUNTER_FILE: &str = "counter.txt";
+/// 可识别的截图文件扩展名
+pub const IMAGE_EXTS: [&str; 5] = ["png", "jpg", "jpeg", "bmp", "webp"];
+/// 文件重名时最多尝试的序号
+const MAX_COLLISION: u32 = 999;
+/// 窗口标题占位符的默认最大长度
+const TITLE_MAX_LEN: usize = 64;
+
+/// 计数器文件锁，防止并发截图时计数重复
+static COUNTER_LOCK: Mutex<()> = Mutex::new(());
+
+/// 单次截图任务信息
+/// 在启动 ScreenCapture 前生成，记录本次截图的模式、参数与环境
+#[derive(Clone, Debug)]
+pub struct CaptureJob {
+    /// 截图模式
+    pub mode: CaptureMode,
+    /// 截图程序路径
+    pub program: PathBuf,
+    /// 传递给截图程序的命令行参数
+    pub args: Vec<String>,
+    /// 截图引擎指定的输出文件，由截图程序自行命名时为 `None`
+    pub output: Option<PathBuf>,
+    /// 本次截图的保存目录，为空表示手动选择
+    pub save_dir: PathBuf,
+    /// 配置的保存目录均不可用时的失败原因
+    pub save_dir_error: Option<String>,
+    /// 截图开始时间
+    pub started: DateTime<Local>,
+    /// 截图程序退出时间，用于计算截图耗时（不含后续处理与上传）
+    pub finished: Option<DateTime<Local>>,
+    /// 截图开始时的前台窗口标题
+    pub window_title: String,
+    /// 截图开始时前台窗口所属程序的文件名
+    pub window_app: String,
+}
+/// 截图开始时的环境信息
+/// 在托盘或快捷键线程中同步获取，其余准备工作放到截图线程中进行
+#[derive(Clone, Debug)]
+pub struct CaptureOrigin {
+    /// 截图开始时间
+    pub started: DateTime<Local>,
+    /// 截图开始时的前台窗口标题
+    pub window_title: String,
+    /// 截图开始时前台窗口所属程序的文件名
+    pub window_app: String,
+}
+impl CaptureOrigin {
+    /// 记录当前时间与前台窗口
+    ///
+    /// ### 说明
+    /// - 必须在启动截图线程之前调用，之后前台窗口可能已经改变
+    /// - 只读取窗口信息，不访问磁盘，不会阻塞托盘事件循环
+    pub fn now() -> Self {
+        CaptureOrigin {
+            started: Local::now(),
+            window_title: unsafe { foreground_window_title() },
+            window_app: unsafe { foreground_process_name() },
+        }
+    }
+}
+
+impl CaptureJob {
+    /// 创建截图任务
+    ///
+    /// ### 参数
+    /// - `ctx`: 截图任务上下文
+    /// - `mode`: 截图模式
+    /// - `origin`: 截图开始时的环境信息（见 `CaptureOrigin::now`）
+    ///
+    /// ### 说明
+    /// - 会检查并创建保存目录，目录位于断开的网络共享时可能长时间阻塞，
+    ///   因此应在截图线程中调用
+    pub fn new(ctx: &CaptureContext, mode: CaptureMode, origin: CaptureOrigin) -> Self {
+        let CaptureOrigin {
+            started,
+            window_title,
+            window_app,
+        } = origin;
+        // 每次截图前重新检查保存目录，应对移动硬盘拔出、网络共享断开等情况
+        let (base, save_dir_error) = if mode == CaptureMode::Pin {
+            (PathBuf::new(), None)
+        } else {
+            match select_save_dir(&ctx.settings.path) {
+                Ok(dir) => (dir, None),
+                Err(e) => {
+                    eprintln!("No save path is available:\n{}", e);
+                    (PathBuf::new(), Some(e))
+                }
+            }
+        };
+        // 去除 canonicalize 产生的 \\?\ 前缀，便于外部程序使用
+        let save_dir = strip_verbatim(&resolve_save_dir(&ctx.settings, &base, mode, &started));
+        let command = ctx.engine.command(&ctx.settings, mode, &save_dir, &started);
+        CaptureJob {
+            mode,
+            program: command.program,
+            args: command.args,
+            output: command.output,
+            save_dir,
+            save_dir_error,
+            started,
+            finished: None,
+            window_title,
+            window_app,
+        }
+    }
+}
+
+/// 计算本次截图的保存目录
+///
+/// ### 参数
+/// - `settings`: 完整配置集合
+/// - `base`: 本次截图前选择的保存目录，为空表示手动选择
+/// - `mode`: 截图模式
+/// - `now`: 截图开始时间
+///
+/// ### 返回值
+/// - `PathBuf`: 保存目录，为空表示手动选择
+///
+/// ### 功能
+/// - 未配置子目录模板或为手动选择时直接返回 base
+/// - 按模板展开子目录并自动创建
+/// - 创建失败时按 subfolder_fallback 回退到 base 根目录或手动选择
+pub fn resolve_save_dir(
+    settings: &SettingsCollection,
+    base: &Path,
+    mode: CaptureMode,
+    now: &DateTime<Local>,
+) -> PathBuf {
+    if base.as_os_str().is_empty() || settings.path.subfolder.is_empty() || mode == CaptureMode::Pin
+    {
+        return base.to_path_buf();
+    }
+
+    let relative = render_subfolder(settings, mode, now);
+    if relative.as_os_str().is_empty() {
+        return base.to_path_buf();
+    }
+
+    let target = base.join(relative);
+    match fs::create_dir_all(&target) {
+        Ok(_) => target,
+        Err(e) => {
+            eprintln!(
+                "Failed to create subfolder {}: {}, fallback: {:?}",
+                target.display(),
+                e,
+                settings.path.subfolder_fallback
+            );
+            match settings.path.subfolder_fallback {
+                SubfolderFallback::Base => base.to_path_buf(),
+                SubfolderFallback::Manual => PathBuf::new(),
+            }
+        }
+    }
+}
+
+/// 渲染子目录模板
+///
+/// ### 支持的占位符
+/// - `{yyyy}` / `{yy}` / `{mm}` / `{dd}`: 年 / 两位年 / 月 / 日
+/// - `{week}` / `{isoyear}`: ISO 周数（两位）及其所属年份
+/// - `{date:格式}`: 任意 strftime 格式
+/// - `{profile}` / `{project}`: 当前配置名
+/// - `{mode}`: 截图模式
+///
+/// ### 返回值
+/// - `PathBuf`: 相对于 save_path 的子目录，以 `/` 分隔多级目录
+///
+/// ### 说明
+/// - 每级目录名都会被规范化，空目录名与 `..` 会被忽略，保证结果位于 save_path 内
+fn render_subfolder(
+    settings: &SettingsCollection,
+    mode: CaptureMode,
+    now: &DateTime<Local>,
+) -> PathBuf {
+    let mut relative = PathBuf::new();
+    for part in settings.path.subfolder.split('/') {
+        let rendered = expand_placeholders(part, |name, spec| match name {
+            "yyyy" => format_time(now, "%Y"),
+            "yy" => format_time(now, "%y"),
+            "mm" => format_time(now, "%m"),
+            "dd" => format_time(now, "%d"),
+            "week" => Some(format!("{:02}", now.iso_week().week())),
+            "isoyear" => Some(now.iso_week().year().to_string()),
+            "date" => format_time(now, spec.unwrap_or("%Y-%m-%d")),
+            "profile" | "project" => Some(settings.sundry.profile.clone()),
+            "mode" => Some(mode.key().to_string()),
+            _ => None,
+        });
+        let component = sanitize_file_name(&rendered);
+        if !component.is_empty() {
+            relative.push(component);
+        }
+    }
+    relative
+}
+
+/// 截图结束后处理输出文件
+///
+/// ### 参数
+/// - `ctx`: 截图任务上下文
+/// - `job`: 本次截图任务信息
+/// - `outcome`: 截图结果
+///
+/// ### 返回值
+/// - `Option<PathBuf>`: 最终的截图文件路径，未保存到文件或无法定位时返回 `None`
+///
+/// ### 功能
+/// - 仅处理保存到文件的结果
+/// - 优先使用截图引擎指定的输出文件，否则在保存目录中定位本次截图生成的文件
+/// - 配置了文件名模板时按模板重命名，失败时保留原文件名
+pub fn finish_capture(
+    ctx: &CaptureContext,
+    job: &CaptureJob,
+    outcome: &CaptureOutcome,
+) -> Option<PathBuf> {
+    if *outcome != CaptureOutcome::SavedToFile {
+        return None;
+    }
+
+    let output = job
+        .output
+        .clone()
+        .filter(|file| file.is_file())
+        .or_else(|| locate_output(&job.save_dir, job.started.into()))?;
+    println!("Output: {}", output.display());
+
+    if ctx.settings.path.filename_template.is_empty() {
+        return Some(output);
+    }
+    match rename_by_template(ctx, job, &output) {
+        Ok(renamed) => {
+            println!("Renamed: {}", renamed.display());
+            Some(renamed)
+        }
+        Err(e) => {
+            eprintln!("Failed to rename capture by template: {}", e);
+            Some(output)
+        }
+    }
+}
+
+/// 在保存目录中定位本次截图生成的文件
+///
+/// ### 参数
+/// - `dir`: 保存目录，为空时表示手动选择，无法定位
+/// - `since`: 截图开始时间
+///
+/// ### 返回值
+/// - `Option<PathBuf>`: 开始时间之后修改的最新图片文件
+pub fn locate_output(dir: &Path, since: SystemTime) -> Option<PathBuf> {
+    if dir.as_os_str().is_empty() {
+        return None;
+    }
+    // 容忍文件系统时间戳精度带来的误差
+    let since = since.checked_sub(Duration::from_secs(2)).unwrap_or(since);
+
+    fs::read_dir(dir)
+        .ok()?
+        .filter_map(|entry| entry.ok())
+        .filter(|entry| is_image(&entry.path()))
+        .filter_map(|entry| {
+            let meta = entry.metadata().ok()?;
+            let modified = meta.modified().ok()?;
+            (meta.is_file() && modified >= since).then(|| (modified, entry.path()))
+        })
+        .max_by_key(|(modified, _)| *modified)
+        .map(|(_, path)| path)
+}
+
+/// 判断路径是否为可识别的截图文件
+pub fn is_image(path: &Path) -> bool {
+    path.extension()
+        .and_then(|ext| ext.to_str())
+        .map(|ext| IMAGE_EXTS.contains(&ext.to_ascii_lowercase().as_str()))
+        .unwrap_or(false)
+}
+
+/// 按文件名模板重命名截图文件
+///
+/// ### 参数
+/// - `ctx`: 截图任务上下文
+/// - `job`: 本次截图任务信息
+/// - `output`: ScreenCapture 保存的文件
+///
+/// ### 返回值
+/// - `io::Result<PathBuf>`: 重命名后的文件路径
+///
+/// ### 说明
+/// - 扩展名始终沿用原文件的扩展名
+/// - 目标文件已存在时自动追加 `_1`、`_2` 等序号，不会覆盖已有文件
+fn rename_by_template(
+    ctx: &CaptureContext,
+    job: &CaptureJob,
+    output: &Path,
+) -> io::Result<PathBuf> {
+    let stem = render_file_name(ctx, job);
+    if stem.is_empty() {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidInput,
+            "file name template renders to an empty name",
+        ));
+    }
+    let ext = output
+        .extension()
+        .and_then(|ext| ext.to_str())
+        .unwrap_or("png")
+        .to_string();
+    let dir = output.parent().unwrap_or(Path::new("."));
+    move_no_clobber(output, dir, &stem, &ext)
+}
+
+/// 渲染文件名模板
+///
+/// ### 支持的占位符
+/// - `{date}` / `{date:格式}`: 日期，默认 `%Y-%m-%d`
+/// - `{time}` / `{time:格式}`: 时间，默认 `%H%M%S`
+/// - `{counter}` / `{counter:03}`: 当日截图序号，可指定补零宽度
+/// - `{hostname}`: 计算机名
+/// - `{profile}` / `{project}`: 当前配置名
+/// - `{title}` / `{title:长度}`: 截图前的前台窗口标题
+/// - `{mode}`: 截图模式
+///
+/// ### 返回值
+/// - `String`: 不含扩展名的合法文件名
+fn render_file_name(ctx: &CaptureContext, job: &CaptureJob) -> String {
+    let mut counter: Option<u32> = None;
+    let rendered = expand_placeholders(
+        &ctx.settings.path.filename_template,
+        |name, spec| match name {
+            "date" => format_time(&job.started, spec.unwrap_or("%Y-%m-%d")),
+            "time" => format_time(&job.started, spec.unwrap_or("%H%M%S")),
+            "counter" => {
+                let value = match counter {
+                    Some(value) => value,
+                    None => {
+                        let value =
+                            next_daily_counter(&ctx.paths.dir_path, job.started.date_naive())
+                                .unwrap_or_else(|e| {
+                                    eprintln!("Failed to update daily counter: {}", e);
+                                    1
+                                });
+                        counter = Some(value);
+                        value
+                    }
+                };
+                let width = spec.and_then(|s| s.parse::<usize>().ok()).unwrap_or(0);
+                Some(format!("{:0width$}", value, width = width))
+            }
+            "hostname" => Some(std::env::var("COMPUTERNAME").unwrap_or_default()),
+            "profile" | "project" => Some(ctx.settings.sundry.profile.clone()),
+            "title" => {
+                let max_len = spec
+                    .and_then(|s| s.parse::<usize>().ok())
+                    .unwrap_or(TITLE_MAX_LEN);
+                Some(job.window_title.chars().take(max_len).collect())
+            }
+            "mode" => Some(job.mode.key().to_string()),
+            _ => None,
+        },
+    );
+
+    // 模板中自带的图片扩展名会被替换为实际扩展名
+    let path = Path::new(&rendered);
+    let stem = if is_image(path) {
+        path.file_stem()
+            .map(|s| s.to_string_lossy().to_string())
+            .unwrap_or_default()
+    } else {
+        rendered.clone()
+    };
+    sanitize_file_name(&stem)
+}
+
+/// 按 strftime 格式输出时间
+///
+/// ### 返回值
+/// - `Option<String>`: 格式说明无效时返回 `None`，占位符将保留原样
+pub fn format_time(time: &DateTime<Local>, spec: &str) -> Option<String> {
+    let mut output = String::new();
+    write!(output, "{}", time.format(spec)).ok()?;
+    Some(output)
+}
+
+/// 获取并递增当日截图序号
+///
+/// ### 参数
+/// - `dir_path`: 程序根目录，计数器文件存放于此
+/// - `today`: 当前日期
+///
+/// ### 返回值
+/// - `io::Result<u32>`: 当日的截图序号（从 1 开始）
+///
+/// ### 说明
+/// - 计数器文件格式为 `YYYY-MM-DD 序号`，日期变化时序号重置
+fn next_daily_counter(dir_path: &Path, today: NaiveDate) -> io::Result<u32> {
+    let _guard = COUNTER_LOCK.lock().unwrap_or_else(|e| e.into_inner());
+    let counter_path = dir_path.join(COUNTER_FILE);
+    let today_str = today.format("%Y-%m-%d").to_string();
+
+    let last = fs::read_to_string(&counter_path)
+        .ok()
+        .and_then(|content| {
+            let (date, count) = content.trim().split_once(' ')?;
+            (date == today_str).then(|| count.parse::<u32>().ok())?
+        })
+        .unwrap_or(0);
+
+    let next = last + 1;
+    fs::write(&counter_path, format!("{} {}", today_str, next))?;
+    Ok(next)
+}
+
+/// 将文件移动到目标目录并命名，不覆盖已有文件
+///
+/// ### 参数
+/// - `src`: 源文件
+/// - `dir`: 目标目录
+/// - `stem`: 目标文件名（不含扩展名）
+/// - `ext`: 扩展名
+///
+/// ### 返回值
+/// - `io::Result<PathBuf>`: 实际使用的目标路径
+///
+/// ### 功能
+/// - 先以独占方式创建目标文件占位，占位成功后再替换，避免竞争时覆盖他人文件
+/// - 重名时依次尝试 `名称_1`、`名称_2` ...
+/// - 重命名失败（如跨卷）时退化为复制后删除
+pub fn move_no_clobber(src: &Path, dir: &Path, stem: &str, ext: &str) -> io::Result<PathBuf> {
+    for i in 0..=MAX_COLLISION {
+        let name = match (i, ext.is_empty()) {
+            (0, true) => stem.to_string(),
+            (0, false) => format!("{}.{}", stem, ext),
+            (_, true) => format!("{}_{}", stem, i),
+            (_, false) => format!("{}_{}.{}", stem, i, ext),
+        };
+        let target = dir.join(name);
+        if target == src {
+            return Ok(target);
+        }
+
+        match fs::OpenOptions::new()
+            .write(true)
+            .create_new(true)
+            .open(&target)
+        {
+            Ok(_) => (),
+            Err(e) if e.kind() == io::ErrorKind::AlreadyExists => continue,
+            Err(e) => return Err(e),
+        }
+
+        let moved = fs::rename(src, &target)
+            .or_else(|_| fs::copy(src, &target).and_then(|_| fs::remove_file(src)));
+        if let Err(e) = moved {
+            let _ = fs::remove_file(&target);
+            return Err(e);
+        }
+        return Ok(target);
+    }
+    Err(io::Error::new(
+        io::ErrorKind::AlreadyExists,
+        "too many files with the same name",
+    ))
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use super::*;
+    use crate::engine::ScreenCaptureEngine;
+    use chrono::TimeZone;
+    use std::sync::Arc;
+
+    /// 为测试创建空的临时目录
+    pub(crate) fn temp_dir(name: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("sc_starter_{}_{}", name, std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).unwrap();
+        dir
+    }
+
+    /// 以 `dir` 为程序目录的截图任务上下文
+    pub(crate) fn test_context(
+        dir: &Path,
+        edit: impl FnOnce(&mut SettingsCollection),
+    ) -> CaptureContext {
+        let mut settings = SettingsCollection::default();
+        edit(&mut settings);
+        CaptureContext {
+            paths: PathInfos {
+                dir_path: dir.to_path_buf(),
+                exe_path: dir.join("ScreenCapture.exe"),
+                conf_path: dir.join("config.toml"),
+                conf_example_path: dir.join("config_example.toml"),
+                engine_path: None,
+            },
+            settings: Arc::new(settings),
+            engine: Arc::new(ScreenCaptureEngine {
+                path: dir.join("ScreenCapture.exe"),
+                caps: EngineCapabilities::default(),
+            }),
+        }
+    }
+
+    /// 2026-03-05 14:07:09 开始的区域截图任务
+    pub(crate) fn test_job(dir: &Path, title: &str) -> CaptureJob {
+        CaptureJob {
+            mode: CaptureMode::Region,
+            program: PathBuf::new(),
+            args: Vec::new(),
+            output: None,
+            save_dir: dir.to_path_buf(),
+            save_dir_error: None,
+            started: Local.with_ymd_and_hms(2026, 3, 5, 14, 7, 9).unwrap(),
+            finished: None,
+            window_title: title.to_string(),
+            window_app: "notepad.exe".to_string(),
+        }
+    }
+
+    fn render(dir: &Path, template: &str, title: &str) -> String {
+        let ctx = test_context(dir, |s| {
+            s.path.filename_template = template.to_string();
+            s.sundry.profile = "docs".to_string();
+        });
+        render_file_name(&ctx, &test_job(dir, title))
+    }
+
+    #[test]
+    fn renders_placeholders() {
+        let dir = temp_dir("render");
+        assert_eq!(
+            render(&dir, "{date}_{time}_{mode}_{profile}_{project}", ""),
+            "2026-03-05_140709_region_docs_docs"
+        );
+        assert_eq!(
+            render(&dir, "{date:%Y%m%d}-{time:%H.%M}", ""),
+            "20260305-14.07"
+        );
+        assert_eq!(
+            render(&dir, "{title:5} {title}", "Hello World"),
+            "Hello Hello World"
+        );
+        // 未知占位符保留原样
+        assert_eq!(render(&dir, "shot {unknown}", ""), "shot {unknown}");
+        // 模板中的图片扩展名被去除
+        assert_eq!(render(&dir, "shot_{mode}.png", ""), "shot_region");
+    }
+
+    #[test]
+    fn sanitizes_file_names() {
+        let dir = temp_dir("sanitize");
+        assert_eq!(
+            render(&dir, "{title}", r#"a/b\c:d*e?f"g<h>i|j"#),
+            "a_b_c_d_e_f_g_h_i_j"
+        );
+        assert_eq!(render(&dir, "{title}", " report. "), "report");
+        assert_eq!(render(&dir, "{title}", "tab\there"), "tab_here");
+    }
+
+    #[test]
+    fn counter_is_shared_within_a_name() {
+        let dir = temp_dir("render_counter");
+        assert_eq!(render(&dir, "{counter:03}-{counter}", ""), "001-1");
+        assert_eq!(render(&dir, "{counter:03}-{counter}", ""), "002-2");
+    }
+
+    #[test]
+    fn daily_counter_rolls_over() {
+        let dir = temp_dir("counter");
+        let day = NaiveDate::from_ymd_opt(2026, 3, 5).unwrap();
+        assert_eq!(next_daily_counter(&dir, day).unwrap(), 1);
+        assert_eq!(next_daily_counter(&dir, day).unwrap(), 2);
+        assert_eq!(
+            fs::read_to_string(dir.join(COUNTER_FILE)).unwrap(),
+            "2026-03-05 2"
+        );
+        // 日期变化后从 1 重新计数
+        let next_day = day.succ_opt().unwrap();
+        assert_eq!(next_daily_counter(&dir, next_day).unwrap(), 1);
+        // 无法解析的计数器文件视为新的一天
+        fs::write(dir.join(COUNTER_FILE), "garbage").unwrap();
+        assert_eq!(next_daily_counter(&dir, next_day).unwrap(), 1);
+    }
+
+    #[test]
+    fn move_adds_suffix_on_collision() {
+        let dir = temp_dir("move");
+        let target = dir.join("out");
+        fs::create_dir_all(&target).unwrap();
+        for (i, expected) in ["shot.png", "shot_1.png", "shot_2.png"].iter().enumerate() {
+            let src = dir.join(format!("src{}.tmp", i));
+            fs::write(&src, format!("capture {}", i)).unwrap();
+            let moved = move_no_clobber(&src, &target, "shot", "png").unwrap();
+            assert_eq!(moved, target.join(expected));
+            assert!(!src.exists());
+            assert_eq!(
+                fs::read_to_string(&moved).unwrap(),
+                format!("capture {}", i)
+            );
+        }
+
+        // 无扩展名
+        for expected in ["notes", "notes_1"] {
+            let src = dir.join("notes.tmp");
+            fs::write(&src, "notes").unwrap();
+            let moved = move_no_clobber(&src, &target, "notes", "").unwrap();
+            assert_eq!(moved, target.join(expected));
+        }
+    }
+
+    #[test]
+    fn move_onto_itself_keeps_file() {
+        let dir = temp_dir("move_same");
+        let file = dir.join("shot.png");
+        fs::write(&file, "capture").unwrap();
+        assert_eq!(move_no_clobber(&file, &dir, "shot", "png").unwrap(), file);
+        assert_eq!(fs::read_to_string(&file).unwrap(), "capture");
+        assert!(!dir.join("shot_1.png").exists());
+    }
+}
@@ -0,0 +1,704 @@
+//! 命令行模块
+//!
+//! 本模块负责：
+//! - 识别并分发命令行子命令
+//! - 查询、筛选与导出截图历史（`history`）
+//! - 扫描目录并报告重复截图（`dedupe`）
+//! - 按配置上传文件以验证上传设置（`upload`）
+//! - 读取截图中的来源元数据（`meta`）
+//! - 列出用户配置中缺少的示例配置项（`diff-config`）
+//! - 在 Windows 子系统程序中附加到父进程控制台以输出结果
+
+use crate::config::{missing_keys, parse_config_file, read_cli_settings, uncomment_example};
+use crate::dedupe::scan_duplicates;
+use crate::file_ops::RES_CONF;
+use crate::history::{HistoryEntry, HistoryFilter, history_path, load_history};
+use crate::metadata::{METADATA_KEYWORD, StoredMetadata, read_capture_metadata, sidecar_path};
+use crate::upload::upload_file;
+use crate::window_handle::attach_parent_console;
+use chrono::{DateTime, Duration, Local, NaiveDate, TimeZone};
+use std::{
+    fs,
+    io::{self, Write},
+    path::{Path, PathBuf},
+};
+
+/// 命令行执行成功
+const CLI_OK: i32 = 0;
+/// 命令行参数错误
+const CLI_USAGE: i32 = 1;
+/// 读写文件失败
+const CLI_IO: i32 = 2;
+
+const HISTORY_USAGE: &str = r#"Usage: SC_Starter.exe history <list|export> [options]
+
+Commands:
+    list                  Print matching captures as a table
+    export                Export matching captures as JSON Lines or CSV
+
+Options:
+    --since <when>        Only captures at or after <when>
+    --until <when>        Only captures before the end of <when>
+    --mode <m,...>        region, long, fullscreen, pin
+    --outcome <o,...>     saved, clipboard, pinned, cancelled, crashed, unknown
+    --profile <p,...>     Profile names
+    --path <glob>         Output path pattern, e.g. "D:/Shots/**/*.png"
+    --limit <n>           Only the newest <n> captures
+    --format <jsonl|csv>  Export format (default: jsonl)
+    --output <file>       Write the export to <file> instead of stdout
+
+<when> is YYYY-MM-DD, "YYYY-MM-DD HH:MM", today, yesterday or <n>d (n days ago)"#;
+
+const DEDUPE_USAGE: &str = r#"Usage: SC_Starter.exe dedupe [folder] [options]
+
+Scans [folder] (default: the configured save path) and reports groups of
+identical or visually similar captures. Nothing is modified.
+
+Options:
+    --threshold <n>       Maximum perceptual hash distance, 0-64 (default: config or 4)
+    --exact               Only report byte-identical files
+    --no-recursive        Do not scan subfolders"#;
+
+const UPLOAD_USAGE: &str = r#"Usage: SC_Starter.exe upload <file> [--url <url>]
+
+Uploads <file> with the [upload] settings from config.toml and prints the
+returned link. --url overrides the configured endpoint, e.g. a local test
+server such as http://127.0.0.1:8080/upload. The upload works even when
+[upload] is disabled, so the settings can be tried out first."#;
+
+const META_USAGE: &str = r#"Usage: SC_Starter.exe meta <file> [--json]
+
+Prints the capture metadata stored in <file>: PNG text chunks (tEXt/iTXt)
+and the <file>.json sidecar, if present.
+
+Options:
+    --json                Print only the SC_Starter metadata as JSON"#;
+
+const DIFF_CONFIG_USAGE: &str = r#"Usage: SC_Starter.exe diff-config [config]
+
+Lists the sections and keys of the bundled config example that are missing
+from [config] (default: config.toml in the data folder). Missing keys use
+their default values; copy them from config_example.toml to change them.
+Optional keys that are commented out in the example are listed separately."#;
+
+/// 尝试作为命令行工具运行
+///
+/// ### 参数
+/// - `args`: 去除程序路径后的命令行参数
+///
+/// ### 返回值
+/// - `Option<i32>`: 识别到子命令时返回进程退出码，否则返回 `None` 以继续启动托盘程序
+pub fn run_cli(args: &[String]) -> Option<i32> {
+    let command = args.first()?.as_str();
+    let handler: fn(&Path, &[String]) -> i32 = match command {
+        "history" => cmd_history,
+        "dedupe" => cmd_dedupe,
+        "upload" => cmd_upload,
+        "meta" => cmd_meta,
+        "diff-config" => cmd_diff_config,
+        _ => return None,
+    };
+
+    // 发布版为 Windows 子系统程序，需附加到父进程控制台才能输出
+    unsafe {
+        attach_parent_console();
+    }
+
+    let dir_path = match directories::BaseDirs::new() {
+        Some(bd) => bd.data_local_dir().join("SC_Starter"),
+        None => {
+            eprintln!("Failed to determine base directories (LOCALAPPDATA missing?).");
+            return Some(CLI_IO);
+        }
+    };
+    Some(handler(&dir_path, &args[1..]))
+}
+
+/// 导出格式
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum ExportFormat {
+    JsonLines,
+    Csv,
+}
+
+/// history 子命令选项
+struct HistoryOptions {
+    filter: HistoryFilter,
+    limit: Option<usize>,
+    format: ExportFormat,
+    output: Option<PathBuf>,
+}
+
+/// 处理 history 子命令
+///
+/// ### 参数
+/// - `dir_path`: 程序根目录（历史记录所在目录）
+/// - `args`: history 之后的参数
+///
+/// ### 返回值
+/// - `i32`: 进程退出码
+fn cmd_history(dir_path: &Path, args: &[String]) -> i32 {
+    let action = args.first().map(String::as_str).unwrap_or("help");
+    if !matches!(action, "list" | "export") {
+        println!("{}", HISTORY_USAGE);
+        return if action == "help" { CLI_OK } else { CLI_USAGE };
+    }
+
+    let options = match parse_history_options(&args[1..]) {
+        Ok(options) => options,
+        Err(e) => {
+            eprintln!("{}\n\n{}", e, HISTORY_USAGE);
+            return CLI_USAGE;
+        }
+    };
+
+    let mut entries: Vec<HistoryEntry> = load_history(dir_path)
+        .into_iter()
+        .filter(|entry| options.filter.matches(entry))
+        .collect();
+    if let Some(limit) = options.limit
+        && entries.len() > limit
+    {
+        entries.drain(..entries.len() - limit);
+    }
+
+    let result = if action == "list" {
+        println!("History: {}", history_path(dir_path).display());
+        print_table(&entries);
+        Ok(())
+    } else {
+        export_entries(&entries, options.format, options.output.as_deref())
+    };
+    match result {
+        Ok(_) => CLI_OK,
+        Err(e) => {
+            eprintln!("Failed to export history: {}", e);
+            CLI_IO
+        }
+    }
+}
+
+/// 解析 history 子命令选项
+fn parse_history_options(args: &[String]) -> Result<HistoryOptions, String> {
+    let mut options = HistoryOptions {
+        filter: HistoryFilter::default(),
+        limit: None,
+        format: ExportFormat::JsonLines,
+        output: None,
+    };
+
+    let mut iter = args.iter();
+    while let Some(flag) = iter.next() {
+        let mut value = || {
+            iter.next()
+                .cloned()
+                .ok_or_else(|| format!("Missing value for {}", flag))
+        };
+        let list = |text: String| -> Vec<String> {
+            text.split(',')
+                .map(|s| s.trim().to_string())
+                .filter(|s| !s.is_empty())
+                .collect()
+        };
+        match flag.as_str() {
+            "--since" => options.filter.since = Some(parse_when(&value()?, false)?),
+            "--until" => options.filter.until = Some(parse_when(&value()?, true)?),
+            "--mode" => options.filter.modes = list(value()?),
+            "--outcome" => options.filter.outcomes = list(value()?),
+            "--profile" => options.filter.profiles = list(value()?),
+            "--path" => {
+                let pattern = value()?.replace('\\', "/");
+                options.filter.path_pattern =
+                    Some(glob::Pattern::new(&pattern).map_err(|e| e.to_string())?);
+            }
+            "--limit" => {
+                let text = value()?;
+                options.limit = Some(
+                    text.parse()
+                        .map_err(|_| format!("Invalid limit: {}", text))?,
+                );
+            }
+            "--format" => {
+                options.format = match value()?.to_ascii_lowercase().as_str() {
+                    "jsonl" | "json" => ExportFormat::JsonLines,
+                    "csv" => ExportFormat::Csv,
+                    other => return Err(format!("Unknown format: {}", other)),
+                }
+            }
+            "--output" => options.output = Some(PathBuf::from(value()?)),
+            other => return Err(format!("Unknown option: {}", other)),
+        }
+    }
+    Ok(options)
+}
+
+/// 解析时间参数
+///
+/// ### 参数
+/// - `text`: 日期、日期时间、today、yesterday 或 `<n>d`
+/// - `end`: 是否作为结束时间（仅日期时取次日零点）
+///
+/// ### 返回值
+/// - `Result<DateTime<Local>, String>`: 本地时间
+fn parse_when(text: &str, end: bool) -> Result<DateTime<Local>, String> {
+    let text = text.trim();
+    let today = Local::now().date_naive();
+    let date = match text {
+        "today" => Some(today),
+        "yesterday" => today.pred_opt(),
+        _ => match text.strip_suffix('d').and_then(|n| n.parse::<i64>().ok()) {
+            Some(days) => Some(
+                Duration::try_days(days)
+                    .and_then(|delta| today.checked_sub_signed(delta))
+                    .ok_or_else(|| "date out of range".to_string())?,
+            ),
+            None => NaiveDate::parse_from_str(text, "%Y-%m-%d").ok(),
+        },
+    };
+
+    let naive = match date {
+        Some(date) => {
+            let date = if end {
+                date.succ_opt().unwrap_or(date)
+            } else {
+                date
+            };
+            date.and_hms_opt(0, 0, 0)
+        }
+        None => chrono::NaiveDateTime::parse_from_str(text, "%Y-%m-%d %H:%M")
+            .or_else(|_| chrono::NaiveDateTime::parse_from_str(text, "%Y-%m-%dT%H:%M:%S"))
+            .ok(),
+    };
+    naive
+        .and_then(|n| Local.from_local_datetime(&n).earliest())
+        .ok_or_else(|| format!("Invalid time: {}", text))
+}
+
+/// 以表格形式输出历史记录
+fn print_table(entries: &[HistoryEntry]) {
+    println!(
+        "{:<19}  {:<10}  {:<9}  {:>8}  {:<10}  Path",
+        "Time", "Mode", "Outcome", "Duration", "Profile"
+    );
+    for entry in entries {
+        println!(
+            "{:<19}  {:<10}  {:<9}  {:>7.1}s  {:<10}  {}",
+            entry.timestamp.format("%Y-%m-%d %H:%M:%S"),
+            entry.mode,
+            entry.outcome,
+            entry.duration_ms as f64 / 1000.0,
+            entry.profile,
+            entry
+                .path
+                .as_ref()
+                .map(|p| p.display().to_string())
+                .unwrap_or_else(|| "-".to_string()),
+        );
+    }
+    println!("{} capture(s)", entries.len());
+}
+
+/// 导出历史记录
+///
+/// ### 参数
+/// - `entries`: 要导出的记录
+/// - `format`: 导出格式
+/// - `output`: 输出文件，为 `None` 时输出到标准输出
+fn export_entries(
+    entries: &[HistoryEntry],
+    format: ExportFormat,
+    output: Option<&Path>,
+) -> io::Result<()> {
+    let mut text = String::new();
+    match format {
+        ExportFormat::JsonLines => {
+            for entry in entries {
+                text.push_str(&entry.to_json().to_string());
+                text.push('\n');
+            }
+        }
+        ExportFormat::Csv => {
+            text.push_str("timestamp,mode,outcome,code,path,duration_ms,profile,args\r\n");
+            for entry in entries {
+                let fields = [
+                    entry.timestamp.to_rfc3339(),
+                    entry.mode.clone(),
+                    entry.outcome.clone(),
+                    entry.code.map(|c| c.to_string()).unwrap_or_default(),
+                    entry
+                        .path
+                        .as_ref()
+                        .map(|p| p.display().to_string())
+                        .unwrap_or_default(),
+                    entry.duration_ms.to_string(),
+                    entry.profile.clone(),
+                    entry.args.join(" "),
+                ];
+                let line: Vec<String> = fields.iter().map(|f| csv_field(f)).collect();
+                text.push_str(&line.join(","));
+                text.push_str("\r\n");
+            }
+        }
+    }
+
+    match output {
+        Some(path) => {
+            fs::write(path, text)?;
+            println!(
+                "Exported {} capture(s) to {}",
+                entries.len(),
+                path.display()
+            );
+            Ok(())
+        }
+        None => io::stdout().write_all(text.as_bytes()),
+    }
+}
+
+/// 按 RFC 4180 转义 CSV 字段
+fn csv_field(field: &str) -> String {
+    if field.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", field.replace('"', "\"\""))
+    } else {
+        field.to_string()
+    }
+}
+
+/// 处理 dedupe 子命令
+///
+/// ### 参数
+/// - `dir_path`: 程序根目录（配置文件所在目录）
+/// - `args`: dedupe 之后的参数
+///
+/// ### 返回值
+/// - `i32`: 进程退出码
+///
+/// ### 说明
+/// - 只报告重复分组，不修改任何文件
+fn cmd_dedupe(dir_path: &Path, args: &[String]) -> i32 {
+    let mut folder: Option<PathBuf> = None;
+    let mut threshold: Option<u32> = None;
+    let mut exact = false;
+    let mut recursive = true;
+
+    let mut iter = args.iter();
+    while let Some(arg) = iter.next() {
+        match arg.as_str() {
+            "help" | "--help" | "-h" => {
+                println!("{}", DEDUPE_USAGE);
+                return CLI_OK;
+            }
+            "--exact" => exact = true,
+            "--no-recursive" => recursive = false,
+            "--threshold" => match iter.next().and_then(|v| v.parse::<u32>().ok()) {
+                Some(num) if num <= 64 => threshold = Some(num),
+                _ => {
+                    eprintln!("Invalid threshold\n\n{}", DEDUPE_USAGE);
+                    return CLI_USAGE;
+                }
+            },
+            other if other.starts_with("--") || folder.is_some() => {
+                eprintln!("Unknown option: {}\n\n{}", other, DEDUPE_USAGE);
+                return CLI_USAGE;
+            }
+            other => folder = Some(PathBuf::from(other)),
+        }
+    }
+
+    // 未指定目录或阈值时使用配置文件中的设置
+    let folder = match folder {
+        Some(folder) => folder,
+        None => {
+            read_cli_settings(&dir_path.join("config.toml"))
+                .path
+                .save_path
+        }
+    };
+    if folder.as_os_str().is_empty() || !folder.is_dir() {
+        eprintln!(
+            "Folder not found: \"{}\" (pass a folder or set save_path in config.toml)",
+            folder.display()
+        );
+        return CLI_USAGE;
+    }
+    let threshold = if exact {
+        None
+    } else {
+        Some(threshold.unwrap_or_else(|| {
+            read_cli_settings(&dir_path.join("config.toml"))
+                .dedupe
+                .threshold
+        }))
+    };
+
+    println!("Scanning {} ...", folder.display());
+    let (groups, total) = scan_duplicates(&folder, recursive, threshold);
+    for (index, group) in groups.iter().enumerate() {
+        let reclaimable: u64 = group
+            .files
+            .iter()
+            .skip(1)
+            .filter_map(|f| fs::metadata(f).ok())
+            .map(|m| m.len())
+            .sum();
+        println!(
+            "\nGroup {} ({}, {} files, {:.1} MB reclaimable):",
+            index + 1,
+            if group.exact { "identical" } else { "similar" },
+            group.files.len(),
+            reclaimable as f64 / 1024.0 / 1024.0
+        );
+        for file in &group.files {
+            println!("    {}", file.display());
+        }
+    }
+    println!(
+        "\n{} duplicate group(s) in {} image(s)",
+        groups.len(),
+        total
+    );
+    CLI_OK
+}
+
+/// 处理 upload 子命令
+///
+/// ### 参数
+/// - `dir_path`: 程序根目录（配置文件所在目录）
+/// - `args`: upload 之后的参数
+///
+/// ### 返回值
+/// - `i32`: 进程退出码
+fn cmd_upload(dir_path: &Path, args: &[String]) -> i32 {
+    let mut file: Option<PathBuf> = None;
+    let mut url: Option<String> = None;
+
+    let mut iter = args.iter();
+    while let Some(arg) = iter.next() {
+        match arg.as_str() {
+            "help" | "--help" | "-h" => {
+                println!("{}", UPLOAD_USAGE);
+                return CLI_OK;
+            }
+            "--url" => match iter.next() {
+                Some(value) => url = Some(value.clone()),
+                None => {
+                    eprintln!("Missing value for --url\n\n{}", UPLOAD_USAGE);
+                    return CLI_USAGE;
+                }
+            },
+            other if other.starts_with("--") || file.is_some() => {
+                eprintln!("Unknown option: {}\n\n{}", other, UPLOAD_USAGE);
+                return CLI_USAGE;
+            }
+            other => file = Some(PathBuf::from(other)),
+        }
+    }
+    let Some(file) = file.filter(|f| f.is_file()) else {
+        eprintln!("File not found\n\n{}", UPLOAD_USAGE);
+        return CLI_USAGE;
+    };
+
+    let mut config = read_cli_settings(&dir_path.join("config.toml")).upload;
+    if let Some(url) = url {
+        config.url = url;
+    }
+    if config.url.is_empty() {
+        eprintln!("No upload url configured\n\n{}", UPLOAD_USAGE);
+        return CLI_USAGE;
+    }
+
+    println!("{} {} ...", config.method, config.url);
+    let result = upload_file(&config, &file);
+    match result.url {
+        Some(url) => {
+            println!("{}", url);
+            CLI_OK
+        }
+        None => {
+            eprintln!(
+                "Upload failed after {} attempt(s): {}",
+                result.attempts,
+                result.error.unwrap_or_default()
+            );
+            CLI_IO
+        }
+    }
+}
+
+/// 处理 meta 子命令
+///
+/// ### 参数
+/// - `_dir_path`: 程序根目录（未使用）
+/// - `args`: meta 之后的参数
+///
+/// ### 返回值
+/// - `i32`: 进程退出码，文件中没有任何元数据时返回 `CLI_IO`
+fn cmd_meta(_dir_path: &Path, args: &[String]) -> i32 {
+    let mut file: Option<PathBuf> = None;
+    let mut json_only = false;
+    for arg in args {
+        match arg.as_str() {
+            "help" | "--help" | "-h" => {
+                println!("{}", META_USAGE);
+                return CLI_OK;
+            }
+            "--json" => json_only = true,
+            other if other.starts_with("--") || file.is_some() => {
+                eprintln!("Unknown option: {}\n\n{}", other, META_USAGE);
+                return CLI_USAGE;
+            }
+            other => file = Some(PathBuf::from(other)),
+        }
+    }
+    let Some(file) = file.filter(|f| f.is_file()) else {
+        eprintln!("File not found\n\n{}", META_USAGE);
+        return CLI_USAGE;
+    };
+
+    let StoredMetadata { texts, sidecar } = match read_capture_metadata(&file) {
+        Ok(result) => result,
+        Err(e) => {
+            eprintln!("Failed to read {}: {}", file.display(), e);
+            return CLI_IO;
+        }
+    };
+
+    if json_only {
+        // 优先使用文件内嵌的元数据，其次为附属文件
+        let embedded = texts
+            .iter()
+            .find(|(key, _)| key == METADATA_KEYWORD)
+            .and_then(|(_, text)| serde_json::from_str::<serde_json::Value>(text).ok());
+        return match embedded.or(sidecar) {
+            Some(value) => {
+                println!(
+                    "{}",
+                    serde_json::to_string_pretty(&value).unwrap_or_default()
+                );
+                CLI_OK
+            }
+            None => {
+                eprintln!("No SC_Starter metadata in {}", file.display());
+                CLI_IO
+            }
+        };
+    }
+
+    if texts.is_empty() && sidecar.is_none() {
+        eprintln!("No metadata in {}", file.display());
+        return CLI_IO;
+    }
+    for (key, text) in &texts {
+        println!("[{}]", key);
+        match serde_json::from_str::<serde_json::Value>(text) {
+            Ok(value) if value.is_object() => {
+                println!(
+                    "{}",
+                    serde_json::to_string_pretty(&value).unwrap_or_default()
+                )
+            }
+            _ => println!("{}", text),
+        }
+    }
+    if let Some(sidecar) = sidecar {
+        println!("[{}]", sidecar_path(&file).display());
+        println!(
+            "{}",
+            serde_json::to_string_pretty(&sidecar).unwrap_or_default()
+        );
+    }
+    CLI_OK
+}
+
+/// 处理 diff-config 子命令
+///
+/// ### 参数
+/// - `dir_path`: 程序根目录（默认配置文件所在目录）
+/// - `args`: diff-config 之后的参数
+///
+/// ### 返回值
+/// - `i32`: 进程退出码，配置文件无法解析时返回 `CLI_IO`
+///
+/// ### 说明
+/// - 与嵌入程序的示例配置比较，而不是数据目录中可能过时的 config_example.toml
+fn cmd_diff_config(dir_path: &Path, args: &[String]) -> i32 {
+    let mut file: Option<PathBuf> = None;
+    for arg in args {
+        match arg.as_str() {
+            "help" | "--help" | "-h" => {
+                println!("{}", DIFF_CONFIG_USAGE);
+                return CLI_OK;
+            }
+            other if other.starts_with("--") || file.is_some() => {
+                eprintln!("Unknown option: {}\n\n{}", other, DIFF_CONFIG_USAGE);
+                return CLI_USAGE;
+            }
+            other => file = Some(PathBuf::from(other)),
+        }
+    }
+    let file = file.unwrap_or_else(|| dir_path.join("config.toml"));
+    if !file.is_file() {
+        eprintln!(
+            "File not found: {}\n\n{}",
+            file.display(),
+            DIFF_CONFIG_USAGE
+        );
+        return CLI_USAGE;
+    }
+
+    let example_text = String::from_utf8_lossy(RES_CONF);
+    let parse_example = |text: &str| {
+        toml::from_str::<toml::Table>(text)
+            .map_err(|e| eprintln!("Failed to parse the bundled config example: {}", e))
+    };
+    let (Ok(example), Ok(full_example)) = (
+        parse_example(&example_text),
+        parse_example(&uncomment_example(&example_text)),
+    ) else {
+        return CLI_IO;
+    };
+    let config = match parse_config_file(&file) {
+        Ok(config) => config,
+        Err(e) => {
+            eprintln!("{}", e);
+            return CLI_IO;
+        }
+    };
+    let empty = toml::Table::new();
+    let config = config.as_table().unwrap_or(&empty);
+    let missing = missing_keys(&example, config);
+    // 示例中默认注释掉的键只作为可选项列出
+    let optional: Vec<String> = missing_keys(&full_example, config)
+        .into_iter()
+        .filter(|key| !missing.contains(key))
+        .collect();
+
+    if missing.is_empty() {
+        println!("{} has every key of the config example.", file.display());
+    } else {
+        println!(
+            "{} key(s) of the config example are missing from {}:",
+            missing.len(),
+            file.display()
+        );
+        for key in &missing {
+            println!("    {}", key);
+        }
+    }
+    if !optional.is_empty() {
+        println!(
+            "\n{} optional key(s) are commented out in the config example and not set:",
+            optional.len()
+        );
+        for key in &optional {
+            println!("    {}", key);
+        }
+    }
+    if !missing.is_empty() || !optional.is_empty() {
+        println!(
+            "\nDefault values are used for them, see {}",
+            dir_path.join("config_example.toml").display()
+        );
+    }
+    CLI_OK
+}
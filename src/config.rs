@@ -7,9 +7,13 @@
 //! - 转换配置格式
 
 use crate::types::*;
-use std::{collections::HashMap, fs, path::PathBuf};
+use std::{
+    collections::HashMap,
+    fs,
+    path::{Path, PathBuf},
+};
 use toml::Value;
-use win_msgbox_timeout::error_msgbox;
+use win_msgbox_timeout::{error_msgbox, warn_msgbox};
 
 /// 读取并解析TOML配置文件
 ///
@@ -29,16 +33,7 @@ pub fn read_config(conf_path: &PathBuf) -> SettingsCollection {
 
     // 尝试读取TOML配置文件
     let config_content = match fs::read_to_string(conf_path) {
-        Ok(content) => content
-            .replace("“", "\"")
-            .replace("”", "\"")
-            .replace("‘", "'")
-            .replace("’", "'")
-            .replace("，", ",")
-            .replace("。", ".")
-            .replace("｜", "|")
-            .replace("：", ":")
-            .replace("—", "-"),
+        Ok(content) => normalize_punctuation(&content),
         Err(e) => {
             eprintln!("Failed to read config file: {}", e);
             // 返回默认配置
@@ -57,12 +52,230 @@ pub fn read_config(conf_path: &PathBuf) -> SettingsCollection {
         }
     };
 
+    let sundry = get_sundry_settings(default_settings.sundry, &config);
+    // 通知模板的默认值跟随语言设置
+    let notify = get_notify_from_config(NotifyTemplates::localized(sundry.lang), &config);
+
     // 返回最终配置集合
     SettingsCollection {
         keys_collection: get_kvs_from_config(default_settings.keys_collection, &config),
         path: get_path_from_config(default_settings.path, &config),
-        sundry: get_sundry_settings(default_settings.sundry, &config),
+        sundry,
         gui: get_gui_config(default_settings.gui, &config),
+        notify,
+        hooks: get_hooks_from_config(&config),
+        history: get_history_from_config(default_settings.history, &config),
+        retention: get_retention_from_config(default_settings.retention, &config),
+        dedupe: get_dedupe_from_config(default_settings.dedupe, &config),
+        upload: get_upload_from_config(default_settings.upload, &config),
+        copy_as: get_copy_as_from_config(default_settings.copy_as, &config),
+        convert: get_profiled_from_config(
+            default_settings.convert.base,
+            &config,
+            "convert",
+            parse_convert_section,
+        ),
+        watermark: get_profiled_from_config(
+            default_settings.watermark.base,
+            &config,
+            "watermark",
+            parse_watermark_section,
+        ),
+        metadata: get_metadata_from_config(default_settings.metadata, &config),
+        resource: get_resource_from_config(default_settings.resource, &config),
+        capture: get_capture_from_config(default_settings.capture, &config),
+    }
+}
+
+/// 将配置文件中误输入的全角标点替换为半角
+fn normalize_punctuation(content: &str) -> String {
+    content
+        .replace("“", "\"")
+        .replace("”", "\"")
+        .replace("‘", "'")
+        .replace("’", "'")
+        .replace("，", ",")
+        .replace("。", ".")
+        .replace("｜", "|")
+        .replace("：", ":")
+        .replace("—", "-")
+}
+
+/// 在释放资源前单独读取资源文件配置
+///
+/// ### 参数
+/// - `conf_path`: 配置文件路径
+///
+/// ### 返回值
+/// - `ResourceConfig`: 配置文件不存在或无法解析时为默认值
+///
+/// ### 说明
+/// - 释放 exe 早于读取完整配置，此处不弹出错误提示，解析错误由 `read_config` 报告
+pub fn read_resource_config(conf_path: &Path) -> ResourceConfig {
+    read_config_quiet(conf_path)
+        .map(|config| get_resource_from_config(ResourceConfig::default(), &config))
+        .unwrap_or_default()
+}
+
+/// 在释放资源前单独读取外部 ScreenCapture 路径（`[capture] engine_path`）
+///
+/// ### 参数
+/// - `conf_path`: 配置文件路径
+///
+/// ### 返回值
+/// - `Option<PathBuf>`: 未设置或为空时返回 `None`，表示使用内置版本
+pub fn read_engine_path(conf_path: &Path) -> Option<PathBuf> {
+    read_config_quiet(conf_path)?
+        .get("capture")
+        .and_then(|v| v.get("engine_path"))
+        .and_then(|v| v.as_str())
+        .map(|s| s.trim())
+        .filter(|s| !s.is_empty())
+        .and_then(expand_path)
+}
+
+/// 读取并解析配置文件，失败时不提示
+pub fn read_config_quiet(conf_path: &Path) -> Option<Value> {
+    parse_config_file(conf_path).ok()
+}
+
+/// 读取并解析配置文件
+///
+/// ### 返回值
+/// - `Result<Value, String>`: 读取或解析失败时返回错误说明，不弹出提示
+pub fn parse_config_file(conf_path: &Path) -> Result<Value, String> {
+    let content = fs::read_to_string(conf_path)
+        .map_err(|e| format!("Failed to read {}: {}", conf_path.display(), e))?;
+    toml::from_str(&normalize_punctuation(&content))
+        .map_err(|e| format!("Failed to parse {}: {}", conf_path.display(), e))
+}
+
+/// 供命令行子命令读取的配置，不弹出任何提示
+#[derive(Clone, Debug, Default)]
+pub struct CliSettings {
+    /// 路径配置（`save_path` 为按顺序找到的第一个已存在的目录）
+    pub path: PathConfig,
+    /// 重复截图检测配置
+    pub dedupe: DedupeConfig,
+    /// 上传配置（未检查是否启用）
+    pub upload: UploadConfig,
+}
+
+/// 为命令行子命令读取配置
+///
+/// ### 参数
+/// - `conf_path`: 配置文件路径
+///
+/// ### 返回值
+/// - `CliSettings`: 配置文件缺失或无法解析时为默认值
+///
+/// ### 说明
+/// - 命令行中不能弹出消息框，所有问题都输出到标准错误
+/// - 不创建保存目录、不写入任何文件，只读的子命令（如 dedupe）不会修改磁盘
+pub fn read_cli_settings(conf_path: &Path) -> CliSettings {
+    let defaults = CliSettings::default();
+    let config = match parse_config_file(conf_path) {
+        Ok(config) => config,
+        Err(e) => {
+            eprintln!("{}, using defaults.", e);
+            return defaults;
+        }
+    };
+    let mut path = parse_path_section(defaults.path, &config);
+    // 只读查找，不创建目录也不写入探测文件
+    path.save_path = find_save_dir(&path).unwrap_or_else(|e| {
+        eprintln!("No save path is available:\n{}", e);
+        PathBuf::new()
+    });
+    CliSettings {
+        path,
+        dedupe: get_dedupe_from_config(defaults.dedupe, &config),
+        upload: parse_upload_section(defaults.upload, &config),
+    }
+}
+
+/// 列出示例配置中存在、而用户配置中缺少的键
+///
+/// ### 参数
+/// - `example`: 示例配置
+/// - `config`: 用户配置
+///
+/// ### 返回值
+/// - `Vec<String>`: 缺少的键，以 `段.键` 表示；整段缺少时为 `[段]`
+///
+/// ### 说明
+/// - 数组（包括表数组）视为单个值，不比较其中的元素
+pub fn missing_keys(example: &toml::Table, config: &toml::Table) -> Vec<String> {
+    let mut missing = Vec::new();
+    collect_missing_keys(example, config, "", &mut missing);
+    missing
+}
+
+/// 取消示例配置中示例键的注释
+///
+/// ### 参数
+/// - `example`: 示例配置文本
+///
+/// ### 返回值
+/// - `String`: 示例键与示例段已取消注释的配置文本
+///
+/// ### 说明
+/// - 只处理形如 `# 键 = 值`、`# [段]`、`# [[段]]` 的注释行，其余说明保持不变
+/// - 用于 `diff-config` 列出默认注释掉的可选键（通知模板、钩子、请求头等）
+pub fn uncomment_example(example: &str) -> String {
+    let is_name = |name: &str| {
+        !name.is_empty()
+            && name
+                .chars()
+                .all(|c| c.is_ascii_alphanumeric() || matches!(c, '_' | '-' | '.'))
+    };
+    example
+        .lines()
+        .map(|line| {
+            let Some(rest) = line.trim_start().strip_prefix('#').map(str::trim_start) else {
+                return line;
+            };
+            let header = rest
+                .strip_prefix("[[")
+                .and_then(|r| r.trim_end().strip_suffix("]]"))
+                .or_else(|| {
+                    rest.strip_prefix('[')
+                        .and_then(|r| r.trim_end().strip_suffix(']'))
+                });
+            let entry = rest
+                .split_once('=')
+                .filter(|(key, value)| is_name(key.trim()) && !value.trim().is_empty());
+            if header.is_some_and(is_name) || entry.is_some() {
+                rest
+            } else {
+                line
+            }
+        })
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+/// 递归比较两个表，将缺少的键追加到 `missing`
+fn collect_missing_keys(
+    example: &toml::Table,
+    config: &toml::Table,
+    prefix: &str,
+    missing: &mut Vec<String>,
+) {
+    for (key, value) in example {
+        let path = if prefix.is_empty() {
+            key.clone()
+        } else {
+            format!("{}.{}", prefix, key)
+        };
+        match (value, config.get(key)) {
+            (Value::Table(_), None) => missing.push(format!("[{}]", path)),
+            (_, None) => missing.push(path),
+            (Value::Table(table), Some(Value::Table(sub))) => {
+                collect_missing_keys(table, sub, &path, missing)
+            }
+            _ => (),
+        }
     }
 }
 
@@ -167,11 +380,35 @@ fn get_kvs_from_config(
 /// - `PathConfig`: 解析后的路径配置结构
 ///
 /// ### 功能
-/// - 从配置文件path段读取dir、launch_app_path和launch_app_args设置
+/// - 从配置文件path段读取dir、fallback_dirs、create_dir、mirrors、launch_app_path、launch_app_args、filename_template和subfolder设置
 /// - 处理路径字符串规范化和特殊符号解析
+/// - 按顺序选择第一个可写的保存目录，全部不可用时提示并改为手动选择
 /// - 解析启动应用程序的参数（使用Tab分隔）
 /// - 如果配置缺失则使用默认值
 fn get_path_from_config(default: PathConfig, config: &Value) -> PathConfig {
+    let mut path_config = parse_path_section(default, config);
+    path_config.launch_app.path =
+        resolve_path(path_config.launch_app.path.to_string_lossy(), false);
+    // 启动时先选择一次保存目录，截图时还会重新检查
+    path_config.save_path = match select_save_dir(&path_config) {
+        Ok(dir) => dir,
+        Err(e) => {
+            warn_msgbox(
+                format!("{}\nNo save path is available, use EMPTY for now.", e),
+                "Warn Path Invalid",
+                5,
+            );
+            PathBuf::new()
+        }
+    };
+    path_config
+}
+
+/// 解析配置文件path段，不检查路径、不弹出提示
+///
+/// ### 说明
+/// - `save_path` 为空，`launch_app.path` 为未解析的路径字符串
+fn parse_path_section(default: PathConfig, config: &Value) -> PathConfig {
     let path_section = config.get("path").and_then(|v| v.as_table());
 
     let unchecked = match path_section {
@@ -179,7 +416,7 @@ fn get_path_from_config(default: PathConfig, config: &Value) -> PathConfig {
             let str_save_path = if let Some(dir) = section.get("dir").and_then(|v| v.as_str()) {
                 handle_str_path(dir)
             } else {
-                default.save_path.to_string_lossy().to_string()
+                default.save_path_str.clone()
             };
             let str_launch_path =
                 if let Some(launch) = section.get("launch_app_path").and_then(|v| v.as_str()) {
@@ -197,18 +434,70 @@ fn get_path_from_config(default: PathConfig, config: &Value) -> PathConfig {
             (str_save_path, str_launch_path, str_launch_args)
         }
         None => (
-            default.save_path.to_string_lossy().to_string(),
+            default.save_path_str.clone(),
             default.launch_app.path.to_string_lossy().to_string(),
             default.launch_app.args,
         ),
     };
 
+    let filename_template = path_section
+        .and_then(|t| t.get("filename_template"))
+        .and_then(|v| v.as_str())
+        .map(|s| s.trim().to_string())
+        .unwrap_or(default.filename_template);
+
+    let subfolder = path_section
+        .and_then(|t| t.get("subfolder"))
+        .and_then(|v| v.as_str())
+        .map(handle_str_path)
+        .unwrap_or(default.subfolder);
+
+    let subfolder_fallback = match path_section
+        .and_then(|t| t.get("subfolder_fallback"))
+        .and_then(|v| v.as_str())
+    {
+        Some(name) => SubfolderFallback::from_name(name).unwrap_or_else(|| {
+            eprintln!("Invalid subfolder_fallback: {}, use default.", name);
+            default.subfolder_fallback
+        }),
+        None => default.subfolder_fallback,
+    };
+
+    // 备用目录与镜像目录常为网络共享，不能用 handle_str_path 去除开头的 `//`
+    let dir_list = |key: &str| -> Option<Vec<String>> {
+        path_section
+            .and_then(|t| t.get(key))
+            .and_then(|v| v.as_array())
+            .map(|items| {
+                items
+                    .iter()
+                    .filter_map(|v| v.as_str())
+                    .map(|s| s.trim().trim_matches(['"', '\'']).to_string())
+                    .filter(|s| !s.is_empty())
+                    .collect()
+            })
+    };
+    let fallback_dirs = dir_list("fallback_dirs").unwrap_or(default.fallback_dirs);
+    let mirrors = dir_list("mirrors").unwrap_or(default.mirrors);
+
+    let create_dir = path_section
+        .and_then(|t| t.get("create_dir"))
+        .and_then(|v| v.as_bool())
+        .unwrap_or(default.create_dir);
+
     PathConfig {
-        save_path: resolve_path(&unchecked.0, true),
+        save_path: PathBuf::new(),
+        save_path_str: unchecked.0,
+        fallback_dirs,
+        create_dir,
+        mirrors,
         launch_app: LaunchAppConfig {
-            path: resolve_path(&unchecked.1, false),
+            path: PathBuf::from(unchecked.1),
             args: unchecked.2,
         },
+        filename_template,
+        subfolder,
+        subfolder_fallback,
     }
 }
 
@@ -222,7 +511,7 @@ fn get_path_from_config(default: PathConfig, config: &Value) -> PathConfig {
 /// - `Sundry`: 包含自启动、压缩级别和缩放级别的配置结构
 ///
 /// ### 功能
-/// - 从配置文件sundry段读取startup、comp_level和scale_ratio设置
+/// - 从配置文件sundry段读取startup、comp_level、scale_ratio、lang、notification和profile设置
 /// - 验证压缩级别范围（-1到10）
 /// - 验证缩放比例范围（1到100）
 /// - 对超出范围的值使用默认配置
@@ -268,12 +557,20 @@ fn get_sundry_settings(default: Sundry, config: &Value) -> Sundry {
         .and_then(|v| v.as_bool())
         .unwrap_or(default.notification);
 
+    let profile_name = sundry_section
+        .and_then(|t| t.get("profile"))
+        .and_then(|v| v.as_str())
+        .map(|s| s.trim().to_string())
+        .filter(|s| !s.is_empty())
+        .unwrap_or(default.profile);
+
     Sundry {
         auto_start: startup_bool,
         comp_level: comp,
         scale_level: scale,
         lang: lang_code == 1,
         notification: notification_bool,
+        profile: profile_name,
     }
 }
 
@@ -318,6 +615,452 @@ fn get_gui_config(default: HashMap<String, String>, config: &Value) -> HashMap<S
     temp
 }
 
+/// 从配置中提取通知模板
+///
+/// ### 参数
+/// - `default`: 按语言生成的默认通知模板
+/// - `config`: TOML配置值引用
+///
+/// ### 返回值
+/// - `NotifyTemplates`: 解析后的通知模板
+///
+/// ### 功能
+/// - 从配置文件notification段读取saved、clipboard、pinned、cancelled、crashed、unknown模板
+/// - 未配置的结果沿用默认模板，配置为空字符串表示不通知该结果
+/// - 忽略无法识别的键并输出提示
+fn get_notify_from_config(default: NotifyTemplates, config: &Value) -> NotifyTemplates {
+    let mut templates = default;
+    let notify_section = match config.get("notification").and_then(|v| v.as_table()) {
+        Some(table) => table,
+        None => return templates,
+    };
+
+    for (key, value) in notify_section {
+        match (templates.get_mut(key), value.as_str()) {
+            (Some(slot), Some(text)) => *slot = text.to_string(),
+            _ => eprintln!("Ignored notification template: {}", key),
+        }
+    }
+    templates
+}
+
+/// 从配置中提取截图后钩子
+///
+/// ### 参数
+/// - `config`: TOML配置值引用
+///
+/// ### 返回值
+/// - `Vec<HookConfig>`: 按配置顺序排列的钩子列表
+///
+/// ### 功能
+/// - 从配置文件hooks段读取post_capture数组
+/// - args 支持字符串数组，或与 launch_app_args 相同的 Tab 分隔字符串
+/// - 缺少 command 的条目会被跳过并提示
+fn get_hooks_from_config(config: &Value) -> Vec<HookConfig> {
+    let entries = match config
+        .get("hooks")
+        .and_then(|v| v.get("post_capture"))
+        .and_then(|v| v.as_array())
+    {
+        Some(entries) => entries,
+        None => return Vec::new(),
+    };
+
+    let mut hooks = Vec::new();
+    for (index, entry) in entries.iter().enumerate() {
+        let default = HookConfig::default();
+        let command = match entry.get("command").and_then(|v| v.as_str()) {
+            Some(cmd) if !cmd.trim().is_empty() => handle_str_path(cmd),
+            _ => {
+                let error_message = format!("Hook #{} has no command, skipped.", index + 1);
+                eprintln!("{}", error_message);
+                error_msgbox(error_message, "Configuration Error", 5);
+                continue;
+            }
+        };
+        let args = match entry.get("args") {
+            Some(Value::Array(items)) => items
+                .iter()
+                .filter_map(|v| v.as_str().map(String::from))
+                .collect(),
+            Some(Value::String(line)) if !line.trim().is_empty() => {
+                line.split("\t").map(String::from).collect()
+            }
+            _ => default.args,
+        };
+        let cwd = entry
+            .get("cwd")
+            .and_then(|v| v.as_str())
+            .map(handle_str_path)
+            .unwrap_or(default.cwd);
+        let timeout = entry
+            .get("timeout")
+            .and_then(|v| v.as_integer())
+            .filter(|secs| *secs > 0)
+            .map(|secs| std::time::Duration::from_secs(secs as u64))
+            .unwrap_or(default.timeout);
+        let exit_codes = entry
+            .get("on_exit_codes")
+            .and_then(|v| v.as_array())
+            .map(|codes| {
+                codes
+                    .iter()
+                    .filter_map(|v| v.as_integer().map(|c| c as i32))
+                    .collect()
+            })
+            .unwrap_or(default.exit_codes);
+
+        hooks.push(HookConfig {
+            command,
+            args,
+            cwd,
+            timeout,
+            exit_codes,
+        });
+    }
+    hooks
+}
+
+/// 从配置中提取历史记录设置
+///
+/// ### 参数
+/// - `default`: 默认历史记录配置
+/// - `config`: TOML配置值引用
+///
+/// ### 返回值
+/// - `HistoryConfig`: 解析后的历史记录配置
+///
+/// ### 功能
+/// - 从配置文件history段读取enabled、max_entries、retention_days和recent_count设置
+/// - 对超出范围的值使用默认配置
+fn get_history_from_config(default: HistoryConfig, config: &Value) -> HistoryConfig {
+    let history_section = config.get("history").and_then(|v| v.as_table());
+    let integer = |key: &str| {
+        history_section
+            .and_then(|t| t.get(key))
+            .and_then(|v| v.as_integer())
+    };
+
+    HistoryConfig {
+        enabled: history_section
+            .and_then(|t| t.get("enabled"))
+            .and_then(|v| v.as_bool())
+            .unwrap_or(default.enabled),
+        max_entries: integer("max_entries")
+            .filter(|num| *num > 0)
+            .map(|num| num as usize)
+            .unwrap_or(default.max_entries),
+        retention_days: integer("retention_days")
+            .filter(|num| *num >= 0)
+            .map(|num| num as u32)
+            .unwrap_or(default.retention_days),
+        recent_count: integer("recent_count")
+            .filter(|num| (0..=30).contains(num))
+            .map(|num| num as usize)
+            .unwrap_or(default.recent_count),
+    }
+}
+
+/// 从配置中提取截图目录保留策略
+///
+/// ### 参数
+/// - `default`: 默认保留策略
+/// - `config`: TOML配置值引用
+///
+/// ### 返回值
+/// - `RetentionConfig`: 解析后的保留策略
+///
+/// ### 功能
+/// - 从配置文件retention段读取各项限制、排除规则、清理方式与定时间隔
+/// - 负数限制视为无效并使用默认值
+/// - 无法识别的清理方式使用默认值并提示
+fn get_retention_from_config(default: RetentionConfig, config: &Value) -> RetentionConfig {
+    let section = match config.get("retention").and_then(|v| v.as_table()) {
+        Some(table) => table,
+        None => return default,
+    };
+    let integer = |key: &str| {
+        section
+            .get(key)
+            .and_then(|v| v.as_integer())
+            .filter(|num| *num >= 0)
+    };
+    let boolean = |key: &str| section.get(key).and_then(|v| v.as_bool());
+
+    let action = match section.get("action").and_then(|v| v.as_str()) {
+        Some(name) => match name.trim().to_ascii_lowercase().as_str() {
+            "trash" => RetentionAction::Trash,
+            "delete" => RetentionAction::Delete,
+            _ => {
+                eprintln!("Invalid retention action: {}, use default.", name);
+                default.action
+            }
+        },
+        None => default.action,
+    };
+    let exclude = section
+        .get("exclude")
+        .and_then(|v| v.as_array())
+        .map(|items| {
+            items
+                .iter()
+                .filter_map(|v| v.as_str().map(|s| s.replace('\\', "/")))
+                .collect()
+        })
+        .unwrap_or(default.exclude);
+
+    RetentionConfig {
+        enabled: boolean("enabled").unwrap_or(default.enabled),
+        max_age_days: integer("max_age_days")
+            .map(|num| num as u32)
+            .unwrap_or(default.max_age_days),
+        max_total_mb: integer("max_total_mb")
+            .map(|num| num as u64)
+            .unwrap_or(default.max_total_mb),
+        max_files: integer("max_files")
+            .map(|num| num as usize)
+            .unwrap_or(default.max_files),
+        exclude,
+        action,
+        interval_hours: integer("interval_hours")
+            .map(|num| num as u64)
+            .unwrap_or(default.interval_hours),
+        dry_run: boolean("dry_run").unwrap_or(default.dry_run),
+    }
+}
+
+/// 从配置中提取重复截图检测设置
+///
+/// ### 参数
+/// - `default`: 默认检测配置
+/// - `config`: TOML配置值引用
+///
+/// ### 返回值
+/// - `DedupeConfig`: 解析后的检测配置
+///
+/// ### 功能
+/// - 从配置文件dedupe段读取enabled、action、perceptual、threshold和window设置
+/// - threshold 超出 0-64 或无法识别的处理方式使用默认值并提示
+fn get_dedupe_from_config(default: DedupeConfig, config: &Value) -> DedupeConfig {
+    let section = match config.get("dedupe").and_then(|v| v.as_table()) {
+        Some(table) => table,
+        None => return default,
+    };
+    let boolean = |key: &str| section.get(key).and_then(|v| v.as_bool());
+
+    let action = match section.get("action").and_then(|v| v.as_str()) {
+        Some(name) => match name.trim().to_ascii_lowercase().as_str() {
+            "warn" => DedupeAction::Warn,
+            "delete" => DedupeAction::Delete,
+            "link" => DedupeAction::Link,
+            _ => {
+                eprintln!("Invalid dedupe action: {}, use default.", name);
+                default.action
+            }
+        },
+        None => default.action,
+    };
+    let threshold = match section.get("threshold").and_then(|v| v.as_integer()) {
+        Some(num) if (0..=64).contains(&num) => num as u32,
+        Some(num) => {
+            eprintln!("Invalid dedupe threshold: {}, use default.", num);
+            default.threshold
+        }
+        None => default.threshold,
+    };
+
+    DedupeConfig {
+        enabled: boolean("enabled").unwrap_or(default.enabled),
+        action,
+        perceptual: boolean("perceptual").unwrap_or(default.perceptual),
+        threshold,
+        window: section
+            .get("window")
+            .and_then(|v| v.as_integer())
+            .filter(|num| *num > 0)
+            .map(|num| num as usize)
+            .unwrap_or(default.window),
+    }
+}
+
+/// 从配置中提取 HTTP 上传设置
+///
+/// ### 参数
+/// - `default`: 默认上传配置
+/// - `config`: TOML配置值引用
+///
+/// ### 返回值
+/// - `UploadConfig`: 解析后的上传配置
+///
+/// ### 功能
+/// - 从配置文件upload段读取地址、请求格式、请求头、令牌、链接字段与重试设置
+/// - 启用上传但未填写 url 时自动禁用并提示
+fn get_upload_from_config(default: UploadConfig, config: &Value) -> UploadConfig {
+    let mut upload = parse_upload_section(default, config);
+    if upload.enabled && upload.url.is_empty() {
+        let error_message = "Upload is enabled but no url is set, upload disabled.";
+        eprintln!("{}", error_message);
+        error_msgbox(error_message, "Configuration Error", 5);
+        upload.enabled = false;
+    }
+    upload
+}
+
+/// 解析配置文件upload段，不弹出提示
+fn parse_upload_section(default: UploadConfig, config: &Value) -> UploadConfig {
+    let section = match config.get("upload").and_then(|v| v.as_table()) {
+        Some(table) => table,
+        None => return default,
+    };
+    let text = |key: &str| {
+        section
+            .get(key)
+            .and_then(|v| v.as_str())
+            .map(|s| s.trim().to_string())
+    };
+    let integer = |key: &str| {
+        section
+            .get(key)
+            .and_then(|v| v.as_integer())
+            .filter(|num| *num >= 0)
+    };
+
+    let method = match text("method").map(|m| m.to_ascii_uppercase()) {
+        Some(m) if m == "POST" || m == "PUT" => m,
+        Some(m) => {
+            eprintln!("Invalid upload method: {}, use default.", m);
+            default.method
+        }
+        None => default.method,
+    };
+    let body = match text("body").map(|b| b.to_ascii_lowercase()) {
+        Some(b) if b == "multipart" => UploadBody::Multipart,
+        Some(b) if b == "raw" => UploadBody::Raw,
+        Some(b) => {
+            eprintln!("Invalid upload body: {}, use default.", b);
+            default.body
+        }
+        None => default.body,
+    };
+    let headers = section
+        .get("headers")
+        .and_then(|v| v.as_table())
+        .map(|table| {
+            table
+                .iter()
+                .filter_map(|(k, v)| v.as_str().map(|v| (k.clone(), v.to_string())))
+                .collect()
+        })
+        .unwrap_or(default.headers);
+
+    UploadConfig {
+        enabled: section
+            .get("enabled")
+            .and_then(|v| v.as_bool())
+            .unwrap_or(default.enabled),
+        url: text("url").unwrap_or(default.url),
+        method,
+        body,
+        field: text("field")
+            .filter(|f| !f.is_empty())
+            .unwrap_or(default.field),
+        headers,
+        token: text("token").unwrap_or(default.token),
+        token_header: text("token_header")
+            .filter(|h| !h.is_empty())
+            .unwrap_or(default.token_header),
+        // 前缀通常以空格结尾，不能去除空白
+        token_prefix: section
+            .get("token_prefix")
+            .and_then(|v| v.as_str())
+            .map(String::from)
+            .unwrap_or(default.token_prefix),
+        url_field: text("url_field").unwrap_or(default.url_field),
+        copy_url: section
+            .get("copy_url")
+            .and_then(|v| v.as_bool())
+            .unwrap_or(default.copy_url),
+        retries: integer("retries")
+            .map(|num| num.min(10) as u32)
+            .unwrap_or(default.retries),
+        backoff: integer("backoff_ms")
+            .map(|ms| std::time::Duration::from_millis(ms as u64))
+            .unwrap_or(default.backoff),
+        timeout: integer("timeout")
+            .filter(|secs| *secs > 0)
+            .map(|secs| std::time::Duration::from_secs(secs as u64))
+            .unwrap_or(default.timeout),
+    }
+}
+
+/// 从配置中提取复制为文本片段的设置
+///
+/// ### 参数
+/// - `default`: 默认配置（含内置模板）
+/// - `config`: TOML配置值引用
+///
+/// ### 返回值
+/// - `CopyAsConfig`: 解析后的配置
+///
+/// ### 功能
+/// - 从配置文件copy_as段读取docs_root、format和auto设置
+/// - copy_as.templates 中的同名模板覆盖内置模板，新名称追加到末尾，空模板表示移除
+/// - format 不存在时回退到第一个模板
+fn get_copy_as_from_config(default: CopyAsConfig, config: &Value) -> CopyAsConfig {
+    let section = match config.get("copy_as").and_then(|v| v.as_table()) {
+        Some(table) => table,
+        None => return default,
+    };
+
+    let mut templates = default.templates;
+    if let Some(table) = section.get("templates").and_then(|v| v.as_table()) {
+        for (name, value) in table {
+            let Some(template) = value.as_str() else {
+                eprintln!("Ignored copy_as template: {}", name);
+                continue;
+            };
+            match templates
+                .iter_mut()
+                .find(|(key, _)| key.eq_ignore_ascii_case(name))
+            {
+                Some(entry) => entry.1 = template.to_string(),
+                None => templates.push((name.clone(), template.to_string())),
+            }
+        }
+        templates.retain(|(_, template)| !template.is_empty());
+    }
+
+    let mut copy_as = CopyAsConfig {
+        docs_root: section
+            .get("docs_root")
+            .and_then(|v| v.as_str())
+            .map(handle_str_path)
+            .unwrap_or(default.docs_root),
+        default_format: section
+            .get("format")
+            .and_then(|v| v.as_str())
+            .map(|s| s.trim().to_string())
+            .unwrap_or(default.default_format),
+        auto: section
+            .get("auto")
+            .and_then(|v| v.as_bool())
+            .unwrap_or(default.auto),
+        templates,
+    };
+    if copy_as.template(&copy_as.default_format).is_none() {
+        eprintln!(
+            "Unknown copy_as format: {}, use the first template.",
+            copy_as.default_format
+        );
+        copy_as.default_format = copy_as
+            .templates
+            .first()
+            .map(|(name, _)| name.clone())
+            .unwrap_or_default();
+    }
+    copy_as
+}
+
 /// 管理系统启动时的快捷方式
 ///
 /// ### 参数
@@ -370,3 +1113,645 @@ pub fn set_startup(renew: bool, startup_dir: &std::path::Path, self_path: &PathB
         }
     }
 }
+
+/// 从配置中提取截图元数据设置
+///
+/// ### 参数
+/// - `default`: 默认元数据配置
+/// - `config`: TOML配置值引用
+///
+/// ### 返回值
+/// - `MetadataConfig`: 解析后的元数据配置
+fn get_metadata_from_config(default: MetadataConfig, config: &Value) -> MetadataConfig {
+    let section = match config.get("metadata").and_then(|v| v.as_table()) {
+        Some(table) => table,
+        None => return default,
+    };
+    let boolean = |key: &str| section.get(key).and_then(|v| v.as_bool());
+
+    MetadataConfig {
+        enabled: boolean("enabled").unwrap_or(default.enabled),
+        png_chunks: boolean("png_chunks").unwrap_or(default.png_chunks),
+        sidecar: boolean("sidecar").unwrap_or(default.sidecar),
+    }
+}
+
+/// 从配置中提取截图引擎设置
+///
+/// ### 参数
+/// - `default`: 默认截图引擎配置
+/// - `config`: TOML配置值引用
+///
+/// ### 返回值
+/// - `CaptureConfig`: 解析后的截图引擎配置
+///
+/// ### 功能
+/// - 读取 capture 段的 engine（screencapture / command）
+/// - 读取 capture.command 段中各截图模式的命令数组、cancel_codes、no_output 与 format
+/// - `engine_path` 在释放资源前由 `read_engine_path` 单独读取
+fn get_capture_from_config(default: CaptureConfig, config: &Value) -> CaptureConfig {
+    let section = match config.get("capture").and_then(|v| v.as_table()) {
+        Some(table) => table,
+        None => return default,
+    };
+    let engine = match section.get("engine").and_then(|v| v.as_str()) {
+        Some(value) => match value.trim().to_ascii_lowercase().as_str() {
+            "screencapture" | "" => EngineKind::ScreenCapture,
+            "command" => EngineKind::Command,
+            other => {
+                eprintln!("Unknown capture engine: {}", other);
+                default.engine
+            }
+        },
+        None => default.engine,
+    };
+
+    let mut command = default.command;
+    if let Some(table) = section.get("command").and_then(|v| v.as_table()) {
+        command.commands = CaptureMode::ALL
+            .iter()
+            .filter_map(|&mode| {
+                let args: Vec<String> = table
+                    .get(mode.key())?
+                    .as_array()?
+                    .iter()
+                    .filter_map(|v| v.as_str().map(String::from))
+                    .collect();
+                (!args.is_empty()).then_some((mode, args))
+            })
+            .collect();
+        if let Some(codes) = table.get("cancel_codes").and_then(|v| v.as_array()) {
+            command.cancel_codes = codes
+                .iter()
+                .filter_map(|v| v.as_integer())
+                .map(|c| c as i32)
+                .collect();
+        }
+        if let Some(value) = table.get("no_output").and_then(|v| v.as_str()) {
+            command.no_output = match value.trim().to_ascii_lowercase().as_str() {
+                "clipboard" => CaptureOutcome::CopiedToClipboard,
+                "cancelled" => CaptureOutcome::Cancelled,
+                other => {
+                    eprintln!("Unknown capture.command.no_output: {}", other);
+                    command.no_output
+                }
+            };
+        }
+        if let Some(format) = table
+            .get("format")
+            .and_then(|v| v.as_str())
+            .map(|s| s.trim().trim_start_matches('.').to_ascii_lowercase())
+            .filter(|s| !s.is_empty())
+        {
+            command.format = format;
+        }
+    }
+
+    CaptureConfig { engine, command }
+}
+
+/// 从配置中提取资源文件设置
+///
+/// ### 参数
+/// - `default`: 默认资源文件配置
+/// - `config`: TOML配置值引用
+///
+/// ### 返回值
+/// - `ResourceConfig`: 解析后的资源文件配置，无法识别的策略使用默认值
+fn get_resource_from_config(default: ResourceConfig, config: &Value) -> ResourceConfig {
+    let section = match config.get("resource").and_then(|v| v.as_table()) {
+        Some(table) => table,
+        None => return default,
+    };
+    let policy = match section.get("policy").and_then(|v| v.as_str()) {
+        Some(value) => match value.trim().to_ascii_lowercase().replace('_', "-").as_str() {
+            "embedded" => ResourcePolicy::Embedded,
+            "prefer-newer" => ResourcePolicy::PreferNewer,
+            "pinned-external" => ResourcePolicy::PinnedExternal,
+            other => {
+                eprintln!("Unknown resource policy: {}", other);
+                default.policy
+            }
+        },
+        None => default.policy,
+    };
+
+    let config_recovery = match section.get("config_recovery").and_then(|v| v.as_str()) {
+        Some(value) => match value.trim().to_ascii_lowercase().as_str() {
+            "restore" => ConfigRecovery::Restore,
+            "ask" => ConfigRecovery::Ask,
+            "exit" => ConfigRecovery::Exit,
+            other => {
+                eprintln!("Unknown config recovery: {}", other);
+                default.config_recovery
+            }
+        },
+        None => default.config_recovery,
+    };
+
+    ResourceConfig {
+        policy,
+        backup: section
+            .get("backup")
+            .and_then(|v| v.as_bool())
+            .unwrap_or(default.backup),
+        config_backups: section
+            .get("config_backups")
+            .and_then(|v| v.as_integer())
+            .and_then(|v| usize::try_from(v).ok())
+            .unwrap_or(default.config_backups),
+        config_recovery,
+    }
+}
+
+/// 读取可按截图模式或配置名覆盖的配置段
+///
+/// ### 参数
+/// - `default`: 默认设置
+/// - `config`: TOML配置值引用
+/// - `name`: 段名
+/// - `parse`: 解析单个表的函数，表中缺少的键使用传入的默认值
+///
+/// ### 返回值
+/// - `Profiled<T>`: 基础设置与各子表的覆盖设置
+///
+/// ### 说明
+/// - 子表以基础设置作为默认值解析，因此只需填写需要修改的键
+fn get_profiled_from_config<T: Clone>(
+    default: T,
+    config: &Value,
+    name: &str,
+    parse: impl Fn(T, &toml::Table) -> T,
+) -> Profiled<T> {
+    let section = match config.get(name).and_then(|v| v.as_table()) {
+        Some(table) => table,
+        None => {
+            return Profiled {
+                base: default,
+                overrides: Vec::new(),
+            };
+        }
+    };
+    let base = parse(default, section);
+    let overrides = section
+        .iter()
+        .filter_map(|(key, value)| value.as_table().map(|sub| (key, sub)))
+        .map(|(key, sub)| (key.clone(), parse(base.clone(), sub)))
+        .collect();
+    Profiled { base, overrides }
+}
+
+/// 解析 `[convert]` 段或其子表
+///
+/// ### 参数
+/// - `default`: 缺省值（子表为基础设置）
+/// - `section`: 表
+///
+/// ### 返回值
+/// - `ConvertConfig`: 解析后的格式转换配置
+fn parse_convert_section(default: ConvertConfig, section: &toml::Table) -> ConvertConfig {
+    let boolean = |key: &str| section.get(key).and_then(|v| v.as_bool());
+
+    let format = match section.get("format").and_then(|v| v.as_str()) {
+        Some(name) => match name.trim().to_ascii_lowercase().as_str() {
+            "keep" | "" => ConvertFormat::Keep,
+            "png" => ConvertFormat::Png,
+            "jpg" | "jpeg" => ConvertFormat::Jpeg,
+            "webp" => ConvertFormat::Webp,
+            _ => {
+                eprintln!("Invalid convert format: {}, use default.", name);
+                default.format
+            }
+        },
+        None => default.format,
+    };
+    let quality = match section.get("quality").and_then(|v| v.as_integer()) {
+        Some(num) if (1..=100).contains(&num) => num as u8,
+        Some(num) => {
+            eprintln!("Invalid convert quality: {}, use default.", num);
+            default.quality
+        }
+        None => default.quality,
+    };
+
+    ConvertConfig {
+        enabled: boolean("enabled").unwrap_or(default.enabled),
+        format,
+        quality,
+        max_dimension: section
+            .get("max_dimension")
+            .and_then(|v| v.as_integer())
+            .filter(|num| *num >= 0)
+            .map(|num| num.min(u32::MAX as i64) as u32)
+            .unwrap_or(default.max_dimension),
+        strip_metadata: boolean("strip_metadata").unwrap_or(default.strip_metadata),
+        keep_original: boolean("keep_original").unwrap_or(default.keep_original),
+    }
+}
+
+/// 解析 `[watermark]` 段或其子表
+///
+/// ### 参数
+/// - `default`: 缺省值（子表为基础设置）
+/// - `section`: 表
+///
+/// ### 返回值
+/// - `WatermarkConfig`: 解析后的水印配置
+fn parse_watermark_section(default: WatermarkConfig, section: &toml::Table) -> WatermarkConfig {
+    let text = |key: &str| section.get(key).and_then(|v| v.as_str());
+    let color = |key: &str, default: [u8; 4]| match text(key) {
+        Some(value) => parse_color(value).unwrap_or_else(|| {
+            eprintln!("Invalid watermark {}: {}, use default.", key, value);
+            default
+        }),
+        None => default,
+    };
+
+    let corner = match text("corner") {
+        Some(name) => match name
+            .trim()
+            .to_ascii_lowercase()
+            .replace(['-', '_', ' '], "")
+            .as_str()
+        {
+            "topleft" => WatermarkCorner::TopLeft,
+            "topright" => WatermarkCorner::TopRight,
+            "bottomleft" => WatermarkCorner::BottomLeft,
+            "bottomright" => WatermarkCorner::BottomRight,
+            _ => {
+                eprintln!("Invalid watermark corner: {}, use default.", name);
+                default.corner
+            }
+        },
+        None => default.corner,
+    };
+    let number = |key: &str| {
+        section
+            .get(key)
+            .and_then(|v| v.as_float().or_else(|| v.as_integer().map(|n| n as f64)))
+    };
+
+    WatermarkConfig {
+        enabled: section
+            .get("enabled")
+            .and_then(|v| v.as_bool())
+            .unwrap_or(default.enabled),
+        text: text("text").map(String::from).unwrap_or(default.text),
+        font: text("font").map(handle_str_path).unwrap_or(default.font),
+        font_size: number("font_size")
+            .filter(|size| *size >= 4.0 && *size <= 512.0)
+            .map(|size| size as f32)
+            .unwrap_or(default.font_size),
+        color: color("color", default.color),
+        background: color("background", default.background),
+        logo: text("logo").map(handle_str_path).unwrap_or(default.logo),
+        logo_height: section
+            .get("logo_height")
+            .and_then(|v| v.as_integer())
+            .filter(|num| (0..=4096).contains(num))
+            .map(|num| num as u32)
+            .unwrap_or(default.logo_height),
+        corner,
+        opacity: number("opacity")
+            .map(|value| value.clamp(0.0, 1.0) as f32)
+            .unwrap_or(default.opacity),
+        padding: section
+            .get("padding")
+            .and_then(|v| v.as_integer())
+            .filter(|num| (0..=4096).contains(num))
+            .map(|num| num as u32)
+            .unwrap_or(default.padding),
+    }
+}
+
+/// 解析 `#RRGGBB` 或 `#RRGGBBAA` 形式的颜色
+///
+/// ### 返回值
+/// - `Option<[u8; 4]>`: RGBA 颜色，格式错误时返回 `None`
+fn parse_color(value: &str) -> Option<[u8; 4]> {
+    let hex = value.trim().trim_start_matches('#');
+    if !(hex.len() == 6 || hex.len() == 8) || !hex.is_ascii() {
+        return None;
+    }
+    let mut rgba = [255u8; 4];
+    for (i, channel) in rgba.iter_mut().enumerate().take(hex.len() / 2) {
+        *channel = u8::from_str_radix(&hex[i * 2..i * 2 + 2], 16).ok()?;
+    }
+    Some(rgba)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::file_ops::RES_CONF;
+
+    /// 解析嵌入的示例配置，`uncomment` 为 true 时包含注释掉的示例键
+    fn example(uncomment: bool) -> toml::Table {
+        let text = String::from_utf8_lossy(RES_CONF);
+        let text = if uncomment {
+            uncomment_example(&text)
+        } else {
+            text.to_string()
+        };
+        toml::from_str(&text).expect("config example should parse")
+    }
+
+    /// 按 `段.键` 查找示例中的值
+    fn lookup<'a>(table: &'a toml::Table, path: &str) -> Option<&'a Value> {
+        let (section, key) = path.rsplit_once('.')?;
+        let mut current = table;
+        for name in section.split('.') {
+            current = current.get(name)?.as_table()?;
+        }
+        current.get(key)
+    }
+
+    /// 默认配置中每个设置项在示例中对应的键（`段.键`）
+    ///
+    /// 按字段完整解构，新增设置项而未在此登记时无法编译；
+    /// 通知模板（默认值随语言变化）与用户自定义的表只在示例中注释给出，不在此列
+    fn default_keys(settings: &SettingsCollection) -> Vec<String> {
+        let SettingsCollection {
+            keys_collection,
+            path,
+            sundry,
+            gui: _,
+            notify: _,
+            hooks: _,
+            history,
+            retention,
+            dedupe,
+            upload,
+            copy_as,
+            convert,
+            watermark,
+            metadata,
+            resource,
+            capture,
+        } = settings;
+        let PathConfig {
+            save_path: _,
+            save_path_str: _,
+            fallback_dirs: _,
+            create_dir: _,
+            mirrors: _,
+            launch_app: LaunchAppConfig { path: _, args: _ },
+            filename_template: _,
+            subfolder: _,
+            subfolder_fallback: _,
+        } = path;
+        let Sundry {
+            auto_start: _,
+            comp_level: _,
+            scale_level: _,
+            lang: _,
+            notification: _,
+            profile: _,
+        } = sundry;
+        let HistoryConfig {
+            enabled: _,
+            max_entries: _,
+            retention_days: _,
+            recent_count: _,
+        } = history;
+        let RetentionConfig {
+            enabled: _,
+            max_age_days: _,
+            max_total_mb: _,
+            max_files: _,
+            exclude: _,
+            action: _,
+            interval_hours: _,
+            dry_run: _,
+        } = retention;
+        let DedupeConfig {
+            enabled: _,
+            action: _,
+            perceptual: _,
+            threshold: _,
+            window: _,
+        } = dedupe;
+        let UploadConfig {
+            enabled: _,
+            url: _,
+            method: _,
+            body: _,
+            field: _,
+            headers: _,
+            token: _,
+            token_header: _,
+            token_prefix: _,
+            url_field: _,
+            copy_url: _,
+            retries: _,
+            backoff: _,
+            timeout: _,
+        } = upload;
+        let CopyAsConfig {
+            docs_root: _,
+            default_format: _,
+            auto: _,
+            templates: _,
+        } = copy_as;
+        let ConvertConfig {
+            enabled: _,
+            format: _,
+            quality: _,
+            max_dimension: _,
+            strip_metadata: _,
+            keep_original: _,
+        } = &convert.base;
+        let WatermarkConfig {
+            enabled: _,
+            text: _,
+            font: _,
+            font_size: _,
+            color: _,
+            background: _,
+            logo: _,
+            logo_height: _,
+            corner: _,
+            opacity: _,
+            padding: _,
+        } = &watermark.base;
+        let MetadataConfig {
+            enabled: _,
+            png_chunks: _,
+            sidecar: _,
+        } = metadata;
+        let ResourceConfig {
+            policy: _,
+            backup: _,
+            config_backups: _,
+            config_recovery: _,
+        } = resource;
+        let CaptureConfig {
+            engine: _,
+            command:
+                CommandEngineConfig {
+                    commands: _,
+                    cancel_codes: _,
+                    no_output: _,
+                    format: _,
+                },
+        } = capture;
+
+        let sections: [(&str, &[&str]); 14] = [
+            (
+                "path",
+                &[
+                    "dir",
+                    "fallback_dirs",
+                    "create_dir",
+                    "mirrors",
+                    "launch_app_path",
+                    "launch_app_args",
+                    "filename_template",
+                    "subfolder",
+                    "subfolder_fallback",
+                ],
+            ),
+            (
+                "sundry",
+                &[
+                    "startup",
+                    "comp_level",
+                    "scale_ratio",
+                    "lang",
+                    "notification",
+                    "profile",
+                ],
+            ),
+            ("gui", &["gui_config", "long_gui_config"]),
+            (
+                "history",
+                &["enabled", "max_entries", "retention_days", "recent_count"],
+            ),
+            (
+                "retention",
+                &[
+                    "enabled",
+                    "max_age_days",
+                    "max_total_mb",
+                    "max_files",
+                    "exclude",
+                    "action",
+                    "interval_hours",
+                    "dry_run",
+                ],
+            ),
+            (
+                "dedupe",
+                &["enabled", "action", "perceptual", "threshold", "window"],
+            ),
+            (
+                "upload",
+                &[
+                    "enabled",
+                    "url",
+                    "method",
+                    "body",
+                    "field",
+                    "token",
+                    "token_header",
+                    "token_prefix",
+                    "url_field",
+                    "copy_url",
+                    "retries",
+                    "backoff_ms",
+                    "timeout",
+                ],
+            ),
+            ("copy_as", &["docs_root", "format", "auto"]),
+            (
+                "convert",
+                &[
+                    "enabled",
+                    "format",
+                    "quality",
+                    "max_dimension",
+                    "strip_metadata",
+                    "keep_original",
+                ],
+            ),
+            (
+                "watermark",
+                &[
+                    "enabled",
+                    "text",
+                    "font",
+                    "font_size",
+                    "color",
+                    "background",
+                    "logo",
+                    "logo_height",
+                    "corner",
+                    "opacity",
+                    "padding",
+                ],
+            ),
+            ("metadata", &["enabled", "png_chunks", "sidecar"]),
+            (
+                "resource",
+                &["policy", "backup", "config_backups", "config_recovery"],
+            ),
+            ("capture", &["engine", "engine_path"]),
+            ("capture.command", &["cancel_codes", "no_output", "format"]),
+        ];
+        keys_collection
+            .keys()
+            .map(|key| format!("hotkey.{}", key))
+            .chain(sections.iter().flat_map(|(section, keys)| {
+                keys.iter().map(move |key| format!("{}.{}", section, key))
+            }))
+            .collect()
+    }
+
+    #[test]
+    fn default_settings_are_in_example() {
+        let example = example(false);
+        let absent: Vec<String> = default_keys(&SettingsCollection::default())
+            .into_iter()
+            .filter(|key| lookup(&example, key).is_none())
+            .collect();
+        assert!(
+            absent.is_empty(),
+            "missing from res/config.toml: {:?}",
+            absent
+        );
+    }
+
+    #[test]
+    fn commented_keys_are_optional() {
+        let mut optional = missing_keys(&example(true), &example(false));
+        optional.sort();
+        let mut expected = vec![
+            "notification.saved",
+            "notification.clipboard",
+            "notification.pinned",
+            "notification.cancelled",
+            "notification.crashed",
+            "notification.unknown",
+            "[hooks]",
+            "[upload.headers]",
+            "[copy_as.templates]",
+            "[convert.long]",
+            "[watermark.audit]",
+            "capture.command.region",
+            "capture.command.fullscreen",
+        ];
+        expected.sort();
+        assert_eq!(optional, expected);
+    }
+
+    #[test]
+    fn reports_missing_tables_and_keys() {
+        let example: toml::Table =
+            toml::from_str("[a]\nx = 1\ny = 2\n[a.sub]\nz = 3\n[b]\nw = 4\n").unwrap();
+        let config: toml::Table = toml::from_str("[a]\nx = 5\n").unwrap();
+        let mut missing = missing_keys(&example, &config);
+        missing.sort();
+        assert_eq!(missing, vec!["[a.sub]", "[b]", "a.y"]);
+        assert!(missing_keys(&example, &example).is_empty());
+    }
+}
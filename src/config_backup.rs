@@ -0,0 +1,266 @@
+//! 配置文件备份模块
+//!
+//! 本模块负责：
+//! - 滚动保留最近几份可以正常解析的配置文件
+//! - 配置文件被删除或无法解析时，按恢复方式还原最近一次可用的备份
+//! - 还原前保留损坏的配置文件，不丢失用户的修改
+
+use crate::capture::move_no_clobber;
+use crate::config::{read_config_quiet, read_resource_config};
+use crate::file_ops::{RES_BACKUP_DIR, ResourceError, write_resource_atomic};
+use crate::integrity::{sha256_file, sha256_reader};
+use crate::types::*;
+use std::{
+    fs,
+    path::{Path, PathBuf},
+};
+use win_msgbox_timeout::{notify_msgbox_standalone, quest_msgbox_yesno, warn_msgbox};
+
+/// 配置文件备份的子目录（位于 backup 目录下）
+pub const CONFIG_BACKUP_DIR: &str = "config";
+
+/// 备份文件名前缀，其后为保存时间
+const BACKUP_PREFIX: &str = "config-";
+
+/// 备份文件名中的时间格式
+const BACKUP_TIME_FORMAT: &str = "%Y%m%d_%H%M%S";
+
+/// 配置文件的异常情况
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ConfigDamage {
+    /// 配置文件不存在
+    Missing,
+    /// 配置文件无法解析
+    Corrupted,
+}
+
+/// 检查配置文件是否缺失或损坏
+///
+/// ### 参数
+/// - `paths`: 程序路径信息
+///
+/// ### 返回值
+/// - `Option<ConfigDamage>`: 配置文件可以正常解析时返回 `None`
+pub fn config_damage(paths: &PathInfos) -> Option<ConfigDamage> {
+    if !paths.conf_path.exists() {
+        Some(ConfigDamage::Missing)
+    } else if read_config_quiet(&paths.conf_path).is_none() {
+        Some(ConfigDamage::Corrupted)
+    } else {
+        None
+    }
+}
+
+/// 备份当前配置文件
+///
+/// ### 参数
+/// - `paths`: 程序路径信息
+/// - `keep`: 保留的备份数量，为 0 时不备份
+///
+/// ### 返回值
+/// - `Result<Option<PathBuf>, ResourceError>`: 新备份的路径；无需备份时返回 `None`
+///
+/// ### 说明
+/// - 只备份可以正常解析的配置文件
+/// - 与最近一份备份内容相同时跳过
+/// - 备份为 `backup/config/config-<时间>.toml`，超出数量的旧备份被删除
+pub fn backup_config(paths: &PathInfos, keep: usize) -> Result<Option<PathBuf>, ResourceError> {
+    if keep == 0 || read_config_quiet(&paths.conf_path).is_none() {
+        return Ok(None);
+    }
+    let io_error = |path: &Path| {
+        let path = path.to_path_buf();
+        move |source| ResourceError::Io { path, source }
+    };
+
+    let data = fs::read(&paths.conf_path).map_err(io_error(&paths.conf_path))?;
+    let sha256 = sha256_reader(data.as_slice()).map_err(io_error(&paths.conf_path))?;
+    if let Some(latest) = list_backups(paths).first()
+        && sha256_file(latest).is_ok_and(|hash| hash == sha256)
+    {
+        return Ok(None);
+    }
+
+    let dir = backup_dir(paths);
+    fs::create_dir_all(&dir).map_err(io_error(&dir))?;
+    let target = dir.join(format!(
+        "{}{}.toml",
+        BACKUP_PREFIX,
+        chrono::Local::now().format(BACKUP_TIME_FORMAT)
+    ));
+    write_resource_atomic(&target, data.as_slice(), data.len() as u64, &sha256)?;
+
+    for old in list_backups(paths).iter().skip(keep) {
+        if let Err(e) = fs::remove_file(old) {
+            eprintln!("Failed to remove old backup {}: {}", old.display(), e);
+        }
+    }
+    Ok(Some(target))
+}
+
+/// 按恢复方式处理缺失或损坏的配置文件
+///
+/// ### 参数
+/// - `paths`: 程序路径信息
+/// - `damage`: 配置文件的异常情况
+///
+/// ### 返回值
+/// - `bool`: 是否已从备份还原
+///
+/// ### 说明
+/// - 配置文件本身已不可用，恢复方式读取自最近一次可用的备份
+/// - 没有可用的备份或用户拒绝时不做处理，缺失的配置文件随后由 `unzip_res` 释放默认配置
+/// - 还原损坏的配置文件前将其移动到 `backup/config/corrupted-<时间>.toml`
+/// - 只有用户确认后才会退出程序
+pub fn recover_config(paths: &PathInfos, damage: ConfigDamage) -> bool {
+    let Some(backup) = list_backups(paths)
+        .into_iter()
+        .find(|backup| read_config_quiet(backup).is_some())
+    else {
+        println!("CONF: No usable backup to restore.");
+        return false;
+    };
+    let recovery = read_resource_config(&backup).config_recovery;
+    let saved = backup_time(&backup);
+    let problem = match damage {
+        ConfigDamage::Missing => "config.toml was deleted.",
+        ConfigDamage::Corrupted => "config.toml cannot be parsed.",
+    };
+
+    let restore = match recovery {
+        ConfigRecovery::Restore => true,
+        ConfigRecovery::Ask => {
+            let otherwise = match damage {
+                ConfigDamage::Missing => "use the default config",
+                ConfigDamage::Corrupted => "keep the current file",
+            };
+            quest_msgbox_yesno(
+                format!(
+                    "{}\n\nRestore the last working copy ({})?\nChoose No to {}.",
+                    problem, saved, otherwise
+                ),
+                "SC_Starter",
+                0,
+            ) == IDYES
+        }
+        ConfigRecovery::Exit => {
+            let exit = quest_msgbox_yesno(
+                format!(
+                    "{}\n\nExit SC_Starter now?\nChoose No to restore the last working copy ({}).",
+                    problem, saved
+                ),
+                "SC_Starter",
+                0,
+            ) == IDYES;
+            if exit {
+                println!("CONF: Exit confirmed by the user.");
+                std::process::exit(0);
+            }
+            true
+        }
+    };
+    if !restore {
+        println!("CONF: Restore declined.");
+        return false;
+    }
+
+    if damage == ConfigDamage::Corrupted {
+        match set_aside_corrupted(paths) {
+            Ok(moved) => println!("CONF: Moved the broken config to {}", moved.display()),
+            Err(e) => {
+                eprintln!("Failed to move the broken config: {}", e);
+                return false;
+            }
+        }
+    }
+    match restore_backup(paths, &backup) {
+        Ok(_) => {
+            println!("CONF: Restored from {}", backup.display());
+            if recovery == ConfigRecovery::Restore {
+                notify_msgbox_standalone(
+                    "SC_Starter",
+                    format!("{}\nRestored the last working copy ({}).", problem, saved),
+                    5000,
+                );
+            }
+            true
+        }
+        Err(e) => {
+            eprintln!("Failed to restore config: {}", e);
+            warn_msgbox(
+                format!(
+                    "{}\nFailed to restore the last working copy:\n{}",
+                    problem, e
+                ),
+                "SC_Starter",
+                0,
+            );
+            false
+        }
+    }
+}
+
+/// 配置文件备份目录
+fn backup_dir(paths: &PathInfos) -> PathBuf {
+    paths.dir_path.join(RES_BACKUP_DIR).join(CONFIG_BACKUP_DIR)
+}
+
+/// 列出全部备份，按保存时间从新到旧排列
+fn list_backups(paths: &PathInfos) -> Vec<PathBuf> {
+    let Ok(entries) = fs::read_dir(backup_dir(paths)) else {
+        return Vec::new();
+    };
+    let mut backups: Vec<PathBuf> = entries
+        .filter_map(|entry| entry.ok())
+        .map(|entry| entry.path())
+        .filter(|path| {
+            path.extension().is_some_and(|ext| ext == "toml")
+                && path
+                    .file_name()
+                    .is_some_and(|name| name.to_string_lossy().starts_with(BACKUP_PREFIX))
+        })
+        .collect();
+    // 文件名中的时间可直接按字符串排序
+    backups.sort();
+    backups.reverse();
+    backups
+}
+
+/// 从备份文件名中读取保存时间，无法识别时返回文件名
+fn backup_time(backup: &Path) -> String {
+    let stem = backup
+        .file_stem()
+        .map(|s| s.to_string_lossy().to_string())
+        .unwrap_or_default();
+    stem.strip_prefix(BACKUP_PREFIX)
+        .and_then(|time| chrono::NaiveDateTime::parse_from_str(time, BACKUP_TIME_FORMAT).ok())
+        .map(|time| time.format("%Y-%m-%d %H:%M:%S").to_string())
+        .unwrap_or(stem)
+}
+
+/// 将备份写回配置文件
+fn restore_backup(paths: &PathInfos, backup: &Path) -> Result<(), ResourceError> {
+    let io_error = |source| ResourceError::Io {
+        path: backup.to_path_buf(),
+        source,
+    };
+    let data = fs::read(backup).map_err(io_error)?;
+    let sha256 = sha256_reader(data.as_slice()).map_err(io_error)?;
+    write_resource_atomic(
+        &paths.conf_path,
+        data.as_slice(),
+        data.len() as u64,
+        &sha256,
+    )
+}
+
+/// 将损坏的配置文件移动到备份目录
+fn set_aside_corrupted(paths: &PathInfos) -> std::io::Result<PathBuf> {
+    let dir = backup_dir(paths);
+    fs::create_dir_all(&dir)?;
+    let stem = format!(
+        "corrupted-{}",
+        chrono::Local::now().format(BACKUP_TIME_FORMAT)
+    );
+    move_no_clobber(&paths.conf_path, &dir, &stem, "toml")
+}
@@ -0,0 +1,281 @@
+//! 复制为文本片段模块
+//!
+//! 本模块负责：
+//! - 记录最近一次保存的截图
+//! - 按模板生成 Markdown、HTML、文件 URI、路径等文本
+//! - 生成相对于文档根目录的路径
+//! - 从托盘、快捷键或截图后自动复制到剪贴板
+
+use crate::history::load_history;
+use crate::types::*;
+use std::{
+    path::{Component, Path, PathBuf},
+    sync::Mutex,
+    thread,
+};
+use win_msgbox_timeout::{notify_msgbox_standalone, wait_notifications};
+
+/// 最近一次保存的截图及其上传链接
+static LAST_CAPTURE: Mutex<Option<(PathBuf, Option<String>)>> = Mutex::new(None);
+
+/// 记录最近一次保存的截图
+///
+/// ### 参数
+/// - `file`: 截图文件
+/// - `url`: 上传后返回的链接
+pub fn set_last_capture(file: &Path, url: Option<&str>) {
+    let mut last = LAST_CAPTURE.lock().unwrap_or_else(|e| e.into_inner());
+    *last = Some((file.to_path_buf(), url.map(String::from)));
+}
+
+/// 获取最近一次保存且仍然存在的截图
+///
+/// ### 参数
+/// - `dir_path`: 程序根目录
+///
+/// ### 返回值
+/// - `Option<(PathBuf, Option<String>)>`: 截图文件与上传链接
+///
+/// ### 说明
+/// - 本次运行尚未截图时从历史记录中查找
+pub fn last_capture(dir_path: &Path) -> Option<(PathBuf, Option<String>)> {
+    let last = LAST_CAPTURE
+        .lock()
+        .unwrap_or_else(|e| e.into_inner())
+        .clone();
+    if let Some((file, url)) = last
+        && file.is_file()
+    {
+        return Some((file, url));
+    }
+    load_history(dir_path)
+        .into_iter()
+        .rev()
+        .find_map(|entry| match entry.path {
+            Some(path) if path.is_file() => Some((path, entry.upload_url)),
+            _ => None,
+        })
+}
+
+/// 按模板生成文本片段
+///
+/// ### 参数
+/// - `config`: 复制为配置
+/// - `template`: 模板字符串
+/// - `file`: 截图文件
+/// - `url`: 上传链接
+///
+/// ### 支持的占位符
+/// - `{path}`: 相对于 docs_root 的路径（`/` 分隔，空格与括号已转义），未设置或不在同一磁盘时为绝对路径
+/// - `{rel}`: 同 `{path}`，但不转义
+/// - `{abs}`: 绝对路径
+/// - `{uri}`: `file:///` 形式的文件 URI
+/// - `{name}` / `{stem}`: 文件名 / 不含扩展名的文件名
+/// - `{url}`: 上传链接，未上传时为 `{path}`
+pub fn render_copy_as(
+    config: &CopyAsConfig,
+    template: &str,
+    file: &Path,
+    url: Option<&str>,
+) -> String {
+    let abs = path_display(file, "");
+    let rel = expand_path(&config.docs_root)
+        .filter(|root| !root.as_os_str().is_empty())
+        .and_then(|root| relative_path(&root, file))
+        .unwrap_or_else(|| abs.replace('\\', "/"));
+    let path = percent_encode(&rel, |c| {
+        !matches!(c, ' ' | '(' | ')' | '%' | '<' | '>' | '"')
+    });
+
+    expand_placeholders(template, |name, _| match name {
+        "path" => Some(path.clone()),
+        "rel" => Some(rel.clone()),
+        "abs" => Some(abs.clone()),
+        "uri" => Some(format!(
+            "file:///{}",
+            percent_encode(&abs.replace('\\', "/"), |c| {
+                c.is_ascii_alphanumeric() || "/:-._~".contains(c)
+            })
+        )),
+        "name" => file.file_name().map(|n| n.to_string_lossy().to_string()),
+        "stem" => file.file_stem().map(|n| n.to_string_lossy().to_string()),
+        "url" => Some(url.map(String::from).unwrap_or_else(|| path.clone())),
+        _ => None,
+    })
+}
+
+/// 将截图按指定格式复制到剪贴板
+///
+/// ### 参数
+/// - `ctx`: 截图任务上下文
+/// - `file`: 截图文件
+/// - `url`: 上传链接
+/// - `format`: 格式名
+///
+/// ### 返回值
+/// - `Option<String>`: 复制的文本，格式不存在或复制失败时返回 `None`
+pub fn copy_capture_as(
+    ctx: &CaptureContext,
+    file: &Path,
+    url: Option<&str>,
+    format: &str,
+) -> Option<String> {
+    let config = &ctx.settings.copy_as;
+    let Some(template) = config.template(format) else {
+        eprintln!("Unknown copy_as format: {}", format);
+        return None;
+    };
+    let text = render_copy_as(config, template, file, url);
+    match clipboard_win::set_clipboard_string(&text) {
+        Ok(_) => {
+            println!("Copied as {}: {}", format, text);
+            Some(text)
+        }
+        Err(e) => {
+            eprintln!("Failed to copy to clipboard: {}", e);
+            None
+        }
+    }
+}
+
+/// 在新线程中按指定格式复制最近一次的截图并显示通知
+///
+/// ### 参数
+/// - `ctx`: 截图任务上下文
+/// - `format`: 格式名
+///
+/// ### 说明
+/// - 供托盘菜单与快捷键调用，不阻塞调用线程
+pub fn spawn_copy_last(ctx: &CaptureContext, format: &str) {
+    let ctx = ctx.clone();
+    let format = format.to_string();
+    thread::spawn(move || {
+        let lang = ctx.settings.sundry.lang;
+        let message = match last_capture(&ctx.paths.dir_path) {
+            Some((file, url)) => copy_capture_as(&ctx, &file, url.as_deref(), &format),
+            None => Some(
+                if lang {
+                    "还没有保存过截图"
+                } else {
+                    "No saved capture yet"
+                }
+                .to_string(),
+            ),
+        };
+        if let Some(message) = message {
+            let title = if lang {
+                format!("SC_Starter 已复制为 {}", format)
+            } else {
+                format!("SC_Starter Copied as {}", format)
+            };
+            notify_msgbox_standalone(&title, message, 2000);
+            wait_notifications();
+        }
+    });
+}
+
+/// 计算 `target` 相对于 `base` 目录的路径
+///
+/// ### 返回值
+/// - `Option<String>`: 以 `/` 分隔的相对路径，不在同一磁盘时返回 `None`
+///
+/// ### 说明
+/// - Windows 路径不区分大小写
+fn relative_path(base: &Path, target: &Path) -> Option<String> {
+    let normalize = |p: &Path| -> Vec<String> {
+        PathBuf::from(path_display(p, ""))
+            .components()
+            .filter(|c| !matches!(c, Component::CurDir))
+            .map(|c| c.as_os_str().to_string_lossy().to_string())
+            .collect()
+    };
+    let base = normalize(base);
+    let target = normalize(target);
+    if base.first()?.to_lowercase() != target.first()?.to_lowercase() {
+        return None;
+    }
+
+    let common = base
+        .iter()
+        .zip(target.iter())
+        .take_while(|(a, b)| a.to_lowercase() == b.to_lowercase())
+        .count();
+    let mut parts: Vec<String> = vec!["..".to_string(); base.len() - common];
+    parts.extend(target[common..].iter().cloned());
+    Some(parts.join("/"))
+}
+
+/// 对不满足 `keep` 的字符进行百分号编码（按 UTF-8 字节）
+fn percent_encode(text: &str, keep: impl Fn(char) -> bool) -> String {
+    let mut output = String::with_capacity(text.len());
+    for c in text.chars() {
+        if keep(c) {
+            output.push(c);
+        } else {
+            let mut buffer = [0u8; 4];
+            for byte in c.encode_utf8(&mut buffer).bytes() {
+                output.push_str(&format!("%{:02X}", byte));
+            }
+        }
+    }
+    output
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn relative(base: &str, target: &str) -> Option<String> {
+        relative_path(Path::new(base), Path::new(target))
+    }
+
+    #[test]
+    fn relative_path_inside_base() {
+        assert_eq!(
+            relative(r"C:\Docs\site", r"C:\Docs\site\img\a.png").as_deref(),
+            Some("img/a.png")
+        );
+        assert_eq!(
+            relative(r"\\?\C:\Docs\site", r"C:\Docs\site\a.png").as_deref(),
+            Some("a.png")
+        );
+    }
+
+    #[test]
+    fn relative_path_climbs_up() {
+        assert_eq!(
+            relative(r"C:\Docs\site", r"C:\Docs\a.png").as_deref(),
+            Some("../a.png")
+        );
+        assert_eq!(
+            relative(r"C:\Docs\site\pages", r"C:\Shots\2026\a.png").as_deref(),
+            Some("../../../Shots/2026/a.png")
+        );
+    }
+
+    #[test]
+    fn relative_path_ignores_case() {
+        assert_eq!(
+            relative(r"c:\DOCS\Site", r"C:\docs\site\Img\a.png").as_deref(),
+            Some("Img/a.png")
+        );
+    }
+
+    #[test]
+    fn relative_path_across_drives() {
+        assert_eq!(relative(r"D:\Docs", r"C:\Shots\a.png"), None);
+        assert_eq!(relative(r"\\server\share\docs", r"C:\Shots\a.png"), None);
+    }
+
+    #[test]
+    fn percent_encodes_utf8_bytes() {
+        let keep = |c: char| c.is_ascii_alphanumeric() || "/:-._~".contains(c);
+        assert_eq!(
+            percent_encode("C:/Shots/a b.png", keep),
+            "C:/Shots/a%20b.png"
+        );
+        assert_eq!(percent_encode("截图.png", keep), "%E6%88%AA%E5%9B%BE.png");
+        assert_eq!(percent_encode("100%", keep), "100%25");
+        assert_eq!(percent_encode("", keep), "");
+    }
+}
@@ -0,0 +1,363 @@
+//! 重复截图检测模块
+//!
+//! 本模块负责：
+//! - 计算截图的 SHA-256 与感知哈希（dHash）
+//! - 将新截图与最近的历史记录比较，按配置通知、删除或关联
+//! - 扫描目录并按完全相同或相似分组（`dedupe` 命令）
+
+use crate::capture::is_image;
+use crate::history::load_history;
+use crate::metadata::content_sha256;
+use crate::retention::TRASH_DIR;
+use crate::types::*;
+use image::imageops::FilterType;
+use std::{
+    collections::HashMap,
+    fs, io,
+    path::{Path, PathBuf},
+};
+use win_msgbox_timeout::notify_msgbox_standalone;
+
+/// 截图指纹
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Fingerprint {
+    /// 图片内容的 SHA-256（小写十六进制，PNG 不含元数据文本块）
+    pub sha256: String,
+    /// 64 位感知哈希，无法解码图片时为 `None`
+    pub dhash: Option<u64>,
+}
+
+/// 计算图片的差值感知哈希（dHash）
+///
+/// ### 参数
+/// - `path`: 图片路径
+///
+/// ### 返回值
+/// - `Option<u64>`: 64 位哈希，无法解码时返回 `None`
+///
+/// ### 说明
+/// - 缩放为 9x8 灰度图后逐行比较相邻像素亮度
+/// - 两张图片哈希的汉明距离越小越相似
+pub fn dhash(path: &Path) -> Option<u64> {
+    let image = image::open(path)
+        .map_err(|e| eprintln!("Failed to decode {}: {}", path.display(), e))
+        .ok()?;
+    let small = image.resize_exact(9, 8, FilterType::Triangle).to_luma8();
+    let mut hash = 0u64;
+    for y in 0..8 {
+        for x in 0..8 {
+            let left = small.get_pixel(x, y)[0];
+            let right = small.get_pixel(x + 1, y)[0];
+            hash = (hash << 1) | u64::from(left > right);
+        }
+    }
+    Some(hash)
+}
+
+/// 计算截图指纹
+///
+/// ### 参数
+/// - `path`: 图片路径
+/// - `perceptual`: 是否计算感知哈希
+pub fn fingerprint(path: &Path, perceptual: bool) -> io::Result<Fingerprint> {
+    Ok(Fingerprint {
+        sha256: content_sha256(path)?,
+        dhash: if perceptual { dhash(path) } else { None },
+    })
+}
+
+/// 两个感知哈希的汉明距离
+pub fn hamming(a: u64, b: u64) -> u32 {
+    (a ^ b).count_ones()
+}
+
+/// 重复检测结果
+#[derive(Clone, Debug, Default)]
+pub struct DedupeResult {
+    /// 新截图的指纹（未启用检测或计算失败时为 `None`）
+    pub fingerprint: Option<Fingerprint>,
+    /// 与之重复的先前截图
+    pub duplicate_of: Option<PathBuf>,
+    /// 处理后的本次输出文件
+    pub output: Option<PathBuf>,
+    /// 新截图是否已被替换为指向先前截图的硬链接
+    pub linked: bool,
+}
+
+/// 与历史记录中的截图匹配的结果
+struct DuplicateMatch {
+    original: PathBuf,
+    exact: bool,
+    distance: u32,
+}
+
+/// 检测新截图是否与最近的截图重复，并按配置处理
+///
+/// ### 参数
+/// - `ctx`: 截图任务上下文
+/// - `file`: 新保存的截图
+///
+/// ### 返回值
+/// - `DedupeResult`: 指纹、重复来源与处理后的输出文件
+///
+/// ### 功能
+/// - 与最近 window 条带指纹的历史记录比较，优先匹配完全相同的文件
+/// - warn: 通知但保留文件
+/// - delete: 删除新截图，以先前的截图作为本次输出
+/// - link: 完全相同时以指向先前截图的硬链接替换新截图，相似时仅在历史记录中关联
+pub fn check_duplicate(ctx: &CaptureContext, file: &Path) -> DedupeResult {
+    let config = &ctx.settings.dedupe;
+    let mut result = DedupeResult {
+        output: Some(file.to_path_buf()),
+        ..DedupeResult::default()
+    };
+    if !config.enabled {
+        return result;
+    }
+
+    let fingerprint = match fingerprint(file, config.perceptual) {
+        Ok(fp) => fp,
+        Err(e) => {
+            eprintln!("Failed to hash {}: {}", file.display(), e);
+            return result;
+        }
+    };
+    let found = find_match(ctx, file, &fingerprint);
+    result.fingerprint = Some(fingerprint);
+    let Some(found) = found else {
+        return result;
+    };
+
+    println!(
+        "Duplicate of {} (exact: {}, distance: {})",
+        found.original.display(),
+        found.exact,
+        found.distance
+    );
+    let lang = ctx.settings.sundry.lang;
+    let name = found
+        .original
+        .file_name()
+        .map(|n| n.to_string_lossy().to_string())
+        .unwrap_or_default();
+    let mut message = match (found.exact, lang) {
+        (true, true) => format!("与 {} 完全相同", name),
+        (true, false) => format!("Identical to {}", name),
+        (false, true) => format!("与 {} 相似（距离 {}）", name, found.distance),
+        (false, false) => format!("Similar to {} (distance {})", name, found.distance),
+    };
+
+    match config.action {
+        DedupeAction::Warn => {}
+        DedupeAction::Delete => match fs::remove_file(file) {
+            Ok(_) => {
+                message.push_str(if lang {
+                    "，已删除新截图"
+                } else {
+                    ", new capture deleted"
+                });
+                result.output = Some(found.original.clone());
+            }
+            Err(e) => eprintln!("Failed to delete duplicate {}: {}", file.display(), e),
+        },
+        DedupeAction::Link if found.exact => match replace_with_link(file, &found.original) {
+            Ok(_) => {
+                message.push_str(if lang {
+                    "，已替换为硬链接"
+                } else {
+                    ", replaced with a hard link"
+                });
+                result.linked = true;
+            }
+            Err(e) => eprintln!("Failed to link {}: {}", file.display(), e),
+        },
+        DedupeAction::Link => {}
+    }
+    result.duplicate_of = Some(found.original);
+
+    if config.action != DedupeAction::Link {
+        let title = if lang {
+            "SC_Starter 重复截图"
+        } else {
+            "SC_Starter Duplicate Capture"
+        };
+        notify_msgbox_standalone(title, message, 5000);
+    }
+    result
+}
+
+/// 在最近的历史记录中查找重复截图
+fn find_match(
+    ctx: &CaptureContext,
+    file: &Path,
+    fingerprint: &Fingerprint,
+) -> Option<DuplicateMatch> {
+    let config = &ctx.settings.dedupe;
+    let recent: Vec<(PathBuf, String, Option<u64>)> = load_history(&ctx.paths.dir_path)
+        .into_iter()
+        .rev()
+        .filter_map(|entry| Some((entry.path?, entry.sha256?, entry.dhash)))
+        .filter(|(path, _, _)| path != file && path.is_file())
+        .take(config.window)
+        .collect();
+
+    if let Some((path, _, _)) = recent.iter().find(|(_, sha, _)| *sha == fingerprint.sha256) {
+        return Some(DuplicateMatch {
+            original: path.clone(),
+            exact: true,
+            distance: 0,
+        });
+    }
+
+    let hash = fingerprint.dhash?;
+    recent
+        .iter()
+        .filter_map(|(path, _, other)| other.map(|other| (path, hamming(hash, other))))
+        .filter(|(_, distance)| *distance <= config.threshold)
+        .min_by_key(|(_, distance)| *distance)
+        .map(|(path, distance)| DuplicateMatch {
+            original: path.clone(),
+            exact: false,
+            distance,
+        })
+}
+
+/// 以指向先前截图的硬链接替换新截图
+///
+/// ### 说明
+/// - 先在同一目录创建临时硬链接再替换，失败时保留原文件
+/// - 跨卷或文件系统不支持硬链接时返回错误
+fn replace_with_link(file: &Path, original: &Path) -> io::Result<()> {
+    let temp = file.with_extension("link.tmp");
+    let _ = fs::remove_file(&temp);
+    fs::hard_link(original, &temp)?;
+    fs::rename(&temp, file).inspect_err(|_| {
+        let _ = fs::remove_file(&temp);
+    })
+}
+
+/// 重复截图分组
+#[derive(Clone, Debug)]
+pub struct DuplicateGroup {
+    /// 组内文件是否完全相同
+    pub exact: bool,
+    /// 组内文件（第一项为最早的文件）
+    pub files: Vec<PathBuf>,
+}
+
+/// 扫描目录并查找重复截图
+///
+/// ### 参数
+/// - `dir`: 要扫描的目录
+/// - `recursive`: 是否包含子目录（始终跳过 trash 子目录）
+/// - `threshold`: 感知哈希最大汉明距离，为 `None` 时只查找完全相同的文件
+///
+/// ### 返回值
+/// - `(Vec<DuplicateGroup>, usize)`: 重复分组与扫描的图片总数
+pub fn scan_duplicates(
+    dir: &Path,
+    recursive: bool,
+    threshold: Option<u32>,
+) -> (Vec<DuplicateGroup>, usize) {
+    let mut files = Vec::new();
+    collect_images(dir, recursive, &mut files);
+    files.sort_by_key(|path| {
+        fs::metadata(path)
+            .and_then(|m| m.modified())
+            .unwrap_or(std::time::SystemTime::UNIX_EPOCH)
+    });
+
+    // 按 SHA-256 分组
+    let mut by_hash: HashMap<String, Vec<PathBuf>> = HashMap::new();
+    let mut order: Vec<String> = Vec::new();
+    for file in &files {
+        match content_sha256(file) {
+            Ok(hash) => {
+                if !by_hash.contains_key(&hash) {
+                    order.push(hash.clone());
+                }
+                by_hash.entry(hash).or_default().push(file.clone());
+            }
+            Err(e) => eprintln!("Failed to hash {}: {}", file.display(), e),
+        }
+    }
+    let exact_groups: Vec<Vec<PathBuf>> = order
+        .into_iter()
+        .filter_map(|hash| by_hash.remove(&hash))
+        .collect();
+
+    let Some(threshold) = threshold else {
+        let groups = exact_groups
+            .into_iter()
+            .filter(|group| group.len() > 1)
+            .map(|files| DuplicateGroup { exact: true, files })
+            .collect();
+        return (groups, files.len());
+    };
+
+    // 每组完全相同的文件只计算一次感知哈希，再按汉明距离合并相似的组
+    let hashes: Vec<Option<u64>> = exact_groups.iter().map(|group| dhash(&group[0])).collect();
+    let mut parent: Vec<usize> = (0..exact_groups.len()).collect();
+    fn root(parent: &mut [usize], mut i: usize) -> usize {
+        while parent[i] != i {
+            parent[i] = parent[parent[i]];
+            i = parent[i];
+        }
+        i
+    }
+    for (i, a) in hashes.iter().enumerate() {
+        let Some(a) = *a else { continue };
+        for (j, b) in hashes.iter().enumerate().skip(i + 1) {
+            if let Some(b) = *b
+                && hamming(a, b) <= threshold
+            {
+                let (ri, rj) = (root(&mut parent, i), root(&mut parent, j));
+                if ri != rj {
+                    parent[rj] = ri;
+                }
+            }
+        }
+    }
+
+    let mut merged: Vec<(usize, Vec<usize>)> = Vec::new();
+    for i in 0..exact_groups.len() {
+        let r = root(&mut parent, i);
+        match merged.iter_mut().find(|(key, _)| *key == r) {
+            Some((_, members)) => members.push(i),
+            None => merged.push((r, vec![i])),
+        }
+    }
+    let groups = merged
+        .into_iter()
+        .map(|(_, members)| DuplicateGroup {
+            exact: members.len() == 1,
+            files: members
+                .iter()
+                .flat_map(|&i| exact_groups[i].iter().cloned())
+                .collect(),
+        })
+        .filter(|group| group.files.len() > 1)
+        .collect();
+    (groups, files.len())
+}
+
+/// 收集目录中的图片文件
+fn collect_images(dir: &Path, recursive: bool, output: &mut Vec<PathBuf>) {
+    let entries = match fs::read_dir(dir) {
+        Ok(entries) => entries,
+        Err(e) => {
+            eprintln!("Failed to read {}: {}", dir.display(), e);
+            return;
+        }
+    };
+    for entry in entries.filter_map(|e| e.ok()) {
+        let path = entry.path();
+        if path.is_dir() {
+            if recursive && !entry.file_name().eq_ignore_ascii_case(TRASH_DIR) {
+                collect_images(&path, recursive, output);
+            }
+        } else if is_image(&path) {
+            output.push(path);
+        }
+    }
+}
@@ -0,0 +1,355 @@
+//! 截图引擎模块
+//!
+//! 本模块负责：
+//! - 定义截图引擎接口：将截图模式映射为命令，并将退出码与输出文件归一化为截图结果
+//! - 实现 ScreenCapture 引擎（内置或 `[capture] engine_path` 指定的外部版本）
+//! - 实现命令模板引擎，用于调用其它截图工具
+//! - 探测外部 ScreenCapture 支持的命令行参数
+
+use crate::capture::{CaptureJob, locate_output};
+use crate::file_ops::build_capture_args;
+use crate::pe_version::{file_version, format_version};
+use crate::types::*;
+use chrono::{DateTime, Local};
+use std::{
+    fs,
+    path::{Path, PathBuf},
+    sync::Arc,
+};
+
+/// 截图引擎
+///
+/// 托盘与快捷键只通过此接口启动截图，不关心具体使用的程序
+pub trait CaptureEngine: Send + Sync + std::fmt::Debug {
+    /// 引擎名称（用于日志）
+    fn name(&self) -> String;
+
+    /// 截图程序的版本，无法确定时返回 `None`
+    fn version(&self) -> Option<String>;
+
+    /// 是否支持指定的截图模式
+    fn supports(&self, mode: CaptureMode) -> bool;
+
+    /// 生成本次截图的启动命令
+    ///
+    /// ### 参数
+    /// - `settings`: 完整配置集合
+    /// - `mode`: 截图模式
+    /// - `save_dir`: 本次截图的保存目录，为空表示手动选择
+    /// - `started`: 截图开始时间
+    fn command(
+        &self,
+        settings: &SettingsCollection,
+        mode: CaptureMode,
+        save_dir: &Path,
+        started: &DateTime<Local>,
+    ) -> EngineCommand;
+
+    /// 将进程退出码归一化为截图结果
+    ///
+    /// ### 参数
+    /// - `job`: 本次截图任务
+    /// - `code`: 进程退出码，进程被强制终止或无法启动时为 `None`
+    fn outcome(&self, job: &CaptureJob, code: Option<i32>) -> CaptureOutcome;
+}
+
+/// 截图引擎生成的启动命令
+#[derive(Clone, Debug, Default)]
+pub struct EngineCommand {
+    /// 程序路径
+    pub program: PathBuf,
+    /// 命令行参数
+    pub args: Vec<String>,
+    /// 引擎指定的输出文件，由程序自行命名时为 `None`
+    pub output: Option<PathBuf>,
+}
+
+/// ScreenCapture 截图引擎
+#[derive(Debug)]
+pub struct ScreenCaptureEngine {
+    /// ScreenCapture 路径
+    pub path: PathBuf,
+    /// 支持的命令行参数
+    pub caps: EngineCapabilities,
+}
+impl CaptureEngine for ScreenCaptureEngine {
+    fn name(&self) -> String {
+        format!(
+            "ScreenCapture v{}",
+            self.caps.version.as_deref().unwrap_or("unknown")
+        )
+    }
+
+    fn version(&self) -> Option<String> {
+        self.caps.version.clone()
+    }
+
+    fn supports(&self, mode: CaptureMode) -> bool {
+        self.caps.supports(mode)
+    }
+
+    fn command(
+        &self,
+        settings: &SettingsCollection,
+        mode: CaptureMode,
+        save_dir: &Path,
+        _started: &DateTime<Local>,
+    ) -> EngineCommand {
+        EngineCommand {
+            program: self.path.clone(),
+            args: build_capture_args(settings, &self.caps, mode, &save_dir.to_path_buf()),
+            output: None,
+        }
+    }
+
+    fn outcome(&self, _job: &CaptureJob, code: Option<i32>) -> CaptureOutcome {
+        CaptureOutcome::from_code(code)
+    }
+}
+
+/// 命令模板截图引擎
+///
+/// 按 `[capture.command]` 中各模式的命令调用其它截图工具
+#[derive(Debug)]
+pub struct CommandEngine {
+    /// 命令模板配置
+    pub config: CommandEngineConfig,
+}
+impl CaptureEngine for CommandEngine {
+    fn name(&self) -> String {
+        let program = CaptureMode::ALL
+            .iter()
+            .find_map(|&mode| self.config.command(mode))
+            .and_then(|cmd| cmd.first())
+            .map(String::as_str)
+            .unwrap_or("none");
+        format!("Command template ({})", program)
+    }
+
+    fn version(&self) -> Option<String> {
+        // 任意截图工具的版本无法可靠读取
+        None
+    }
+
+    fn supports(&self, mode: CaptureMode) -> bool {
+        self.config.command(mode).is_some()
+    }
+
+    /// ### 支持的占位符
+    /// - `{output}`: 输出文件完整路径（保存目录 + `{name}`）
+    /// - `{dir}`: 保存目录
+    /// - `{name}`: 输出文件名，如 `SC_20250101_120000.png`
+    /// - `{format}`: 输出文件扩展名
+    /// - `{lang}`: `cn` 或 `en`
+    ///
+    /// ### 说明
+    /// - 保存目录为空（手动选择）时输出到系统临时目录
+    fn command(
+        &self,
+        settings: &SettingsCollection,
+        mode: CaptureMode,
+        save_dir: &Path,
+        started: &DateTime<Local>,
+    ) -> EngineCommand {
+        let Some((program, args)) = self.config.command(mode).and_then(|cmd| cmd.split_first())
+        else {
+            return EngineCommand::default();
+        };
+        let dir = if save_dir.as_os_str().is_empty() {
+            std::env::temp_dir()
+        } else {
+            save_dir.to_path_buf()
+        };
+        let name = format!(
+            "SC_{}.{}",
+            started.format("%Y%m%d_%H%M%S"),
+            self.config.format
+        );
+        let output = dir.join(&name);
+        let expand = |template: &str| {
+            expand_placeholders(template, |key, _| match key {
+                "output" => Some(output.display().to_string()),
+                "dir" => Some(dir.display().to_string()),
+                "name" => Some(name.clone()),
+                "format" => Some(self.config.format.clone()),
+                "lang" => Some(if settings.sundry.lang { "cn" } else { "en" }.to_string()),
+                _ => None,
+            })
+        };
+
+        EngineCommand {
+            program: PathBuf::from(expand(program)),
+            args: args.iter().map(|arg| expand(arg)).collect(),
+            output: (mode != CaptureMode::Pin).then_some(output),
+        }
+    }
+
+    /// ### 说明
+    /// - `cancel_codes` 中的退出码视为取消
+    /// - 退出码为 0 时：钉图模式视为已钉图；生成了输出文件（或保存目录中出现新截图）视为已保存，
+    ///   否则按 `no_output` 处理
+    /// - 其它退出码视为未知，无退出码视为异常退出
+    fn outcome(&self, job: &CaptureJob, code: Option<i32>) -> CaptureOutcome {
+        match code {
+            Some(c) if self.config.cancel_codes.contains(&c) => CaptureOutcome::Cancelled,
+            Some(0) if job.mode == CaptureMode::Pin => CaptureOutcome::Pinned,
+            Some(0) => {
+                let saved = job.output.as_deref().is_some_and(Path::is_file)
+                    || locate_output(&job.save_dir, job.started.into()).is_some();
+                if saved {
+                    CaptureOutcome::SavedToFile
+                } else {
+                    self.config.no_output
+                }
+            }
+            Some(x) if x < 0 => CaptureOutcome::Crashed(Some(x)),
+            Some(x) => CaptureOutcome::Unknown(x),
+            None => CaptureOutcome::Crashed(None),
+        }
+    }
+}
+
+/// 按配置创建截图引擎
+///
+/// ### 参数
+/// - `config`: 截图引擎配置
+/// - `paths`: 程序路径信息
+/// - `caps`: ScreenCapture 支持的命令行参数（见 `prepare_engine`）
+///
+/// ### 说明
+/// - 命令模板引擎没有设置任何命令时提示并改用 ScreenCapture
+pub fn create_engine(
+    config: &CaptureConfig,
+    paths: &PathInfos,
+    caps: EngineCapabilities,
+) -> Arc<dyn CaptureEngine> {
+    let screen_capture = || -> Arc<dyn CaptureEngine> {
+        Arc::new(ScreenCaptureEngine {
+            path: paths.engine().to_path_buf(),
+            caps: caps.clone(),
+        })
+    };
+    let engine = match config.engine {
+        EngineKind::ScreenCapture => screen_capture(),
+        EngineKind::Command if config.command.commands.iter().all(|(_, c)| c.is_empty()) => {
+            win_msgbox_timeout::warn_msgbox(
+                "[capture.command] has no commands, ScreenCapture will be used.",
+                "SC_Starter",
+                5,
+            );
+            screen_capture()
+        }
+        EngineKind::Command => Arc::new(CommandEngine {
+            config: config.command.clone(),
+        }),
+    };
+    println!("ENGINE: {}", engine.name());
+    engine
+}
+
+/// 需要探测的命令行参数
+const PROBED_FLAGS: [&str; 7] = [
+    "--cap:long",
+    "--cap:fullscreen",
+    "--pin:clipboard",
+    "--comp:",
+    "--path:",
+    "--tool:",
+    "--lang:",
+];
+
+/// 探测截图引擎支持的命令行参数
+///
+/// ### 参数
+/// - `path`: ScreenCapture 可执行文件路径
+///
+/// ### 返回值
+/// - `Option<EngineCapabilities>`: 支持的参数，无法读取文件时返回 `None`
+///
+/// ### 说明
+/// - 在文件中查找各参数的 UTF-8 与 UTF-16 字面量，不启动进程，不会弹出截图界面
+/// - 一个参数都找不到时（如加壳的程序）无法判断，视为全部支持
+pub fn probe_engine(path: &Path) -> Option<EngineCapabilities> {
+    let data = match fs::read(path) {
+        Ok(data) => data,
+        Err(e) => {
+            eprintln!("Failed to read {}: {}", path.display(), e);
+            return None;
+        }
+    };
+    let version = file_version(&data).map(format_version);
+    let found: Vec<bool> = PROBED_FLAGS
+        .iter()
+        .map(|flag| contains_literal(&data, flag))
+        .collect();
+    if !found.iter().any(|&f| f) {
+        eprintln!(
+            "No known flags found in {}, assuming full support",
+            path.display()
+        );
+        return Some(EngineCapabilities {
+            version,
+            ..EngineCapabilities::default()
+        });
+    }
+
+    let [long, fullscreen, pin, comp, save_path, tool, lang] = found[..] else {
+        return None;
+    };
+    Some(EngineCapabilities {
+        version,
+        long,
+        fullscreen,
+        pin,
+        comp,
+        save_path,
+        tool,
+        lang,
+    })
+}
+
+/// 准备截图引擎
+///
+/// ### 参数
+/// - `paths`: 程序路径信息，外部引擎不可用时清除其 `engine_path`
+///
+/// ### 返回值
+/// - `EngineCapabilities`: 引擎支持的参数，内置引擎视为全部支持
+///
+/// ### 说明
+/// - 外部引擎不存在或无法读取时提示并改用内置版本
+pub fn prepare_engine(paths: &mut PathInfos) -> EngineCapabilities {
+    let Some(engine) = paths.engine_path.clone() else {
+        return EngineCapabilities::default();
+    };
+    match probe_engine(&engine) {
+        Some(caps) => {
+            println!(
+                "ENGINE: Using external ScreenCapture v{} at {}\n        {}",
+                caps.version.as_deref().unwrap_or("unknown"),
+                engine.display(),
+                caps
+            );
+            caps
+        }
+        None => {
+            win_msgbox_timeout::warn_msgbox(
+                format!(
+                    "External ScreenCapture is unavailable:\n{}\n\nThe embedded one will be used.",
+                    engine.display()
+                ),
+                "SC_Starter",
+                5,
+            );
+            paths.engine_path = None;
+            EngineCapabilities::default()
+        }
+    }
+}
+
+/// 判断数据中是否包含指定 ASCII 字符串（UTF-8 或 UTF-16LE 编码）
+fn contains_literal(data: &[u8], text: &str) -> bool {
+    let utf16: Vec<u8> = text.encode_utf16().flat_map(u16::to_le_bytes).collect();
+    data.windows(text.len()).any(|w| w == text.as_bytes())
+        || data.windows(utf16.len()).any(|w| w == utf16.as_slice())
+}
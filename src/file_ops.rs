@@ -4,23 +4,38 @@
 //! - 检查和保护核心文件
 //! - 处理文件操作请求
 //! - 执行外部程序
-//! - 监控文件状态并自动恢复
 
-use crate::types::{FileExist, PathInfos, RES_HASH_SHA1};
+use crate::capture::{CaptureJob, CaptureOrigin, finish_capture, move_no_clobber};
+use crate::copy_as::{copy_capture_as, set_last_capture};
+use crate::dedupe::check_duplicate;
+use crate::history::record_capture;
+use crate::hooks::run_post_capture_hooks;
+use crate::hotkeys::save_path_get;
+use crate::integrity::{copy_with_sha256, hash_matches, sha256_file, sha256_reader};
+use crate::manifest::{
+    RES_CONF_SHA256, RES_EXE_DEFLATE, RES_EXE_SHA256, RES_EXE_SIZE, RES_VERSION, RES_VERSION_NUMBER,
+};
+use crate::metadata::write_capture_metadata;
+use crate::mirror::mirror_capture;
+use crate::pe_version::{file_version, format_version};
+use crate::postprocess::postprocess_capture;
+use crate::types::{
+    CaptureContext, CaptureMode, CaptureOutcome, DEBUG, EngineCapabilities, FileExist, IDYES,
+    PathInfos, ResourceConfig, ResourcePolicy, SettingsCollection,
+};
+use crate::upload::upload_capture;
+use flate2::read::DeflateDecoder;
 use std::{
-    collections::HashMap,
-    fs,
+    fs, io,
     os::windows::process::CommandExt,
-    path::Path,
+    path::{Path, PathBuf},
     process::Command,
-    sync::{
-        Arc,
-        atomic::{AtomicBool, Ordering},
-        mpsc,
-    },
-    thread,
+    thread::{self, JoinHandle},
+    time::{Duration, Instant},
+};
+use win_msgbox_timeout::{
+    error_msgbox, notify_msgbox_standalone, quest_msgbox_yesno, wait_notifications,
 };
-use win_msgbox_timeout::{error_msgbox, info_msgbox, notify_msgbox_standalone, wait_notifications};
 
 /// 检查所需文件是否存在及其状态
 ///
@@ -30,14 +45,17 @@ use win_msgbox_timeout::{error_msgbox, info_msgbox, notify_msgbox_standalone, wa
 /// ### 返回值
 /// - `FileExist`: 包含文件状态的结构体，包括：
 ///   - `exe_exist`: exe文件是否存在
-///   - `exe_latest`: exe文件是否为最新版本
+///   - `exe_latest`: exe文件是否与嵌入的版本一致
+///   - `exe_version`: exe文件的文件版本
 ///   - `conf_exist`: 配置文件是否存在
+///   - `conf_example_latest`: 示例配置文件是否与嵌入的版本一致
 ///
 /// ### 说明
 /// - 如果目录不存在会自动创建
 /// - 检查exe文件是否存在
-/// - 检查exe文件是否为最新版本（通过MD5校验）
-/// - 检查配置文件是否存在
+/// - 检查exe文件是否与嵌入的版本一致（通过 SHA-256 校验），并读取其文件版本
+/// - 检查配置文件是否存在，示例配置文件是否与嵌入的版本一致（升级后需要刷新）
+/// - 使用外部 ScreenCapture 时不校验内置的exe
 pub fn check_res_exist(infos: &PathInfos) -> FileExist {
     let mut files_exist = FileExist::default();
 
@@ -51,87 +69,343 @@ pub fn check_res_exist(infos: &PathInfos) -> FileExist {
     files_exist.exe_exist = infos.exe_path.exists();
     files_exist.conf_exist = infos.conf_path.exists();
     files_exist.conf_example_exist = infos.conf_example_path.exists();
+    files_exist.conf_example_latest = files_exist.conf_example_exist
+        && sha256_file(&infos.conf_example_path)
+            .is_ok_and(|hash| hash_matches(&hash, RES_CONF_SHA256));
 
-    if files_exist.exe_exist {
-        files_exist.exe_latest = check_latest(&infos.exe_path);
+    if files_exist.exe_exist && infos.engine_path.is_none() {
+        (files_exist.exe_latest, files_exist.exe_version) = check_latest(&infos.exe_path);
     }
     files_exist
 }
 
-/// 检查exe文件是否为最新版本
+/// 检查exe文件是否与嵌入的版本一致
 ///
 /// ### 参数
 /// - `file_path`: 要检查的exe文件路径
 ///
 /// ### 返回值
-/// - `bool`: 如果文件MD5哈希值与预期的RES_HASH匹配，返回true
+/// - `(bool, Option<[u16; 4]>)`: 文件的 SHA-256 是否与编译时生成的资源清单一致，以及文件版本
 ///
 /// ### 说明
-/// - 使用Windows系统的certutil工具计算哈希值
-/// - 通过CREATE_NO_WINDOW标志隐藏命令行窗口
-/// - 将计算结果与内置的RES_HASH常量进行比较
-fn check_latest(file_path: &Path) -> bool {
-    let hash = match std::process::Command::new("certutil")
-        .arg("-hashfile")
-        .arg(file_path)
-        .arg("SHA1")
-        .creation_flags(0x08000000) // CREATE_NO_WINDOW - 隐藏命令行窗口
-        .output()
-    {
-        Ok(output) => output,
+/// - 在进程内计算哈希值，不再启动 certutil 子进程
+/// - 文件无法读取（如被占用）时视为不一致，版本为 `None`
+fn check_latest(file_path: &Path) -> (bool, Option<[u16; 4]>) {
+    let data = match fs::read(file_path) {
+        Ok(data) => data,
         Err(e) => {
-            eprintln!("Failed to execute certutil: {}", e);
-            return false;
+            eprintln!("Failed to read {}: {}", file_path.display(), e);
+            return (false, None);
         }
     };
-    let hash_value = {
-        let original = String::from_utf8_lossy(&hash.stdout);
-        match original.lines().nth(1) {
-            Some(line) => line.trim().to_ascii_uppercase(),
-            None => {
-                eprintln!("Unexpected certutil output format");
-                return false;
+    let latest = sha256_reader(data.as_slice())
+        .map(|hash| hash_matches(&hash, RES_EXE_SHA256))
+        .unwrap_or(false);
+    (latest, file_version(&data))
+}
+
+/// 按资源策略判断是否需要释放嵌入的exe
+///
+/// ### 参数
+/// - `exists`: 文件存在状态的检查结果
+/// - `policy`: ScreenCapture 替换策略
+///
+/// ### 返回值
+/// - `bool`: exe不存在，或与嵌入的版本不同且策略要求替换时返回true
+///
+/// ### 说明
+/// - prefer-newer 只保留文件版本高于嵌入版本的exe；版本相同但内容不同视为损坏，仍然替换
+/// - 任一方无法读取版本时按 embedded 处理
+pub fn should_release_exe(exists: &FileExist, policy: ResourcePolicy) -> bool {
+    if !exists.exe_exist {
+        return true;
+    }
+    if exists.exe_latest {
+        return false;
+    }
+    match policy {
+        ResourcePolicy::Embedded => true,
+        ResourcePolicy::PinnedExternal => false,
+        ResourcePolicy::PreferNewer => !matches!(
+            (exists.exe_version, RES_VERSION_NUMBER),
+            (Some(local), Some(embedded)) if local > embedded
+        ),
+    }
+}
+
+/// 嵌入资源文件的结构体（exe 以压缩形式嵌入，见 manifest 模块）
+pub static RES_CONF: &[u8] = include_bytes!("../res/config.toml");
+
+/// 被替换的exe的备份子目录名称
+pub const RES_BACKUP_DIR: &str = "backup";
+/// 资源文件被占用时的最大重试次数
+const LOCKED_RETRY_ATTEMPTS: u32 = 5;
+/// 资源文件被占用时的重试间隔（按次数递增）
+const LOCKED_RETRY_DELAY: Duration = Duration::from_millis(400);
+
+/// 释放资源文件时的错误
+#[derive(Debug)]
+pub enum ResourceError {
+    /// 读写、同步或重命名失败
+    Io { path: PathBuf, source: io::Error },
+    /// 写入的数据与资源清单不一致
+    Corrupted {
+        path: PathBuf,
+        size: u64,
+        sha256: String,
+    },
+    /// 目标文件被占用（如 ScreenCapture 正在运行），重试后仍无法替换
+    Locked { path: PathBuf, attempts: u32 },
+}
+impl std::fmt::Display for ResourceError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ResourceError::Io { path, source } => {
+                write!(f, "Failed to write {}: {}", path.display(), source)
             }
+            ResourceError::Corrupted { path, size, sha256 } => write!(
+                f,
+                "{} does not match the embedded manifest ({} bytes, sha256 {})",
+                path.display(),
+                size,
+                sha256
+            ),
+            ResourceError::Locked { path, attempts } => write!(
+                f,
+                "{} is in use and could not be replaced after {} attempts",
+                path.display(),
+                attempts
+            ),
         }
-    };
-    RES_HASH_SHA1.to_ascii_uppercase() == hash_value
+    }
 }
-
-/// 嵌入资源文件的结构体
-static RES_EXE: &[u8] = include_bytes!("../res/ScreenCapture.exe");
-static RES_CONF: &[u8] = include_bytes!("../res/config.toml");
+impl std::error::Error for ResourceError {}
 
 /// 解压并释放资源文件
 ///
 /// ### 参数
 /// * `paths` - 包含所有需要的路径信息
 /// * `exists` - 文件存在状态的检查结果
+/// * `resource` - 资源文件配置
+///
+/// ### 返回值
+/// * `Result<(), ResourceError>` - 任一文件释放失败时返回错误，已存在的文件保持不变
 ///
 /// ### 功能
-/// * 如果exe不存在或不是最新版本，释放exe文件到指定位置
-/// * 如果配置文件不存在，释放配置文件并执行初始化操作
-/// * 首次释放配置文件后会自动打开配置文件并提示重启程序
-pub fn unzip_res(paths: &PathInfos, exists: &FileExist) {
-    if (!exists.exe_exist) || (!exists.exe_latest) {
-        fs::write(&paths.exe_path, RES_EXE).expect("Error write EXE file.");
-        println!("EXE: Release exe file.");
+/// * 如果exe不存在，或按替换策略需要替换，解压exe文件到指定位置；使用外部 ScreenCapture 时跳过
+/// * 替换已有的exe前按需备份到 backup 子目录
+/// * 输出实际使用的 ScreenCapture 版本
+/// * 如果配置文件不存在，释放默认配置文件（应先尝试从备份恢复，见 `recover_config`）
+/// * 示例配置文件不存在或与嵌入的版本不同时重新释放，用户配置文件不受影响
+pub fn unzip_res(
+    paths: &PathInfos,
+    exists: &FileExist,
+    resource: &ResourceConfig,
+) -> Result<(), ResourceError> {
+    println!(
+        "RES: ScreenCapture v{} (sha256 {}), config.toml (sha256 {})",
+        RES_VERSION, RES_EXE_SHA256, RES_CONF_SHA256
+    );
+    let local_version = exists
+        .exe_version
+        .map(format_version)
+        .unwrap_or("unknown".to_string());
+    if let Some(engine) = &paths.engine_path {
+        println!(
+            "EXE: Skipped, using external ScreenCapture at {}.",
+            engine.display()
+        );
+    } else if should_release_exe(exists, resource.policy) {
+        if exists.exe_exist && resource.backup {
+            let backup =
+                backup_exe(paths, exists.exe_version).map_err(|source| ResourceError::Io {
+                    path: paths.dir_path.join(RES_BACKUP_DIR),
+                    source,
+                })?;
+            println!("EXE: Backed up v{} to {}", local_version, backup.display());
+        }
+        let started = Instant::now();
+        write_resource_atomic(
+            &paths.exe_path,
+            DeflateDecoder::new(RES_EXE_DEFLATE),
+            RES_EXE_SIZE,
+            RES_EXE_SHA256,
+        )?;
+        println!(
+            "EXE: Release exe file, using embedded ScreenCapture v{}.",
+            RES_VERSION
+        );
+        if DEBUG {
+            println!(
+                "EXE: Decompressed {} -> {} bytes in {} ms",
+                RES_EXE_DEFLATE.len(),
+                RES_EXE_SIZE,
+                started.elapsed().as_millis()
+            );
+        }
+    } else if exists.exe_latest {
+        println!(
+            "EXE: No need to release, using embedded ScreenCapture v{}.",
+            RES_VERSION
+        );
     } else {
-        println!("EXE: No need to release.");
+        println!(
+            "EXE: Keeping local ScreenCapture v{} (policy {}, embedded v{}).",
+            local_version,
+            resource.policy.key(),
+            RES_VERSION
+        );
     }
     if !exists.conf_exist {
-        fs::write(&paths.conf_path, RES_CONF).expect("Error write config file.");
+        write_resource_atomic(
+            &paths.conf_path,
+            RES_CONF,
+            RES_CONF.len() as u64,
+            RES_CONF_SHA256,
+        )?;
         println!("CONF: Release config file.");
-        open_config(&paths.conf_path);
-        restart_prompt();
     } else {
         println!("CONF: No need to release.");
     }
-    if !exists.conf_example_exist {
-        fs::write(&paths.conf_example_path, RES_CONF).expect("Error write config file.");
-        println!("CONF: Release config example file.");
+    if !exists.conf_example_latest {
+        write_resource_atomic(
+            &paths.conf_example_path,
+            RES_CONF,
+            RES_CONF.len() as u64,
+            RES_CONF_SHA256,
+        )?;
+        println!(
+            "CONF: {} config example file.",
+            if exists.conf_example_exist {
+                "Refresh outdated"
+            } else {
+                "Release"
+            }
+        );
     } else {
-        println!("CONF: No need to release.");
+        println!("CONF: Config example is up to date.");
     }
+    Ok(())
+}
+
+/// 备份即将被替换的exe
+///
+/// ### 参数
+/// - `paths`: 包含所有需要的路径信息
+/// - `version`: exe的文件版本
+///
+/// ### 返回值
+/// - `io::Result<PathBuf>`: 备份文件路径
+///
+/// ### 说明
+/// - 备份为 `backup/ScreenCapture-<版本>.exe`，无法读取版本时以当前时间命名
+/// - 重名时自动追加序号，不覆盖已有备份
+fn backup_exe(paths: &PathInfos, version: Option<[u16; 4]>) -> io::Result<PathBuf> {
+    let dir = paths.dir_path.join(RES_BACKUP_DIR);
+    fs::create_dir_all(&dir)?;
+    let stem = format!(
+        "ScreenCapture-{}",
+        version
+            .map(format_version)
+            .unwrap_or_else(|| chrono::Local::now().format("%Y%m%d%H%M%S").to_string())
+    );
+    let temp = dir.join(".ScreenCapture.exe.part");
+    fs::copy(&paths.exe_path, &temp)?;
+    move_no_clobber(&temp, &dir, &stem, "exe").inspect_err(|_| {
+        let _ = fs::remove_file(&temp);
+    })
+}
+
+/// 以原子方式写入资源文件
+///
+/// ### 参数
+/// - `path`: 目标路径
+/// - `reader`: 数据来源（如解压流）
+/// - `size` / `sha256`: 资源清单中记录的大小与哈希值
+///
+/// ### 返回值
+/// - `Result<(), ResourceError>`: 写入、校验或替换失败时返回错误
+///
+/// ### 功能
+/// 1. 流式写入同目录下的临时文件并计算哈希值
+/// 2. 同步到磁盘后与资源清单比较
+/// 3. 重命名替换目标文件，目标被占用时按递增间隔重试
+///
+/// ### 说明
+/// - 任一步骤失败都会删除临时文件，目标文件保持原状，不会留下截断的文件
+pub fn write_resource_atomic(
+    path: &Path,
+    reader: impl io::Read,
+    size: u64,
+    sha256: &str,
+) -> Result<(), ResourceError> {
+    let file_name = path
+        .file_name()
+        .map(|n| n.to_string_lossy().to_string())
+        .unwrap_or_default();
+    let temp = path.with_file_name(format!(".{}.part", file_name));
+    let io_error = |source| ResourceError::Io {
+        path: path.to_path_buf(),
+        source,
+    };
+
+    let written = fs::File::create(&temp).and_then(|file| {
+        let mut writer = io::BufWriter::new(file);
+        let result = copy_with_sha256(reader, &mut writer)?;
+        writer.into_inner()?.sync_all()?;
+        Ok(result)
+    });
+    let (written_size, written_hash) = match written {
+        Ok(result) => result,
+        Err(e) => {
+            let _ = fs::remove_file(&temp);
+            return Err(io_error(e));
+        }
+    };
+    if written_size != size || !hash_matches(&written_hash, sha256) {
+        let _ = fs::remove_file(&temp);
+        return Err(ResourceError::Corrupted {
+            path: path.to_path_buf(),
+            size: written_size,
+            sha256: written_hash,
+        });
+    }
+
+    for attempt in 1..=LOCKED_RETRY_ATTEMPTS {
+        match fs::rename(&temp, path) {
+            Ok(_) => return Ok(()),
+            Err(e) if is_locked(&e) && attempt < LOCKED_RETRY_ATTEMPTS => {
+                eprintln!(
+                    "{} is in use, retrying ({}/{})...",
+                    path.display(),
+                    attempt,
+                    LOCKED_RETRY_ATTEMPTS
+                );
+                thread::sleep(LOCKED_RETRY_DELAY * attempt);
+            }
+            Err(e) => {
+                let _ = fs::remove_file(&temp);
+                return Err(if is_locked(&e) {
+                    ResourceError::Locked {
+                        path: path.to_path_buf(),
+                        attempts: attempt,
+                    }
+                } else {
+                    io_error(e)
+                });
+            }
+        }
+    }
+    unreachable!("the last attempt always returns")
+}
+
+/// 判断错误是否由文件被占用导致
+///
+/// ### 说明
+/// - ERROR_ACCESS_DENIED (5)：替换正在运行的 exe
+/// - ERROR_SHARING_VIOLATION (32) / ERROR_LOCK_VIOLATION (33)：文件被其它进程打开
+fn is_locked(e: &io::Error) -> bool {
+    matches!(e.raw_os_error(), Some(5 | 32 | 33))
 }
 
 /// 使用记事本打开配置文件
@@ -145,127 +419,260 @@ pub fn open_config(path: &Path) {
     };
 }
 
-/// 显示重启提示并退出程序
+/// 使用系统默认程序打开文件
+/// - `path`: 文件路径
+pub fn open_file(path: &Path) {
+    if let Err(e) = Command::new("explorer.exe").arg(path).spawn() {
+        eprintln!("Failed to open {}: {}", path.display(), e);
+    }
+}
+
+/// 在资源管理器中显示并选中文件
+/// - `path`: 文件路径
+pub fn reveal_in_folder(path: &Path) {
+    let select_arg = format!(r#"/select,"{}""#, path.display());
+    if let Err(e) = Command::new("explorer.exe").raw_arg(select_arg).spawn() {
+        eprintln!("Failed to reveal {}: {}", path.display(), e);
+    }
+}
+
+/// 将文本复制到剪贴板
+/// - `text`: 要复制的文本
+pub fn copy_to_clipboard(text: &str) {
+    if let Err(e) = clipboard_win::set_clipboard_string(text) {
+        eprintln!("Failed to copy to clipboard: {}", e);
+    }
+}
+
+/// 询问是否退出程序，以便修改配置后重新启动
+///
+/// ### 说明
+/// - 选择否时继续以当前配置运行
 pub fn restart_prompt() {
     pause(3);
-    info_msgbox(
-        "Please restart the program to apply your custom settings.",
+    let answer = quest_msgbox_yesno(
+        "Please restart the program to apply your custom settings.\n\nExit now?",
         "Restart",
-        5,
+        0,
     );
-    std::process::exit(0);
+    if answer == IDYES {
+        std::process::exit(0);
+    }
 }
 
 /// 程序操作控制函数
 ///
 /// ### 参数
-/// - `path`: 要执行的程序路径
-/// - `args`: 参数向量，包含所有命令行参数
-/// - `gui`: GUI相关参数的HashMap，包含normal和long模式的参数
-/// - `notification`: 是否显示通知
+/// - `ctx`: 截图任务上下文，包含程序路径与完整配置
+/// - `mode`: 截图模式
+///
+/// ### 返回值
+/// - `JoinHandle<CaptureOutcome>`: 截图线程句柄，可通过 join 获取截图结果
 ///
 /// ### 功能
-/// - 直接将向量中的所有参数传递给程序
-/// - 根据参数中是否包含"long"选择对应的GUI参数
-/// - 自动添加GUI参数（如果非空）
+/// - 根据截图模式生成命令行参数（含GUI与语言参数），引擎不支持该模式时提示并返回取消
+/// - 由截图引擎生成启动命令，并将退出码转换为统一的截图结果
 /// - 异步启动程序，不阻塞主线程
-pub fn execute_process(
-    path: &Path,
-    args: Vec<String>,
-    gui: HashMap<String, String>,
-    notification: bool,
-    lang: bool,
-) {
-    println!("args: {:?}\n", &args);
-
-    let default_empty = String::new();
-
-    // 根据是否包含long参数决定GUI设置
-    let has_long = args.iter().any(|arg| arg.contains("long"));
-
-    let gui_arg = if has_long {
-        gui.get("long").unwrap_or(&default_empty).clone()
-    } else {
-        gui.get("normal").unwrap_or(&default_empty).clone()
-    };
-
-    let path_clone = path.to_path_buf();
-    let lang_arg = if lang { "--lang:cn" } else { "--lang:en" };
+/// - 处理输出文件（如按模板重命名、转换格式）
+/// - 检测重复截图，写入截图元数据并上传，按配置自动复制为 Markdown 等文本片段
+/// - 记录截图历史并执行匹配的截图后钩子
+/// - 按模板显示通知（钉图模式不通知）
+/// - 将截图复制到镜像目录
+pub fn execute_process(ctx: &CaptureContext, mode: CaptureMode) -> JoinHandle<CaptureOutcome> {
+    // 前台窗口需在截图线程启动前获取；检查保存目录可能被断开的网络共享阻塞，放到线程中进行
+    let origin = CaptureOrigin::now();
+    let ctx = ctx.clone();
 
     std::thread::spawn(move || {
-        let mut command = std::process::Command::new(path_clone);
+        let mut job = CaptureJob::new(&ctx, mode, origin);
+        println!(
+            "program: {}\nargs: {:?}\n",
+            job.program.display(),
+            &job.args
+        );
+        if !ctx.engine.supports(mode) {
+            let (title, tip) = if ctx.settings.sundry.lang {
+                ("SC_Starter 不支持的截图模式", "当前 ScreenCapture 不支持")
+            } else {
+                (
+                    "SC_Starter Unsupported Mode",
+                    "The current ScreenCapture does not support",
+                )
+            };
+            notify_msgbox_standalone(title, format!("{}: {}", tip, mode), 3000);
+            wait_notifications();
+            return CaptureOutcome::Cancelled;
+        }
+        if let Some(error) = &job.save_dir_error {
+            let (title, tip) = if ctx.settings.sundry.lang {
+                ("SC_Starter 保存路径不可用", "本次截图改为手动选择保存位置")
+            } else {
+                (
+                    "SC_Starter Save Path Unavailable",
+                    "Choose where to save this capture manually",
+                )
+            };
+            notify_msgbox_standalone(title, format!("{}\n{}", error, tip), 5000);
+        }
 
-        for arg in &args {
+        let mut command = std::process::Command::new(&job.program);
+
+        for arg in &job.args {
             if !arg.is_empty() {
                 command.arg(arg);
             }
         }
 
-        if !gui_arg.is_empty() {
-            command.arg(gui_arg);
+        let status = command.status();
+        job.finished = Some(chrono::Local::now());
+        let outcome = match status {
+            Ok(status) => {
+                println!("Exit code: {:?}", status.code());
+                ctx.engine.outcome(&job, status.code())
+            }
+            Err(e) => {
+                eprintln!("Failed to execute command: {}", e);
+                CaptureOutcome::Crashed(None)
+            }
+        };
+        println!("Outcome: {}", outcome);
+
+        let saved =
+            finish_capture(&ctx, &job, &outcome).map(|file| postprocess_capture(&ctx, &job, &file));
+        let dedupe = saved.as_deref().map(|file| check_duplicate(&ctx, file));
+        let output = match &dedupe {
+            Some(result) => result.output.clone(),
+            None => saved.clone(),
+        };
+        // 重复截图被删除或替换为硬链接时沿用先前截图的元数据
+        let linked = dedupe.as_ref().is_some_and(|d| d.linked);
+        if let Some(file) = output.as_deref()
+            && saved.as_deref() == Some(file)
+            && !linked
+        {
+            write_capture_metadata(&ctx, &job, file);
+        }
+        // 上传可能因重试耗时较长，未启用时立即记录最近截图并复制文本片段
+        let uploading = ctx.settings.upload.enabled && output.is_some();
+        if let Some(file) = output.as_deref()
+            && !uploading
+        {
+            set_last_capture(file, None);
+            if ctx.settings.copy_as.auto {
+                copy_capture_as(&ctx, file, None, &ctx.settings.copy_as.default_format);
+            }
         }
+        run_post_capture_hooks(&ctx, &job, &outcome, output.as_deref());
 
-        command.arg(lang_arg);
+        let notification = ctx.settings.sundry.notification && mode != CaptureMode::Pin;
+        if notification && let Some(text) = ctx.settings.notify.render(&outcome) {
+            notify_msgbox_standalone("SC_Starter", text, 2000);
+        }
 
-        match command.status() {
-            Ok(status) => {
-                if let Some(code) = status.code() {
-                    println!("Exit code: {}", code);
-                    if notification {
-                        if code == 8 {
-                            notify_msgbox_standalone("SC_Starter", "已保存到文件", 2000);
-                        } else if code == 9 {
-                            notify_msgbox_standalone("SC_Starter", "已保存到剪贴板", 2000);
-                        }
-                    }
-                }
+        // 上传与镜像都可能较慢，放在通知之后执行，不推迟钩子与通知
+        let upload = output
+            .as_deref()
+            .and_then(|file| upload_capture(&ctx, file));
+        if let Some(file) = output.as_deref()
+            && uploading
+        {
+            let url = upload.as_ref().and_then(|u| u.url.as_deref());
+            set_last_capture(file, url);
+            if ctx.settings.copy_as.auto {
+                copy_capture_as(&ctx, file, url, &ctx.settings.copy_as.default_format);
             }
-            Err(e) => eprintln!("Failed to execute command: {}", e),
+        }
+        record_capture(
+            &ctx,
+            &job,
+            &outcome,
+            output.as_deref(),
+            dedupe.as_ref(),
+            upload.as_ref(),
+        );
+
+        // 重复截图被删除时不再镜像
+        if let Some(file) = output.as_deref()
+            && saved.as_deref() == Some(file)
+        {
+            mirror_capture(&ctx, file);
         }
         wait_notifications();
-    });
+        outcome
+    })
 }
 
 /// 构建截图操作的命令行参数
 ///
 /// ### 参数
-/// - `comp_level`: 压缩级别
-/// - `scale_level`: 缩放级别
-/// - `save_path`: 保存路径
-/// - `is_long`: 是否为长截图模式
+/// - `settings`: 完整配置集合
+/// - `engine`: 截图引擎支持的命令行参数
+/// - `mode`: 截图模式
+/// - `save_dir`: 本次截图的保存目录
 ///
 /// ### 返回值
 /// - `Vec<String>`: 格式化后的命令行参数列表
+///
+/// ### 说明
+/// - 区域截图与钉图使用normal GUI参数，长截图使用long GUI参数，全屏截图不附加GUI参数
+/// - 最后附加语言参数
+/// - 省略引擎不支持的可选参数（压缩、保存路径、工具栏、语言）
 pub fn build_capture_args(
-    comp_level: i32,
-    scale_level: i32,
-    save_path: &std::path::PathBuf,
-    is_long: bool,
+    settings: &SettingsCollection,
+    engine: &EngineCapabilities,
+    mode: CaptureMode,
+    save_dir: &std::path::PathBuf,
 ) -> Vec<String> {
-    let mut args = Vec::new();
-    if is_long {
-        args.push("--cap:long".to_string());
+    let comp = if engine.comp {
+        format!(
+            "--comp:{},{}",
+            settings.sundry.comp_level, settings.sundry.scale_level
+        )
+    } else {
+        String::new()
+    };
+    let gui = |key: &str| {
+        if engine.tool {
+            settings.gui.get(key).cloned().unwrap_or_default()
+        } else {
+            String::new()
+        }
+    };
+    let save_path = || {
+        if engine.save_path {
+            save_path_get(save_dir)
+        } else {
+            String::new()
+        }
+    };
+
+    let mut args = match mode {
+        CaptureMode::Region => vec![comp, save_path(), gui("normal")],
+        CaptureMode::Long => vec!["--cap:long".to_string(), comp, save_path(), gui("long")],
+        CaptureMode::Fullscreen => vec!["--cap:fullscreen".to_string(), save_path()],
+        CaptureMode::Pin => vec!["--pin:clipboard".to_string(), gui("normal")],
+    };
+    if engine.lang {
+        args.push(
+            if settings.sundry.lang {
+                "--lang:cn"
+            } else {
+                "--lang:en"
+            }
+            .to_string(),
+        );
     }
-    args.push(format!("--comp:{},{}", comp_level, scale_level));
-    args.push(crate::hotkeys::save_path_get(save_path));
+    args.retain(|arg| !arg.is_empty());
     args
 }
 
 /// 在新线程中异步启动截图程序
 ///
 /// ### 参数
-/// - `exe_path`: 截图程序路径
-/// - `args`: 命令行参数
-/// - `gui`: GUI配置参数
-/// - `lang`: 语言设置
-pub fn spawn_capture(
-    exe_path: &std::path::Path,
-    args: Vec<String>,
-    gui: std::collections::HashMap<String, String>,
-    notification: bool,
-    lang: bool,
-) {
-    execute_process(exe_path, args, gui, notification, lang);
+/// - `ctx`: 截图任务上下文
+/// - `mode`: 截图模式
+pub fn spawn_capture(ctx: &CaptureContext, mode: CaptureMode) {
+    execute_process(ctx, mode);
 }
 
 pub fn pause<T>(n: T)
@@ -274,85 +681,3 @@ where
 {
     std::thread::sleep(std::time::Duration::from_secs_f64(n.into()));
 }
-
-/// 监控并保护核心文件
-///
-/// ### 参数
-/// - `paths`: 包含所有需要保护的文件路径
-///
-/// ### 返回值
-/// - `Arc<AtomicBool>`: 线程运行状态的原子布尔值，可用于外部控制监控线程的停止
-///
-/// ### 功能
-/// - 启动后台监控线程，每5秒检查一次核心文件状态
-/// - 当检测到exe文件或配置文件丢失时，自动尝试恢复
-/// - 使用双线程架构：监控线程负责检测，处理线程负责恢复
-/// - 恢复成功后继续监控，恢复失败则终止程序
-/// - 通过mpsc通道进行线程间通信，确保线程安全
-pub fn avoid_exe_del(paths: &PathInfos) -> Arc<AtomicBool> {
-    let running = Arc::new(AtomicBool::new(true));
-    let r = running.clone();
-
-    // 创建两个通道：文件丢失通知和恢复结果通知
-    let (tx_lost, rx_lost) = mpsc::channel();
-    let (tx_recovered, rx_recovered) = mpsc::channel();
-
-    // 创建监控线程
-    let paths_clone = paths.clone();
-    thread::spawn(move || {
-        while r.load(Ordering::SeqCst) {
-            if !paths_clone.conf_path.exists() || !paths_clone.exe_path.exists() {
-                // 发送文件丢失信号
-                let _ = tx_lost.send(());
-                // 等待恢复完成信号
-                match rx_recovered.recv() {
-                    Ok(true) => {
-                        // 恢复成功，继续下一轮循环
-                        println!("Files recovered, resuming monitoring...");
-                    }
-                    _ => {
-                        // 恢复失败或通道关闭，退出线程
-                        eprintln!("Critical files missing and recovery failed, stopping monitor.");
-                        break;
-                    }
-                }
-            }
-            pause(3);
-        }
-    });
-
-    // 创建处理线程
-    let paths_clone = paths.clone();
-    let r2 = running.clone();
-    thread::spawn(move || {
-        while let Ok(()) = rx_lost.recv() {
-            println!("Attempting to recover files...");
-
-            // 文件丢失时尝试恢复（使用 catch_unwind 防止级联崩溃）
-            let recover_result = std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| {
-                let files_status = check_res_exist(&paths_clone);
-                unzip_res(&paths_clone, &files_status);
-            }));
-
-            if recover_result.is_err() {
-                eprintln!("Recovery panicked, sending failure signal.");
-                running.store(false, Ordering::SeqCst);
-                let _ = tx_recovered.send(false);
-                continue;
-            }
-
-            // 重新检查恢复结果
-            let final_status = check_res_exist(&paths_clone);
-            if final_status.exe_exist && final_status.conf_exist && final_status.exe_latest {
-                // 恢复成功，发送成功信号
-                let _ = tx_recovered.send(true);
-            } else {
-                // 恢复失败，终止程序
-                running.store(false, Ordering::SeqCst);
-                let _ = tx_recovered.send(false);
-            }
-        }
-    });
-
-    r2
-}
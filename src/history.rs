@@ -0,0 +1,353 @@
+//! 截图历史模块
+//!
+//! 本模块负责：
+//! - 以 JSON Lines 格式追加记录每次截图
+//! - 读取历史记录供托盘菜单与命令行使用
+//! - 按条数与保留天数压缩历史文件
+//! - 通知主循环历史记录已更新
+
+use crate::capture::CaptureJob;
+use crate::dedupe::DedupeResult;
+use crate::types::*;
+use crate::upload::UploadResult;
+use chrono::{DateTime, Local};
+use serde_json::{Value, json};
+use std::{
+    fs,
+    io::{self, Write},
+    path::{Path, PathBuf},
+    sync::{
+        Mutex,
+        atomic::{AtomicU64, Ordering},
+    },
+};
+
+/// 历史记录文件名（位于程序根目录）
+pub const HISTORY_FILE: &str = "history.jsonl";
+/// 每写入多少条记录压缩一次历史文件
+const COMPACT_INTERVAL: u64 = 100;
+
+/// 历史文件写入锁，防止多个截图线程同时写入
+static HISTORY_LOCK: Mutex<()> = Mutex::new(());
+/// 历史记录版本号，每次写入后递增，主循环据此刷新托盘菜单
+static HISTORY_GENERATION: AtomicU64 = AtomicU64::new(0);
+
+/// 单条截图历史记录
+#[derive(Clone, Debug)]
+pub struct HistoryEntry {
+    /// 截图开始时间
+    pub timestamp: DateTime<Local>,
+    /// 截图模式
+    pub mode: String,
+    /// 传递给截图程序的参数
+    pub args: Vec<String>,
+    /// 截图结果
+    pub outcome: String,
+    /// 截图程序退出码
+    pub code: Option<i32>,
+    /// 最终输出文件
+    pub path: Option<PathBuf>,
+    /// 截图耗时（毫秒），从开始截图到截图程序退出
+    pub duration_ms: u64,
+    /// 截图时的配置名
+    pub profile: String,
+    /// 输出文件的内容哈希（SHA-256，不含 PNG 元数据文本块，启用重复检测时记录）
+    pub sha256: Option<String>,
+    /// 输出文件的感知哈希（启用重复检测时记录）
+    pub dhash: Option<u64>,
+    /// 与之重复的先前截图
+    pub duplicate_of: Option<PathBuf>,
+    /// 上传后返回的链接
+    pub upload_url: Option<String>,
+    /// 上传失败原因
+    pub upload_error: Option<String>,
+}
+impl HistoryEntry {
+    /// 根据截图任务生成历史记录
+    ///
+    /// ### 参数
+    /// - `ctx`: 截图任务上下文
+    /// - `job`: 本次截图任务信息
+    /// - `outcome`: 截图结果
+    /// - `path`: 最终输出文件
+    /// - `dedupe`: 重复检测结果
+    /// - `upload`: 上传结果
+    pub fn from_job(
+        ctx: &CaptureContext,
+        job: &CaptureJob,
+        outcome: &CaptureOutcome,
+        path: Option<&Path>,
+        dedupe: Option<&DedupeResult>,
+        upload: Option<&UploadResult>,
+    ) -> Self {
+        let fingerprint = dedupe.and_then(|d| d.fingerprint.as_ref());
+        HistoryEntry {
+            timestamp: job.started,
+            mode: job.mode.key().to_string(),
+            args: job.args.clone(),
+            outcome: outcome.key().to_string(),
+            code: outcome.code(),
+            path: path.map(Path::to_path_buf),
+            duration_ms: (job.finished.unwrap_or_else(Local::now) - job.started)
+                .num_milliseconds()
+                .max(0) as u64,
+            profile: ctx.settings.sundry.profile.clone(),
+            sha256: fingerprint.map(|f| f.sha256.clone()),
+            dhash: fingerprint.and_then(|f| f.dhash),
+            duplicate_of: dedupe.and_then(|d| d.duplicate_of.clone()),
+            upload_url: upload.and_then(|u| u.url.clone()),
+            upload_error: upload.and_then(|u| u.error.clone()),
+        }
+    }
+
+    /// 转换为 JSON 对象
+    pub fn to_json(&self) -> Value {
+        json!({
+            "timestamp": self.timestamp.to_rfc3339(),
+            "mode": self.mode,
+            "args": self.args,
+            "outcome": self.outcome,
+            "code": self.code,
+            "path": self.path.as_ref().map(|p| p.display().to_string()),
+            "duration_ms": self.duration_ms,
+            "profile": self.profile,
+            "sha256": self.sha256,
+            "dhash": self.dhash.map(|h| format!("{:016x}", h)),
+            "duplicate_of": self.duplicate_of.as_ref().map(|p| p.display().to_string()),
+            "upload_url": self.upload_url,
+            "upload_error": self.upload_error,
+        })
+    }
+
+    /// 从一行 JSON 解析历史记录，格式错误时返回 `None`
+    pub fn from_json(line: &str) -> Option<Self> {
+        let value: Value = serde_json::from_str(line).ok()?;
+        let text = |key: &str| value.get(key).and_then(|v| v.as_str()).map(String::from);
+        Some(HistoryEntry {
+            timestamp: DateTime::parse_from_rfc3339(&text("timestamp")?)
+                .ok()?
+                .with_timezone(&Local),
+            mode: text("mode")?,
+            args: value
+                .get("args")
+                .and_then(|v| v.as_array())
+                .map(|items| {
+                    items
+                        .iter()
+                        .filter_map(|v| v.as_str().map(String::from))
+                        .collect()
+                })
+                .unwrap_or_default(),
+            outcome: text("outcome")?,
+            code: value.get("code").and_then(|v| v.as_i64()).map(|c| c as i32),
+            path: text("path").map(PathBuf::from),
+            duration_ms: value
+                .get("duration_ms")
+                .and_then(|v| v.as_u64())
+                .unwrap_or(0),
+            profile: text("profile").unwrap_or_default(),
+            sha256: text("sha256"),
+            dhash: text("dhash").and_then(|h| u64::from_str_radix(&h, 16).ok()),
+            duplicate_of: text("duplicate_of").map(PathBuf::from),
+            upload_url: text("upload_url"),
+            upload_error: text("upload_error"),
+        })
+    }
+}
+
+/// 历史记录文件路径
+pub fn history_path(dir_path: &Path) -> PathBuf {
+    dir_path.join(HISTORY_FILE)
+}
+
+/// 当前历史记录版本号
+pub fn history_generation() -> u64 {
+    HISTORY_GENERATION.load(Ordering::SeqCst)
+}
+
+/// 追加一条历史记录
+///
+/// ### 参数
+/// - `dir_path`: 程序根目录
+/// - `entry`: 历史记录
+///
+/// ### 返回值
+/// - `io::Result<()>`: 写入结果
+pub fn append_history(dir_path: &Path, entry: &HistoryEntry) -> io::Result<()> {
+    let _guard = HISTORY_LOCK.lock().unwrap_or_else(|e| e.into_inner());
+    let mut file = fs::OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(history_path(dir_path))?;
+    writeln!(file, "{}", entry.to_json())?;
+    HISTORY_GENERATION.fetch_add(1, Ordering::SeqCst);
+    Ok(())
+}
+
+/// 记录本次截图（历史记录关闭时不做任何事），并定期压缩历史文件
+///
+/// ### 参数
+/// - `ctx`: 截图任务上下文
+/// - `job`: 本次截图任务信息
+/// - `outcome`: 截图结果
+/// - `path`: 最终输出文件
+/// - `dedupe`: 重复检测结果
+/// - `upload`: 上传结果
+pub fn record_capture(
+    ctx: &CaptureContext,
+    job: &CaptureJob,
+    outcome: &CaptureOutcome,
+    path: Option<&Path>,
+    dedupe: Option<&DedupeResult>,
+    upload: Option<&UploadResult>,
+) {
+    if !ctx.settings.history.enabled {
+        return;
+    }
+    let entry = HistoryEntry::from_job(ctx, job, outcome, path, dedupe, upload);
+    if let Err(e) = append_history(&ctx.paths.dir_path, &entry) {
+        eprintln!("Failed to write history: {}", e);
+    }
+    // 长时间运行时定期压缩，避免历史文件无限增长
+    if history_generation().is_multiple_of(COMPACT_INTERVAL)
+        && let Err(e) = compact_history(&ctx.paths.dir_path, &ctx.settings.history)
+    {
+        eprintln!("Failed to compact history: {}", e);
+    }
+}
+
+/// 读取全部历史记录（按时间从旧到新）
+///
+/// ### 参数
+/// - `dir_path`: 程序根目录
+///
+/// ### 返回值
+/// - `Vec<HistoryEntry>`: 历史记录，文件不存在时为空，格式错误的行会被跳过
+pub fn load_history(dir_path: &Path) -> Vec<HistoryEntry> {
+    match fs::read_to_string(history_path(dir_path)) {
+        Ok(content) => content
+            .lines()
+            .filter_map(HistoryEntry::from_json)
+            .collect(),
+        Err(_) => Vec::new(),
+    }
+}
+
+/// 获取最近保存的截图文件
+///
+/// ### 参数
+/// - `dir_path`: 程序根目录
+/// - `count`: 最多返回的数量
+///
+/// ### 返回值
+/// - `Vec<PathBuf>`: 仍然存在的截图文件，按时间从新到旧排列且不重复
+pub fn recent_files(dir_path: &Path, count: usize) -> Vec<PathBuf> {
+    let mut files: Vec<PathBuf> = Vec::new();
+    for entry in load_history(dir_path).into_iter().rev() {
+        if files.len() >= count {
+            break;
+        }
+        if let Some(path) = entry.path
+            && path.is_file()
+            && !files.contains(&path)
+        {
+            files.push(path);
+        }
+    }
+    files
+}
+
+/// 按配置压缩历史记录
+///
+/// ### 参数
+/// - `dir_path`: 程序根目录
+/// - `config`: 历史记录配置
+///
+/// ### 返回值
+/// - `io::Result<()>`: 压缩结果
+///
+/// ### 功能
+/// - 删除超过保留天数的记录（保留天数为 0 时不按时间删除）
+/// - 仅保留最新的 max_entries 条记录
+/// - 先写入临时文件再替换，避免中断时丢失历史
+pub fn compact_history(dir_path: &Path, config: &HistoryConfig) -> io::Result<()> {
+    let _guard = HISTORY_LOCK.lock().unwrap_or_else(|e| e.into_inner());
+    let path = history_path(dir_path);
+    if !path.exists() {
+        return Ok(());
+    }
+
+    let content = fs::read_to_string(&path)?;
+    let total = content.lines().count();
+    let cutoff = Local::now() - chrono::Duration::days(config.retention_days as i64);
+    let mut kept: Vec<HistoryEntry> = content
+        .lines()
+        .filter_map(HistoryEntry::from_json)
+        .filter(|entry| config.retention_days == 0 || entry.timestamp >= cutoff)
+        .collect();
+    if kept.len() > config.max_entries {
+        kept.drain(..kept.len() - config.max_entries);
+    }
+    if kept.len() == total {
+        return Ok(());
+    }
+
+    let temp_path = path.with_extension("jsonl.tmp");
+    let mut output = String::new();
+    for entry in &kept {
+        output.push_str(&entry.to_json().to_string());
+        output.push('\n');
+    }
+    fs::write(&temp_path, output)?;
+    fs::rename(&temp_path, &path)?;
+    println!("History compacted: {} -> {} entries", total, kept.len());
+    HISTORY_GENERATION.fetch_add(1, Ordering::SeqCst);
+    Ok(())
+}
+
+/// 历史记录筛选条件
+/// 各条件之间为“与”关系，列表内的多个取值为“或”关系
+#[derive(Clone, Debug, Default)]
+pub struct HistoryFilter {
+    /// 起始时间（含）
+    pub since: Option<DateTime<Local>>,
+    /// 结束时间（不含）
+    pub until: Option<DateTime<Local>>,
+    /// 截图模式
+    pub modes: Vec<String>,
+    /// 截图结果
+    pub outcomes: Vec<String>,
+    /// 配置名
+    pub profiles: Vec<String>,
+    /// 输出文件路径的通配符模式（不区分大小写）
+    pub path_pattern: Option<glob::Pattern>,
+}
+impl HistoryFilter {
+    /// 判断历史记录是否满足筛选条件
+    pub fn matches(&self, entry: &HistoryEntry) -> bool {
+        let in_list = |list: &Vec<String>, value: &str| {
+            list.is_empty() || list.iter().any(|item| item.eq_ignore_ascii_case(value))
+        };
+        let path_ok = match &self.path_pattern {
+            Some(pattern) => entry
+                .path
+                .as_ref()
+                .map(|p| {
+                    let options = glob::MatchOptions {
+                        case_sensitive: false,
+                        ..glob::MatchOptions::new()
+                    };
+                    pattern.matches_with(&p.display().to_string().replace('\\', "/"), options)
+                })
+                .unwrap_or(false),
+            None => true,
+        };
+
+        self.since.is_none_or(|since| entry.timestamp >= since)
+            && self.until.is_none_or(|until| entry.timestamp < until)
+            && in_list(&self.modes, &entry.mode)
+            && in_list(&self.outcomes, &entry.outcome)
+            && in_list(&self.profiles, &entry.profile)
+            && path_ok
+    }
+}
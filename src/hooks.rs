@@ -0,0 +1,149 @@
+//! 截图后钩子模块
+//!
+//! 本模块负责：
+//! - 按配置顺序执行 `[[hooks.post_capture]]` 中的外部命令
+//! - 展开参数与工作目录中的占位符
+//! - 控制钩子超时并汇总报告失败
+
+use crate::capture::CaptureJob;
+use crate::types::*;
+use std::{
+    os::windows::process::CommandExt,
+    path::{Path, PathBuf},
+    process::{Command, Stdio},
+    time::Instant,
+};
+use win_msgbox_timeout::notify_msgbox_standalone;
+
+/// 轮询钩子进程状态的间隔
+const HOOK_POLL: std::time::Duration = std::time::Duration::from_millis(50);
+
+/// 执行所有匹配当前截图结果的钩子
+///
+/// ### 参数
+/// - `ctx`: 截图任务上下文
+/// - `job`: 本次截图任务信息
+/// - `outcome`: 截图结果
+/// - `file`: 最终的截图文件路径（未保存到文件时为 `None`）
+///
+/// ### 功能
+/// - 依次执行匹配退出码过滤条件的钩子
+/// - 单个钩子失败不影响后续钩子
+/// - 所有失败信息汇总后以非阻塞通知显示
+/// - 在截图线程中运行，不阻塞后续截图
+pub fn run_post_capture_hooks(
+    ctx: &CaptureContext,
+    job: &CaptureJob,
+    outcome: &CaptureOutcome,
+    file: Option<&Path>,
+) {
+    let hooks: Vec<&HookConfig> = ctx
+        .settings
+        .hooks
+        .iter()
+        .filter(|hook| hook.matches(outcome))
+        .collect();
+    if hooks.is_empty() {
+        return;
+    }
+
+    let dir = file
+        .and_then(|f| f.parent())
+        .map(Path::to_path_buf)
+        .unwrap_or_else(|| job.save_dir.clone());
+    let vars = HookVars {
+        file: file.map(|f| f.display().to_string()).unwrap_or_default(),
+        dir: dir.display().to_string(),
+        mode: job.mode.key().to_string(),
+        outcome: outcome.key().to_string(),
+        code: outcome.code().map(|c| c.to_string()).unwrap_or_default(),
+    };
+
+    let mut errors: Vec<String> = Vec::new();
+    for hook in hooks {
+        println!("Hook: {}", hook.command);
+        if let Err(e) = run_hook(hook, &vars) {
+            let error_message = format!("{}: {}", hook.command, e);
+            eprintln!("Hook failed: {}", error_message);
+            errors.push(error_message);
+        }
+    }
+
+    if !errors.is_empty() {
+        let title = if ctx.settings.sundry.lang {
+            "SC_Starter 钩子执行失败"
+        } else {
+            "SC_Starter Hook Failed"
+        };
+        notify_msgbox_standalone(title, errors.join("\n"), 5000);
+    }
+}
+
+/// 钩子占位符对应的值
+struct HookVars {
+    file: String,
+    dir: String,
+    mode: String,
+    outcome: String,
+    code: String,
+}
+impl HookVars {
+    /// 展开字符串中的钩子占位符
+    fn expand(&self, template: &str) -> String {
+        expand_placeholders(template, |name, _| match name {
+            "file" => Some(self.file.clone()),
+            "dir" => Some(self.dir.clone()),
+            "mode" => Some(self.mode.clone()),
+            "outcome" => Some(self.outcome.clone()),
+            "code" => Some(self.code.clone()),
+            _ => None,
+        })
+    }
+}
+
+/// 执行单个钩子
+///
+/// ### 参数
+/// - `hook`: 钩子配置
+/// - `vars`: 占位符取值
+///
+/// ### 返回值
+/// - `Result<(), String>`: 钩子以退出码 0 结束时返回 `Ok`，否则返回失败原因
+///
+/// ### 说明
+/// - 钩子进程不显示控制台窗口
+/// - 超过配置的超时时间后强制终止
+fn run_hook(hook: &HookConfig, vars: &HookVars) -> Result<(), String> {
+    let mut command = Command::new(&hook.command);
+    command
+        .args(hook.args.iter().map(|arg| vars.expand(arg)))
+        .stdin(Stdio::null())
+        .stdout(Stdio::null())
+        .stderr(Stdio::null())
+        .creation_flags(0x08000000); // CREATE_NO_WINDOW - 隐藏命令行窗口
+
+    let cwd = if hook.cwd.is_empty() {
+        PathBuf::from(&vars.dir)
+    } else {
+        PathBuf::from(vars.expand(&hook.cwd))
+    };
+    if cwd.is_dir() {
+        command.current_dir(&cwd);
+    }
+
+    let mut child = command.spawn().map_err(|e| e.to_string())?;
+    let deadline = Instant::now() + hook.timeout;
+    loop {
+        match child.try_wait() {
+            Ok(Some(status)) if status.success() => return Ok(()),
+            Ok(Some(status)) => return Err(format!("exit code {:?}", status.code())),
+            Ok(None) if Instant::now() >= deadline => {
+                let _ = child.kill();
+                let _ = child.wait();
+                return Err(format!("timed out after {}s", hook.timeout.as_secs()));
+            }
+            Ok(None) => std::thread::sleep(HOOK_POLL),
+            Err(e) => return Err(e.to_string()),
+        }
+    }
+}
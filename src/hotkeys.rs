@@ -6,6 +6,7 @@
 //! - 管理快捷键线程
 //! - 管理启动应用程序的进程状态
 
+use crate::copy_as::spawn_copy_last;
 use crate::file_ops::{execute_process, open_config};
 use crate::types::*;
 use crate::window_handle::{is_process_running, set_window_topmost_by_pid};
@@ -25,8 +26,7 @@ use windows_hotkeys::{HotkeyManagerImpl, singlethreaded::HotkeyManager};
 /// 设置全局快捷键并返回事件发送器
 ///
 /// ### 参数
-/// - `paths`: 程序路径信息
-/// - `settings_collected`: 配置集合，包含快捷键设置
+/// - `ctx`: 截图任务上下文，包含程序路径与配置集合
 ///
 /// ### 返回值
 /// - `(JoinHandle<()>, mpsc::Sender<()>)`: 快捷键线程句柄和退出信号发送器
@@ -36,44 +36,28 @@ use windows_hotkeys::{HotkeyManagerImpl, singlethreaded::HotkeyManager};
 /// - 创建快捷键事件处理循环
 /// - 返回线程控制接口
 pub fn set_hotkeys(
-    paths: &PathInfos,
-    settings_collected: &SettingsCollection,
+    ctx: &CaptureContext,
     running: Arc<AtomicBool>,
     exit_proxy: EventLoopProxy<()>,
 ) -> (JoinHandle<()>, mpsc::Sender<()>) {
-    let settings_collected = settings_collected.clone();
+    let ctx = ctx.clone();
     let (exit_tx, exit_rx) = mpsc::channel();
 
-    let exe_path = paths.exe_path.clone();
-    let save_path = settings_collected.path.save_path.clone();
-    let launch = settings_collected.path.launch_app.clone();
-    let conf_path = paths.conf_path.clone();
-    let gui = settings_collected.gui.clone();
+    let launch = ctx.settings.path.launch_app.clone();
+    let conf_path = ctx.paths.conf_path.clone();
 
     let handle = thread::spawn(move || {
-        let key_groups = settings_collected.keys_collection;
+        let key_groups = ctx.settings.keys_collection.clone();
         let mut hkm = HotkeyManager::new();
 
-        let comp_val = settings_collected.sundry.comp_level;
-        let scale_val = settings_collected.sundry.scale_level;
-        let notification = settings_collected.sundry.notification;
-        let lang = settings_collected.sundry.lang;
-        let exe_path_clone = exe_path.clone();
-        let save_path_clone = save_path.clone();
-        let gui_clone = gui.clone();
+        let ctx_clone = ctx.clone();
 
         // 注册截屏快捷键
         let hotkey_sc = hkm.register(
             key_groups.get("screen_capture").unwrap().vkey,
             &key_groups.get("screen_capture").unwrap().mod_keys,
             move || {
-                let args = crate::file_ops::build_capture_args(
-                    comp_val,
-                    scale_val,
-                    &save_path_clone,
-                    false,
-                );
-                execute_process(&exe_path_clone, args, gui_clone.clone(), notification, lang);
+                execute_process(&ctx_clone, CaptureMode::Region);
             },
         );
         if hotkey_sc.is_err() {
@@ -170,31 +154,14 @@ pub fn set_hotkeys(
             };
         }
 
-        let comp_val2 = settings_collected.sundry.comp_level;
-        let scale_val2 = settings_collected.sundry.scale_level;
-        let notification2 = settings_collected.sundry.notification;
-        let exe_path_clone = exe_path.clone();
-        let gui_clone = gui.clone();
-        let save_path_clone = save_path.clone();
+        let ctx_clone = ctx.clone();
 
         // 注册截长屏快捷键
         let hotkey_scl = hkm.register(
             key_groups.get("screen_capture_long").unwrap().vkey,
             &key_groups.get("screen_capture_long").unwrap().mod_keys,
             move || {
-                let args = crate::file_ops::build_capture_args(
-                    comp_val2,
-                    scale_val2,
-                    &save_path_clone,
-                    true,
-                );
-                execute_process(
-                    &exe_path_clone,
-                    args,
-                    gui_clone.clone(),
-                    notification2,
-                    lang,
-                );
+                execute_process(&ctx_clone, CaptureMode::Long);
             },
         );
         if hotkey_scl.is_err() {
@@ -203,21 +170,14 @@ pub fn set_hotkeys(
             panic!("{}", &temp);
         };
 
-        let exe_path_clone = exe_path.clone();
-        let gui_clone = gui.clone();
+        let ctx_clone = ctx.clone();
 
         // 注册钉图快捷键
         let hotkey_pin = hkm.register(
             key_groups.get("pin_to_screen").unwrap().vkey,
             &key_groups.get("pin_to_screen").unwrap().mod_keys,
             move || {
-                execute_process(
-                    &exe_path_clone,
-                    vec!["--pin:clipboard".to_string()],
-                    gui_clone.clone(),
-                    false,
-                    lang,
-                );
+                execute_process(&ctx_clone, CaptureMode::Pin);
             },
         );
         if hotkey_pin.is_err() {
@@ -226,6 +186,21 @@ pub fn set_hotkeys(
             panic!("{}", &temp);
         };
 
+        let ctx_clone = ctx.clone();
+
+        // 注册复制最近截图快捷键（附加功能，注册失败时不退出程序，也不弹出提示）
+        let hotkey_copy = hkm.register(
+            key_groups.get("copy_as").unwrap().vkey,
+            &key_groups.get("copy_as").unwrap().mod_keys,
+            move || {
+                spawn_copy_last(&ctx_clone, &ctx_clone.settings.copy_as.default_format);
+            },
+        );
+        // 组合键可能已被其它程序占用，只记录日志，避免每次启动都弹出提示
+        if let Err(e) = hotkey_copy {
+            eprintln!("Failed reg Hotkey copy_as: {:?}", e);
+        }
+
         // 注册设置快捷键
         let hotkey_conf = hkm.register(
             key_groups.get("open_conf").unwrap().vkey,
@@ -0,0 +1,125 @@
+//! 完整性校验模块
+//!
+//! 本模块负责：
+//! - 在进程内计算文件与数据的 SHA-256（不再依赖 certutil）
+//! - 在复制（如解压）数据的同时计算哈希值
+//! - 比较哈希值，供资源检查、重复截图检测等共用
+
+use sha2::{Digest, Sha256};
+use std::{
+    fs,
+    io::{self, Read, Write},
+    path::Path,
+};
+
+/// 以流式读取计算 SHA-256
+///
+/// ### 参数
+/// - `reader`: 数据来源
+///
+/// ### 返回值
+/// - `io::Result<String>`: 小写十六进制哈希值
+pub fn sha256_reader(reader: impl Read) -> io::Result<String> {
+    copy_with_sha256(reader, io::sink()).map(|(_, hash)| hash)
+}
+
+/// 流式复制数据并同时计算 SHA-256
+///
+/// ### 参数
+/// - `reader`: 数据来源（如解压流）
+/// - `writer`: 写入目标
+///
+/// ### 返回值
+/// - `io::Result<(u64, String)>`: 复制的字节数与小写十六进制哈希值
+pub fn copy_with_sha256(
+    mut reader: impl Read,
+    mut writer: impl Write,
+) -> io::Result<(u64, String)> {
+    let mut hasher = Sha256::new();
+    let mut buffer = vec![0u8; 64 * 1024];
+    let mut total = 0u64;
+    loop {
+        let read = match reader.read(&mut buffer) {
+            Ok(0) => break,
+            Ok(read) => read,
+            Err(e) if e.kind() == io::ErrorKind::Interrupted => continue,
+            Err(e) => return Err(e),
+        };
+        hasher.update(&buffer[..read]);
+        writer.write_all(&buffer[..read])?;
+        total += read as u64;
+    }
+    writer.flush()?;
+    Ok((total, to_hex(&hasher.finalize())))
+}
+
+/// 计算文件的 SHA-256
+///
+/// ### 参数
+/// - `path`: 文件路径
+///
+/// ### 返回值
+/// - `io::Result<String>`: 小写十六进制哈希值
+pub fn sha256_file(path: &Path) -> io::Result<String> {
+    sha256_reader(fs::File::open(path)?)
+}
+
+/// 比较两个十六进制哈希值
+///
+/// ### 说明
+/// - 忽略大小写与首尾空白，任一方为空时视为不匹配
+pub fn hash_matches(actual: &str, expected: &str) -> bool {
+    let (actual, expected) = (actual.trim(), expected.trim());
+    !actual.is_empty() && actual.eq_ignore_ascii_case(expected)
+}
+
+/// 将字节转换为小写十六进制字符串
+fn to_hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|byte| format!("{:02x}", byte)).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const EMPTY: &str = "e3b0c44298fc1c149afbf4c8996fb92427ae41e4649b934ca495991b7852b855";
+    const ABC: &str = "ba7816bf8f01cfea414140de5dae2223b00361a396177a9cb410ff61f20015ad";
+
+    #[test]
+    fn sha256_of_known_inputs() {
+        assert_eq!(sha256_reader(&b""[..]).unwrap(), EMPTY);
+        assert_eq!(sha256_reader(&b"abc"[..]).unwrap(), ABC);
+    }
+
+    #[test]
+    fn copy_keeps_data_and_counts_bytes() {
+        // 超过一个缓冲区，覆盖多次读取
+        let input: Vec<u8> = (0..200_000u32).map(|i| (i % 251) as u8).collect();
+        let mut output = Vec::new();
+        let (count, hash) = copy_with_sha256(input.as_slice(), &mut output).unwrap();
+        assert_eq!(count, input.len() as u64);
+        assert_eq!(output, input);
+        assert_eq!(hash, sha256_reader(input.as_slice()).unwrap());
+    }
+
+    #[test]
+    fn sha256_of_file() {
+        let file =
+            std::env::temp_dir().join(format!("sc_starter_integrity_{}.txt", std::process::id()));
+        fs::write(&file, b"abc").unwrap();
+        let hash = sha256_file(&file);
+        fs::remove_file(&file).unwrap();
+        assert_eq!(hash.unwrap(), ABC);
+    }
+
+    #[test]
+    fn hash_comparison() {
+        assert!(hash_matches(ABC, ABC));
+        assert!(hash_matches(ABC, &ABC.to_ascii_uppercase()));
+        assert!(hash_matches(&format!("  {}\n", ABC), ABC));
+        assert!(!hash_matches(ABC, EMPTY));
+        assert!(!hash_matches("", ""));
+        assert!(!hash_matches("  ", ABC));
+        assert!(!hash_matches(ABC, ""));
+    }
+}
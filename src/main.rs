@@ -9,16 +9,43 @@
 
 #![cfg_attr(not(debug_assertions), windows_subsystem = "windows")]
 // 导入各个模块
+mod capture;
+mod cli;
 mod config;
+mod config_backup;
+mod copy_as;
+mod dedupe;
+mod engine;
 mod file_ops;
+mod history;
+mod hooks;
 mod hotkeys;
+mod integrity;
+mod manifest;
+mod metadata;
+mod mirror;
+mod monitor;
+mod pe_version;
+mod postprocess;
+mod retention;
 mod tray;
 mod types;
+mod upload;
+mod watermark;
 mod window_handle;
 
 use crate::config::*;
+use crate::config_backup::*;
+use crate::copy_as::*;
+use crate::engine::*;
 use crate::file_ops::*;
+use crate::history::*;
 use crate::hotkeys::*;
+use crate::manifest::RES_VERSION;
+use crate::mirror::*;
+use crate::monitor::*;
+use crate::pe_version::format_version;
+use crate::retention::*;
 use crate::tray::*;
 use crate::types::*;
 
@@ -41,6 +68,7 @@ const PROCESS_ID: &str = "78D83F24ADEC8FAF2E4CC1795F166CE4";
 /// 程序主入口函数
 ///
 /// ### 功能流程
+/// 0. 识别到命令行子命令时执行后直接退出
 /// 1. 检查程序单例运行
 /// 2. 初始化程序路径和文件
 /// 3. 读取配置文件
@@ -48,6 +76,14 @@ const PROCESS_ID: &str = "78D83F24ADEC8FAF2E4CC1795F166CE4";
 /// 5. 注册全局快捷键
 /// 6. 启动主事件循环
 fn main() {
+    let startup = std::time::Instant::now();
+
+    // 命令行子命令（如 history）直接执行后退出，不启动托盘程序
+    let cli_args: Vec<String> = std::env::args().skip(1).collect();
+    if let Some(code) = crate::cli::run_cli(&cli_args) {
+        std::process::exit(code);
+    }
+
     // 使用系统互斥锁确保程序单例运行，防止多个实例造成快捷键冲突
     let instance = match SingleInstance::new(PROCESS_ID) {
         Ok(inst) => Box::new(inst),
@@ -91,24 +127,76 @@ fn main() {
     };
     let data_local_dir = binding.data_local_dir();
     let dir_path = PathBuf::from(data_local_dir).join("SC_Starter");
-    let path_infos = PathInfos {
+    let mut path_infos = PathInfos {
         dir_path: dir_path.clone(),
         exe_path: dir_path.join("ScreenCapture.exe"),
         conf_path: dir_path.join("config.toml"),
         conf_example_path: dir_path.join("config_example.toml"),
+        engine_path: None,
     };
+    // 配置文件缺失或损坏时按恢复方式从备份还原，需早于读取其中的任何设置
+    if let Some(damage) = config_damage(&path_infos) {
+        recover_config(&path_infos, damage);
+    }
+    // 外部 ScreenCapture 需在释放资源前确定，不可用时改用内置版本
+    path_infos.engine_path = read_engine_path(&path_infos.conf_path);
+    let mut engine_caps = prepare_engine(&mut path_infos);
     println!("{}", &path_infos);
 
     // 检查必要文件是否存在
     let exist_result = check_res_exist(&path_infos);
-    // 根据检查结果解压资源文件
-    unzip_res(&path_infos, &exist_result);
+    // 根据检查结果与资源策略解压资源文件，失败时若旧文件仍可用则继续运行
+    let resource = read_resource_config(&path_infos.conf_path);
+    let unzipped = unzip_res(&path_infos, &exist_result, &resource);
+    if let Err(e) = &unzipped {
+        eprintln!("{}", e);
+        let usable = path_infos.engine().exists() && path_infos.conf_path.exists();
+        error_msgbox(
+            format!(
+                "Failed to release resources:\n{}{}",
+                e,
+                if usable {
+                    "\n\nThe existing files will be used."
+                } else {
+                    ""
+                }
+            ),
+            "SC_Starter",
+            0,
+        );
+        if !usable {
+            std::process::exit(1);
+        }
+    }
+
+    // 内置 ScreenCapture 的版本取决于是否释放了嵌入的exe
+    if path_infos.engine_path.is_none() {
+        engine_caps.version =
+            if unzipped.is_ok() && should_release_exe(&exist_result, resource.policy) {
+                Some(RES_VERSION.to_string())
+            } else {
+                exist_result.exe_version.map(format_version)
+            };
+    }
+
+    // 首次运行时打开释放的配置文件，并询问是否退出以便修改后重启
+    if !exist_result.conf_exist && path_infos.conf_path.exists() {
+        open_config(&path_infos.conf_path);
+        restart_prompt();
+    }
 
     // 读取配置文件
     // 包含快捷键设置和截图保存路径
     let settings = read_config(&path_infos.conf_path);
     print!("{}", &settings);
 
+    // 备份可以正常解析的配置文件，供被删除或损坏时还原
+    match backup_config(&path_infos, settings.resource.config_backups) {
+        Ok(Some(backup)) => println!("CONF: Backed up to {}", backup.display()),
+        Ok(None) => (),
+        Err(e) => eprintln!("Failed to back up config: {}", e),
+    }
+
     let temp = binding
         .config_dir()
         .join("Microsoft\\Windows\\Start Menu\\Programs\\Startup");
@@ -119,13 +207,24 @@ fn main() {
     };
     set_startup(settings.sundry.auto_start, &temp, &self_path);
 
-    // 创建托盘图标管理器（含右键菜单：截图、长截图、退出）
-    let tray_manager = TrayManager::new(settings.sundry.lang);
+    // 按条数与保留天数压缩截图历史
+    let show_recent = settings.history.enabled && settings.history.recent_count > 0;
+    if settings.history.enabled
+        && let Err(e) = compact_history(&path_infos.dir_path, &settings.history)
+    {
+        eprintln!("Failed to compact history: {}", e);
+    }
+
+    // 创建托盘图标管理器（含右键菜单：截图、长截图、最近截图、清理截图、退出）
+    let tray_manager = TrayManager::new(&settings);
 
     // 获取菜单项 ID（用于事件匹配）
     let capture_id = tray_manager.capture_id.clone();
     let long_capture_id = tray_manager.long_capture_id.clone();
     let full_capture_id = tray_manager.full_capture_id.clone();
+    let cleanup_id = tray_manager.cleanup_id.clone();
+    let cleanup_preview_id = tray_manager.cleanup_preview_id.clone();
+    let mirror_queue_id = tray_manager.mirror_queue_id.clone();
     let open_config_id = tray_manager.open_config_id.clone();
     let exit_id = tray_manager.exit_id.clone();
 
@@ -137,27 +236,44 @@ fn main() {
     let running = Arc::new(AtomicBool::new(true));
 
     // 准备事件处理所需的数据
-    let exe_path = path_infos.exe_path.clone();
-    let save_path = settings.path.save_path.clone();
-    let gui = settings.gui.clone();
-    let comp_level = settings.sundry.comp_level;
-    let scale_level = settings.sundry.scale_level;
-    let notification = settings.sundry.notification;
-    let lang = settings.sundry.lang;
+    let capture_ctx = CaptureContext {
+        engine: create_engine(&settings.capture, &path_infos, engine_caps),
+        paths: path_infos.clone(),
+        settings: Arc::new(settings),
+    };
 
     let conf_path = path_infos.conf_path.clone();
 
     // 设置全局热键并获取控制句柄
     let (handler_hotkeys, hotkey_exit_tx) =
-        set_hotkeys(&path_infos, &settings, running.clone(), proxy.clone());
+        set_hotkeys(&capture_ctx, running.clone(), proxy.clone());
     let handler_hotkeys: Mutex<Option<std::thread::JoinHandle<()>>> =
         Mutex::new(Some(handler_hotkeys));
 
-    // 启动文件监控，防止核心文件被删除
-    let _file_monitor_running = avoid_exe_del(&path_infos);
+    // 监听核心文件变化，被删除或篡改时自动恢复
+    let file_monitor: Mutex<Option<std::thread::JoinHandle<()>>> = Mutex::new(spawn_file_monitor(
+        &path_infos,
+        &capture_ctx.settings.resource,
+        running.clone(),
+    ));
+
+    // 按保留策略定时清理截图目录
+    let _retention_scheduler = spawn_retention_scheduler(&capture_ctx, running.clone());
+
+    // 后台重试复制失败的镜像任务
+    let show_mirror = !capture_ctx.settings.path.mirrors.is_empty();
+    let _mirror_worker = spawn_mirror_worker(&capture_ctx, running.clone());
+
+    if DEBUG {
+        println!("Startup: {} ms", startup.elapsed().as_millis());
+    }
 
     // 包装 tray_manager 以便在退出时显式 drop
     let mut tray_manager = Some(tray_manager);
+    // 已同步到托盘菜单的历史记录版本，初始值保证首次循环时刷新
+    let mut recent_generation = u64::MAX;
+    // 已同步到托盘菜单的镜像队列版本
+    let mut mirror_queue_generation = u64::MAX;
 
     // 主事件循环
     event_loop.run(move |event, _, control_flow| {
@@ -165,6 +281,25 @@ fn main() {
         *control_flow =
             tao::event_loop::ControlFlow::WaitUntil(std::time::Instant::now() + T_SEC_1_100);
 
+        // 历史记录更新后刷新"最近截图"菜单
+        if show_recent && recent_generation != history_generation() {
+            recent_generation = history_generation();
+            if let Some(tm) = &mut tray_manager {
+                tm.refresh_recent(&recent_files(
+                    &capture_ctx.paths.dir_path,
+                    capture_ctx.settings.history.recent_count,
+                ));
+            }
+        }
+
+        // 镜像队列变化后刷新状态菜单项
+        if show_mirror && mirror_queue_generation != mirror_generation() {
+            mirror_queue_generation = mirror_generation();
+            if let Some(tm) = &tray_manager {
+                tm.set_mirror_pending(load_queue(&capture_ctx.paths.dir_path).len());
+            }
+        }
+
         // 处理托盘图标事件（左键双击截图）
         while let Ok(tray_event) = tray_icon::TrayIconEvent::receiver().try_recv() {
             if let tray_icon::TrayIconEvent::DoubleClick {
@@ -172,8 +307,7 @@ fn main() {
                 ..
             } = tray_event
             {
-                let args = build_capture_args(comp_level, scale_level, &save_path, false);
-                spawn_capture(&exe_path, args, gui.clone(), notification, lang);
+                spawn_capture(&capture_ctx, CaptureMode::Region);
             }
         }
 
@@ -186,8 +320,7 @@ fn main() {
                     tm.hide_menu();
                     tm.show_menu();
                 }
-                let args = build_capture_args(comp_level, scale_level, &save_path, false);
-                spawn_capture(&exe_path, args, gui.clone(), notification, lang);
+                spawn_capture(&capture_ctx, CaptureMode::Region);
             } else if menu_event.id == long_capture_id {
                 // 菜单：长截图
                 println!("Menu Event: Long Capture");
@@ -195,29 +328,48 @@ fn main() {
                     tm.hide_menu();
                     tm.show_menu();
                 }
-                let args = build_capture_args(comp_level, scale_level, &save_path, true);
-                spawn_capture(&exe_path, args, gui.clone(), notification, lang);
+                spawn_capture(&capture_ctx, CaptureMode::Long);
             } else if menu_event.id == full_capture_id {
                 println!("Menu Event: Full Capture");
                 if let Some(tm) = &tray_manager {
                     tm.hide_menu();
                     tm.show_menu();
                 }
-                let args: Vec<String> = vec![
-                    "--cap:fullscreen".into(),
-                    crate::hotkeys::save_path_get(&save_path),
-                ];
-                spawn_capture(
-                    &exe_path,
-                    args,
-                    std::collections::HashMap::new(),
-                    notification,
-                    lang,
-                );
+                spawn_capture(&capture_ctx, CaptureMode::Fullscreen);
+            } else if menu_event.id == cleanup_id {
+                // 菜单：立即清理截图
+                println!("Menu Event: Clean Up");
+                spawn_manual_cleanup(&capture_ctx, false);
+            } else if menu_event.id == cleanup_preview_id {
+                // 菜单：预览清理
+                println!("Menu Event: Clean Up Preview");
+                spawn_manual_cleanup(&capture_ctx, true);
+            } else if menu_event.id == mirror_queue_id {
+                // 菜单：镜像队列（立即重试）
+                println!("Menu Event: Mirror Retry");
+                request_mirror_retry();
             } else if menu_event.id == open_config_id {
                 // 菜单：设置
                 println!("Menu Event: Open Config");
                 open_config(&conf_path);
+            } else if let Some((action, file)) = tray_manager
+                .as_ref()
+                .and_then(|tm| tm.recent_action(&menu_event.id))
+            {
+                // 菜单：最近截图
+                println!("Menu Event: Recent {:?} {}", action, file.display());
+                match action {
+                    RecentAction::Open => open_file(&file),
+                    RecentAction::Reveal => reveal_in_folder(&file),
+                    RecentAction::CopyPath => copy_to_clipboard(&file.display().to_string()),
+                }
+            } else if let Some(format) = tray_manager
+                .as_ref()
+                .and_then(|tm| tm.copy_as_action(&menu_event.id))
+            {
+                // 菜单：复制最近截图为
+                println!("Menu Event: Copy As {}", format);
+                spawn_copy_last(&capture_ctx, &format);
             } else if menu_event.id == exit_id {
                 // 菜单：退出
                 println!("Menu Event: Exit requested");
@@ -249,6 +401,18 @@ fn main() {
                 }
             }
 
+            // 等待文件监控线程退出（每 0.5 秒检查一次运行状态），超时（如正在恢复文件）则不再等待
+            if let Some(handle) = file_monitor.lock().unwrap().take() {
+                let wait_deadline = std::time::Instant::now() + std::time::Duration::from_secs(1);
+                while !handle.is_finished() && std::time::Instant::now() < wait_deadline {
+                    std::thread::sleep(T_SEC_1_100);
+                }
+
+                if handle.is_finished() {
+                    handle.join().ok();
+                }
+            }
+
             // 显式 drop 托盘管理器以清理系统托盘图标
             if let Some(tm) = tray_manager.take() {
                 drop(tm);
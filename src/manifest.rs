@@ -0,0 +1,14 @@
+//! 嵌入资源清单模块
+//!
+//! 由 build.rs 在编译时根据 `res/` 下的文件生成，包括：
+//! - `RES_VERSION` / `RES_VERSION_NUMBER`: ScreenCapture 版本（读取自 exe 的版本资源）
+//! - `RES_EXE_SHA256` / `RES_EXE_SIZE`: ScreenCapture.exe 的 SHA-256 与大小
+//! - `RES_CONF_SHA256`: config.toml 的 SHA-256
+//! - `RES_EXE_DEFLATE`: 以 deflate 压缩后嵌入的 ScreenCapture.exe
+//!
+//! 如需固定期望值，在 Cargo.toml 的 `[package.metadata.res]` 中设置，不一致时编译失败
+
+include!(concat!(env!("OUT_DIR"), "/res_manifest.rs"));
+
+pub static RES_EXE_DEFLATE: &[u8] =
+    include_bytes!(concat!(env!("OUT_DIR"), "/ScreenCapture.exe.deflate"));
@@ -0,0 +1,442 @@
+//! 截图元数据模块
+//!
+//! 本模块负责：
+//! - 汇总截图来源信息（截图模式、时间、前台程序、配置名、版本）
+//! - 以 PNG 文本块（tEXt / iTXt）写入截图文件
+//! - 写入同名 .json 附属文件
+//! - 从截图文件与附属文件中读回元数据
+//! - 计算不含元数据文本块的内容哈希，写入元数据前后保持不变
+
+use crate::capture::CaptureJob;
+use crate::integrity::sha256_reader;
+use crate::types::*;
+use serde_json::{Value, json};
+use std::{
+    fs, io,
+    path::{Path, PathBuf},
+};
+
+/// PNG 文件签名
+const PNG_SIGNATURE: [u8; 8] = [0x89, b'P', b'N', b'G', b'\r', b'\n', 0x1A, b'\n'];
+/// 完整元数据所在 iTXt 块的关键字
+pub const METADATA_KEYWORD: &str = "SC_Starter";
+
+/// 截图元数据
+#[derive(Clone, Debug)]
+pub struct CaptureMetadata {
+    /// 截图模式
+    pub mode: String,
+    /// 截图时间（RFC 3339）
+    pub captured_at: String,
+    /// 前台窗口所属程序
+    pub app: String,
+    /// 前台窗口标题
+    pub window_title: String,
+    /// 配置名
+    pub profile: String,
+    /// SC_Starter 版本
+    pub version: String,
+    /// 截图程序版本，无法确定时为 `unknown`
+    pub res_version: String,
+}
+impl CaptureMetadata {
+    /// 根据截图任务生成元数据
+    pub fn from_job(ctx: &CaptureContext, job: &CaptureJob) -> Self {
+        CaptureMetadata {
+            mode: job.mode.key().to_string(),
+            captured_at: job.started.to_rfc3339(),
+            app: job.window_app.clone(),
+            window_title: job.window_title.clone(),
+            profile: ctx.settings.sundry.profile.clone(),
+            version: PKG_VERSION.to_string(),
+            res_version: ctx.engine.version().unwrap_or("unknown".to_string()),
+        }
+    }
+
+    /// 转换为 JSON 对象
+    pub fn to_json(&self) -> Value {
+        json!({
+            "mode": self.mode,
+            "captured_at": self.captured_at,
+            "app": self.app,
+            "window_title": self.window_title,
+            "profile": self.profile,
+            "sc_starter": self.version,
+            "screen_capture": self.res_version,
+        })
+    }
+}
+
+/// 从截图中读回的元数据
+#[derive(Clone, Debug, Default)]
+pub struct StoredMetadata {
+    /// PNG 文本块的关键字与文本
+    pub texts: Vec<(String, String)>,
+    /// .json 附属文件内容
+    pub sidecar: Option<Value>,
+}
+
+/// 为保存的截图写入元数据
+///
+/// ### 参数
+/// - `ctx`: 截图任务上下文
+/// - `job`: 截图任务
+/// - `file`: 已保存的截图
+///
+/// ### 说明
+/// - 按 `[metadata]` 配置写入 PNG 文本块和/或 .json 附属文件
+/// - 非 PNG 文件无法写入文本块，仅写入附属文件（如已启用）
+/// - 写入失败只记录日志，不影响后续流程
+pub fn write_capture_metadata(ctx: &CaptureContext, job: &CaptureJob, file: &Path) {
+    let config = &ctx.settings.metadata;
+    if !config.enabled {
+        return;
+    }
+    let metadata = CaptureMetadata::from_job(ctx, job);
+
+    if config.png_chunks {
+        match embed_png_text(file, &png_text_entries(&metadata)) {
+            Ok(true) => println!("Metadata embedded: {}", file.display()),
+            Ok(false) => println!("Not a PNG, metadata chunks skipped: {}", file.display()),
+            Err(e) => eprintln!("Failed to embed metadata in {}: {}", file.display(), e),
+        }
+    }
+    if config.sidecar {
+        let sidecar = sidecar_path(file);
+        let content = serde_json::to_string_pretty(&metadata.to_json()).unwrap_or_default();
+        match fs::write(&sidecar, content) {
+            Ok(_) => println!("Metadata sidecar: {}", sidecar.display()),
+            Err(e) => eprintln!("Failed to write {}: {}", sidecar.display(), e),
+        }
+    }
+}
+
+/// 元数据附属文件路径（在完整文件名后追加 `.json`）
+pub fn sidecar_path(file: &Path) -> PathBuf {
+    let mut path = file.as_os_str().to_owned();
+    path.push(".json");
+    PathBuf::from(path)
+}
+
+/// 生成写入 PNG 的文本块
+///
+/// ### 返回值
+/// - `Vec<(String, String)>`: 关键字与文本
+///
+/// ### 说明
+/// - `Software` 与 `Creation Time` 为 PNG 规范中的标准关键字，便于其它软件显示
+/// - 完整元数据以 JSON 写入 `SC_Starter` 块
+fn png_text_entries(metadata: &CaptureMetadata) -> Vec<(String, String)> {
+    vec![
+        (
+            "Software".to_string(),
+            format!(
+                "SC_Starter {} / ScreenCapture {}",
+                metadata.version, metadata.res_version
+            ),
+        ),
+        ("Creation Time".to_string(), metadata.captured_at.clone()),
+        (METADATA_KEYWORD.to_string(), metadata.to_json().to_string()),
+    ]
+}
+
+/// 向 PNG 文件写入文本块
+///
+/// ### 参数
+/// - `file`: PNG 文件
+/// - `entries`: 关键字与文本
+///
+/// ### 返回值
+/// - `io::Result<bool>`: 文件不是 PNG 时返回 `Ok(false)`
+///
+/// ### 功能
+/// - 删除同名关键字的旧文本块，在 IEND 之前插入新块
+/// - 纯 Latin-1 文本写为 tEXt，其余写为未压缩的 iTXt（UTF-8）
+/// - 先写入临时文件再替换，避免中途失败损坏截图
+pub fn embed_png_text(file: &Path, entries: &[(String, String)]) -> io::Result<bool> {
+    let data = fs::read(file)?;
+    let Some(chunks) = parse_png_chunks(&data) else {
+        return Ok(false);
+    };
+
+    let mut output = Vec::with_capacity(data.len() + 1024);
+    output.extend_from_slice(&PNG_SIGNATURE);
+    for (kind, body) in chunks {
+        if let Some((keyword, _)) = decode_text_chunk(kind, body)
+            && entries.iter().any(|(key, _)| *key == keyword)
+        {
+            continue;
+        }
+        if kind == b"IEND" {
+            for (keyword, text) in entries {
+                let (kind, body) = encode_text_chunk(keyword, text);
+                write_chunk(&mut output, &kind, &body);
+            }
+        }
+        write_chunk(&mut output, kind, body);
+    }
+
+    let mut temp = file.as_os_str().to_owned();
+    temp.push(".part");
+    let temp = PathBuf::from(temp);
+    fs::write(&temp, output)?;
+    fs::rename(&temp, file).inspect_err(|_| {
+        let _ = fs::remove_file(&temp);
+    })?;
+    Ok(true)
+}
+
+/// 读取截图元数据
+///
+/// ### 参数
+/// - `file`: 截图文件
+///
+/// ### 返回值
+/// - `io::Result<StoredMetadata>`: PNG 文本块与附属文件内容
+///
+/// ### 说明
+/// - 非 PNG 文件的文本块为空
+/// - 压缩的 zTXt / iTXt 块无法解析，显示为占位文字
+pub fn read_capture_metadata(file: &Path) -> io::Result<StoredMetadata> {
+    let data = fs::read(file)?;
+    let texts = parse_png_chunks(&data)
+        .map(|chunks| {
+            chunks
+                .into_iter()
+                .filter_map(|(kind, body)| decode_text_chunk(kind, body))
+                .collect()
+        })
+        .unwrap_or_default();
+
+    let sidecar = fs::read_to_string(sidecar_path(file))
+        .ok()
+        .and_then(|content| serde_json::from_str(&content).ok());
+    Ok(StoredMetadata { texts, sidecar })
+}
+
+/// 计算截图内容的 SHA-256
+///
+/// ### 参数
+/// - `file`: 截图文件
+///
+/// ### 返回值
+/// - `io::Result<String>`: 小写十六进制哈希值
+///
+/// ### 说明
+/// - PNG 文件跳过 tEXt / zTXt / iTXt 文本块，写入元数据前后结果相同
+/// - 非 PNG 文件计算整个文件的哈希
+pub fn content_sha256(file: &Path) -> io::Result<String> {
+    let data = fs::read(file)?;
+    let Some(chunks) = parse_png_chunks(&data) else {
+        return sha256_reader(data.as_slice());
+    };
+    let mut content = Vec::with_capacity(data.len());
+    content.extend_from_slice(&PNG_SIGNATURE);
+    for (kind, body) in chunks {
+        if !matches!(kind, b"tEXt" | b"zTXt" | b"iTXt") {
+            write_chunk(&mut content, kind, body);
+        }
+    }
+    sha256_reader(content.as_slice())
+}
+
+/// 拆分 PNG 数据块
+///
+/// ### 返回值
+/// - `Option<Vec<(&[u8; 4], &[u8])>>`: 块类型与内容，签名错误或数据截断时返回 `None`
+fn parse_png_chunks(data: &[u8]) -> Option<Vec<(&[u8; 4], &[u8])>> {
+    if !data.starts_with(&PNG_SIGNATURE) {
+        return None;
+    }
+    let mut chunks = Vec::new();
+    let mut pos = PNG_SIGNATURE.len();
+    while pos + 12 <= data.len() {
+        let len = u32::from_be_bytes(data[pos..pos + 4].try_into().ok()?) as usize;
+        let kind: &[u8; 4] = data[pos + 4..pos + 8].try_into().ok()?;
+        let end = pos.checked_add(12)?.checked_add(len)?;
+        if end > data.len() {
+            return None;
+        }
+        chunks.push((kind, &data[pos + 8..pos + 8 + len]));
+        pos = end;
+        if kind == b"IEND" {
+            return Some(chunks);
+        }
+    }
+    None
+}
+
+/// 写入一个 PNG 数据块（长度、类型、内容、CRC）
+fn write_chunk(output: &mut Vec<u8>, kind: &[u8; 4], body: &[u8]) {
+    output.extend_from_slice(&(body.len() as u32).to_be_bytes());
+    output.extend_from_slice(kind);
+    output.extend_from_slice(body);
+    let mut hasher = crc32fast::Hasher::new();
+    hasher.update(kind);
+    hasher.update(body);
+    output.extend_from_slice(&hasher.finalize().to_be_bytes());
+}
+
+/// 编码文本块
+///
+/// ### 说明
+/// - 关键字须为 1-79 个 Latin-1 字符，此处只使用内置的 ASCII 关键字
+fn encode_text_chunk(keyword: &str, text: &str) -> ([u8; 4], Vec<u8>) {
+    let mut body = keyword.as_bytes().to_vec();
+    body.push(0);
+    if text.chars().all(|c| (c as u32) < 0x100 && c != '\0') {
+        body.extend(text.chars().map(|c| c as u8));
+        (*b"tEXt", body)
+    } else {
+        // 压缩标志、压缩方法、语言标签、翻译后的关键字
+        body.extend_from_slice(&[0, 0, 0, 0]);
+        body.extend_from_slice(text.as_bytes());
+        (*b"iTXt", body)
+    }
+}
+
+/// 解码文本块
+///
+/// ### 返回值
+/// - `Option<(String, String)>`: 关键字与文本，非文本块时返回 `None`
+fn decode_text_chunk(kind: &[u8; 4], body: &[u8]) -> Option<(String, String)> {
+    let latin1 = |bytes: &[u8]| bytes.iter().map(|&b| b as char).collect::<String>();
+    let split = body.iter().position(|&b| b == 0)?;
+    let keyword = latin1(&body[..split]);
+    let rest = &body[split + 1..];
+
+    match kind {
+        b"tEXt" => Some((keyword, latin1(rest))),
+        b"zTXt" => Some((keyword, "<compressed>".to_string())),
+        b"iTXt" => {
+            let (&compressed, rest) = rest.split_first()?;
+            let rest = rest.get(1..)?;
+            // 跳过语言标签与翻译后的关键字
+            let lang_end = rest.iter().position(|&b| b == 0)?;
+            let rest = &rest[lang_end + 1..];
+            let translated_end = rest.iter().position(|&b| b == 0)?;
+            let text = &rest[translated_end + 1..];
+            if compressed != 0 {
+                Some((keyword, "<compressed>".to_string()))
+            } else {
+                Some((keyword, String::from_utf8_lossy(text).to_string()))
+            }
+        }
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::capture::tests::temp_dir;
+
+    /// 写入一张 3x2 的 PNG 截图
+    fn write_png(path: &Path) {
+        image::RgbImage::from_pixel(3, 2, image::Rgb([40, 120, 200]))
+            .save(path)
+            .unwrap();
+    }
+
+    /// 文件中全部文本块的类型、关键字与文本
+    fn text_chunks(file: &Path) -> Vec<([u8; 4], String, String)> {
+        let data = fs::read(file).unwrap();
+        parse_png_chunks(&data)
+            .unwrap()
+            .into_iter()
+            .filter_map(|(kind, body)| {
+                decode_text_chunk(kind, body).map(|(key, text)| (*kind, key, text))
+            })
+            .collect()
+    }
+
+    fn entry(key: &str, text: &str) -> (String, String) {
+        (key.to_string(), text.to_string())
+    }
+
+    #[test]
+    fn latin1_text_uses_text_chunk() {
+        let dir = temp_dir("metadata_latin1");
+        let file = dir.join("shot.png");
+        write_png(&file);
+
+        let text = "Café © 2026";
+        assert!(embed_png_text(&file, &[entry("Title", text)]).unwrap());
+        assert_eq!(
+            text_chunks(&file),
+            [(*b"tEXt", "Title".to_string(), text.to_string())]
+        );
+        assert_eq!(image::open(&file).unwrap().width(), 3);
+    }
+
+    #[test]
+    fn utf8_text_uses_itxt_chunk() {
+        let dir = temp_dir("metadata_utf8");
+        let file = dir.join("shot.png");
+        write_png(&file);
+
+        let text = "记事本 — 截图";
+        assert!(embed_png_text(&file, &[entry(METADATA_KEYWORD, text)]).unwrap());
+        assert_eq!(
+            text_chunks(&file),
+            [(*b"iTXt", METADATA_KEYWORD.to_string(), text.to_string())]
+        );
+        assert_eq!(
+            read_capture_metadata(&file).unwrap().texts,
+            [entry(METADATA_KEYWORD, text)]
+        );
+        assert_eq!(image::open(&file).unwrap().height(), 2);
+    }
+
+    #[test]
+    fn rewriting_keyword_replaces_chunk() {
+        let dir = temp_dir("metadata_replace");
+        let file = dir.join("shot.png");
+        write_png(&file);
+
+        embed_png_text(&file, &[entry("Title", "first"), entry("Software", "SC")]).unwrap();
+        embed_png_text(&file, &[entry("Title", "第二次")]).unwrap();
+        assert_eq!(
+            text_chunks(&file),
+            [
+                (*b"tEXt", "Software".to_string(), "SC".to_string()),
+                (*b"iTXt", "Title".to_string(), "第二次".to_string()),
+            ]
+        );
+    }
+
+    #[test]
+    fn content_hash_ignores_text_chunks() {
+        let dir = temp_dir("metadata_hash");
+        let file = dir.join("shot.png");
+        write_png(&file);
+        let before = content_sha256(&file).unwrap();
+        assert_eq!(
+            before,
+            sha256_reader(fs::read(&file).unwrap().as_slice()).unwrap()
+        );
+
+        embed_png_text(
+            &file,
+            &[entry(METADATA_KEYWORD, "{\"app\":\"notepad.exe\"}")],
+        )
+        .unwrap();
+        embed_png_text(&file, &[entry("Title", "记事本")]).unwrap();
+        assert_eq!(content_sha256(&file).unwrap(), before);
+        assert_ne!(
+            sha256_reader(fs::read(&file).unwrap().as_slice()).unwrap(),
+            before
+        );
+    }
+
+    #[test]
+    fn non_png_is_left_alone() {
+        let dir = temp_dir("metadata_jpeg");
+        let file = dir.join("shot.jpg");
+        fs::write(&file, b"\xFF\xD8not a png").unwrap();
+
+        assert!(!embed_png_text(&file, &[entry("Title", "x")]).unwrap());
+        assert_eq!(fs::read(&file).unwrap(), b"\xFF\xD8not a png");
+        assert!(read_capture_metadata(&file).unwrap().texts.is_empty());
+    }
+}
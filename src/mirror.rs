@@ -0,0 +1,295 @@
+//! 截图镜像模块
+//!
+//! 本模块负责：
+//! - 截图保存后复制到 `[path]` 中配置的各个镜像目录
+//! - 将复制失败的任务写入持久化重试队列
+//! - 后台定时重试队列，目标目录恢复后自动补齐
+//! - 通知主循环队列状态已变化
+
+use crate::types::*;
+use serde_json::{Value, json};
+use std::{
+    fs, io,
+    path::{Path, PathBuf},
+    sync::{
+        Arc, Mutex,
+        atomic::{AtomicBool, AtomicU64, Ordering},
+    },
+    thread::{self, JoinHandle},
+    time::{Duration, Instant},
+};
+
+/// 重试队列文件名（位于程序根目录）
+pub const MIRROR_QUEUE_FILE: &str = "mirror_queue.jsonl";
+/// 后台重试间隔
+const RETRY_INTERVAL: Duration = Duration::from_secs(60);
+
+/// 队列文件锁
+static QUEUE_LOCK: Mutex<()> = Mutex::new(());
+/// 队列版本号，每次队列变化后递增，主循环据此刷新托盘菜单
+static QUEUE_GENERATION: AtomicU64 = AtomicU64::new(0);
+/// 请求后台线程立即重试
+static RETRY_NOW: AtomicBool = AtomicBool::new(false);
+
+/// 待重试的镜像任务
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct MirrorTask {
+    /// 源文件
+    pub source: PathBuf,
+    /// 目标文件
+    pub target: PathBuf,
+    /// 已尝试次数
+    pub attempts: u32,
+    /// 最近一次失败原因
+    pub error: String,
+}
+impl MirrorTask {
+    /// 转换为 JSON 对象
+    fn to_json(&self) -> Value {
+        json!({
+            "source": self.source.display().to_string(),
+            "target": self.target.display().to_string(),
+            "attempts": self.attempts,
+            "error": self.error,
+        })
+    }
+
+    /// 从一行 JSON 解析任务，格式错误时返回 `None`
+    fn from_json(line: &str) -> Option<Self> {
+        let value: Value = serde_json::from_str(line).ok()?;
+        let text = |key: &str| value.get(key).and_then(|v| v.as_str()).map(String::from);
+        Some(MirrorTask {
+            source: PathBuf::from(text("source")?),
+            target: PathBuf::from(text("target")?),
+            attempts: value.get("attempts").and_then(|v| v.as_u64()).unwrap_or(0) as u32,
+            error: text("error").unwrap_or_default(),
+        })
+    }
+
+    /// 是否为同一源文件与目标的任务
+    fn same_as(&self, other: &MirrorTask) -> bool {
+        self.source == other.source && self.target == other.target
+    }
+}
+
+/// 重试队列文件路径
+pub fn queue_path(dir_path: &Path) -> PathBuf {
+    dir_path.join(MIRROR_QUEUE_FILE)
+}
+
+/// 当前队列版本号
+pub fn mirror_generation() -> u64 {
+    QUEUE_GENERATION.load(Ordering::SeqCst)
+}
+
+/// 读取重试队列
+///
+/// ### 参数
+/// - `dir_path`: 程序根目录
+///
+/// ### 返回值
+/// - `Vec<MirrorTask>`: 待重试的任务，文件不存在时为空
+pub fn load_queue(dir_path: &Path) -> Vec<MirrorTask> {
+    match fs::read_to_string(queue_path(dir_path)) {
+        Ok(content) => content.lines().filter_map(MirrorTask::from_json).collect(),
+        Err(_) => Vec::new(),
+    }
+}
+
+/// 写入重试队列（需持有队列锁），队列为空时删除文件
+fn save_queue(dir_path: &Path, tasks: &[MirrorTask]) -> io::Result<()> {
+    let path = queue_path(dir_path);
+    if tasks.is_empty() {
+        if path.exists() {
+            fs::remove_file(&path)?;
+        }
+    } else {
+        let temp_path = path.with_extension("jsonl.tmp");
+        let mut output = String::new();
+        for task in tasks {
+            output.push_str(&task.to_json().to_string());
+            output.push('\n');
+        }
+        fs::write(&temp_path, output)?;
+        fs::rename(&temp_path, &path)?;
+    }
+    QUEUE_GENERATION.fetch_add(1, Ordering::SeqCst);
+    Ok(())
+}
+
+/// 计算截图在镜像目录中的目标路径
+///
+/// ### 说明
+/// - 截图位于保存目录内时保留相对路径（如日期子目录）
+/// - 否则直接放在镜像目录根目录
+fn mirror_target(mirror: &Path, save_path: &Path, file: &Path) -> Option<PathBuf> {
+    let relative = if save_path.as_os_str().is_empty() {
+        None
+    } else {
+        let base = strip_verbatim(save_path);
+        file.strip_prefix(&base).ok().map(Path::to_path_buf)
+    };
+    let relative = relative.or_else(|| file.file_name().map(PathBuf::from))?;
+    Some(mirror.join(relative))
+}
+
+/// 复制单个文件到镜像目录
+///
+/// ### 说明
+/// - 先复制为临时文件再重命名，避免目标目录中出现不完整的截图
+fn copy_to_mirror(source: &Path, target: &Path) -> io::Result<()> {
+    if let Some(parent) = target.parent() {
+        fs::create_dir_all(parent)?;
+    }
+    let mut temp = target.as_os_str().to_owned();
+    temp.push(".part");
+    let temp = PathBuf::from(temp);
+    fs::copy(source, &temp)?;
+    fs::rename(&temp, target).inspect_err(|_| {
+        let _ = fs::remove_file(&temp);
+    })
+}
+
+/// 将截图复制到所有镜像目录
+///
+/// ### 参数
+/// - `ctx`: 截图任务上下文
+/// - `file`: 已保存的截图
+///
+/// ### 功能
+/// - 依次复制到每个镜像目录
+/// - 复制失败的任务加入重试队列，由后台线程稍后重试
+pub fn mirror_capture(ctx: &CaptureContext, file: &Path) {
+    let mut failed: Vec<MirrorTask> = Vec::new();
+    for raw in &ctx.settings.path.mirrors {
+        let Some(mirror) = expand_path(raw).filter(|p| !p.as_os_str().is_empty()) else {
+            eprintln!("Invalid mirror destination: {}", raw);
+            continue;
+        };
+        let Some(target) = mirror_target(&mirror, &ctx.settings.path.save_path, file) else {
+            continue;
+        };
+        match copy_to_mirror(file, &target) {
+            Ok(_) => println!("Mirrored to {}", target.display()),
+            Err(e) => {
+                eprintln!("Failed to mirror to {}: {}, queued", target.display(), e);
+                failed.push(MirrorTask {
+                    source: file.to_path_buf(),
+                    target,
+                    attempts: 1,
+                    error: e.to_string(),
+                });
+            }
+        }
+    }
+    if failed.is_empty() {
+        return;
+    }
+
+    let _guard = QUEUE_LOCK.lock().unwrap_or_else(|e| e.into_inner());
+    let mut queue = load_queue(&ctx.paths.dir_path);
+    queue.retain(|task| !failed.iter().any(|f| f.same_as(task)));
+    queue.extend(failed);
+    if let Err(e) = save_queue(&ctx.paths.dir_path, &queue) {
+        eprintln!("Failed to write mirror queue: {}", e);
+    }
+}
+
+/// 重试队列中的所有任务
+///
+/// ### 参数
+/// - `dir_path`: 程序根目录
+///
+/// ### 返回值
+/// - `usize`: 仍在队列中的任务数
+///
+/// ### 说明
+/// - 复制期间不持有队列锁，截图线程可以同时加入新任务
+/// - 源文件已不存在的任务会被丢弃
+pub fn drain_queue(dir_path: &Path) -> usize {
+    let pending = {
+        let _guard = QUEUE_LOCK.lock().unwrap_or_else(|e| e.into_inner());
+        load_queue(dir_path)
+    };
+    if pending.is_empty() {
+        return 0;
+    }
+
+    let mut done: Vec<MirrorTask> = Vec::new();
+    let mut retried: Vec<MirrorTask> = Vec::new();
+    for task in pending {
+        if !task.source.is_file() {
+            eprintln!("Mirror source is gone: {}", task.source.display());
+            done.push(task);
+            continue;
+        }
+        match copy_to_mirror(&task.source, &task.target) {
+            Ok(_) => {
+                println!("Mirrored to {} (retry)", task.target.display());
+                done.push(task);
+            }
+            Err(e) => retried.push(MirrorTask {
+                attempts: task.attempts + 1,
+                error: e.to_string(),
+                ..task
+            }),
+        }
+    }
+
+    let _guard = QUEUE_LOCK.lock().unwrap_or_else(|e| e.into_inner());
+    let mut queue = load_queue(dir_path);
+    queue.retain(|task| !done.iter().any(|d| d.same_as(task)));
+    for task in queue.iter_mut() {
+        if let Some(updated) = retried.iter().find(|r| r.same_as(task)) {
+            *task = updated.clone();
+        }
+    }
+    if let Err(e) = save_queue(dir_path, &queue) {
+        eprintln!("Failed to write mirror queue: {}", e);
+    }
+    queue.len()
+}
+
+/// 请求后台线程立即重试队列
+pub fn request_mirror_retry() {
+    RETRY_NOW.store(true, Ordering::SeqCst);
+}
+
+/// 启动镜像队列重试线程
+///
+/// ### 参数
+/// - `ctx`: 截图任务上下文
+/// - `running`: 程序运行状态，变为 false 时线程退出
+///
+/// ### 返回值
+/// - `Option<JoinHandle<()>>`: 未配置镜像目录时返回 `None`
+///
+/// ### 功能
+/// - 启动时立即重试一次上次遗留的任务
+/// - 之后每 60 秒或收到立即重试请求时重试
+pub fn spawn_mirror_worker(
+    ctx: &CaptureContext,
+    running: Arc<AtomicBool>,
+) -> Option<JoinHandle<()>> {
+    if ctx.settings.path.mirrors.is_empty() {
+        return None;
+    }
+    let dir_path = ctx.paths.dir_path.clone();
+
+    Some(thread::spawn(move || {
+        loop {
+            let remaining = drain_queue(&dir_path);
+            if remaining > 0 {
+                println!("Mirror queue: {} pending", remaining);
+            }
+
+            let next_run = Instant::now() + RETRY_INTERVAL;
+            while Instant::now() < next_run && !RETRY_NOW.swap(false, Ordering::SeqCst) {
+                if !running.load(Ordering::SeqCst) {
+                    return;
+                }
+                thread::sleep(T_SEC_1_2);
+            }
+        }
+    }))
+}
@@ -0,0 +1,308 @@
+//! 核心文件监控模块
+//!
+//! 本模块负责：
+//! - 监听程序目录中的文件系统事件（删除、重命名、修改）
+//! - 合并短时间内的连续事件（防抖）
+//! - 核心文件变化后重新校验，必要时自动恢复
+//! - 配置文件修改后更新备份
+
+use crate::config_backup::{ConfigDamage, backup_config, recover_config};
+use crate::file_ops::{check_res_exist, should_release_exe, unzip_res};
+use crate::types::*;
+use notify::{Event, EventKind, RecursiveMode, Watcher};
+use std::{
+    path::Path,
+    sync::{
+        Arc,
+        atomic::{AtomicBool, Ordering},
+        mpsc,
+    },
+    thread::{self, JoinHandle},
+    time::{Duration, Instant},
+};
+
+/// 合并连续事件的等待时间
+const DEBOUNCE: Duration = Duration::from_millis(500);
+
+/// 监听目录中指定文件的变化
+///
+/// ### 参数
+/// - `dir`: 要监听的目录（不递归）
+/// - `files`: 关注的文件名，其它文件的事件被忽略
+/// - `debounce`: 最后一个事件之后等待的时间，期间的事件合并为一次回调
+/// - `running`: 运行状态标志，置为 false 后线程在 0.5 秒内退出并停止监听
+/// - `on_change`: 回调，参数为发生变化的文件名（去重）；监听出错时为空列表，表示需要全面检查
+///
+/// ### 返回值
+/// - `notify::Result<JoinHandle<()>>`: 无法建立监听时返回错误
+///
+/// ### 说明
+/// - 忽略只读访问事件，其余创建、删除、重命名、修改事件都会触发回调
+/// - Windows 文件名不区分大小写
+/// - 不依赖具体文件，可在临时目录中单独使用
+pub fn watch_files(
+    dir: &Path,
+    files: Vec<String>,
+    debounce: Duration,
+    running: Arc<AtomicBool>,
+    mut on_change: impl FnMut(Vec<String>) + Send + 'static,
+) -> notify::Result<JoinHandle<()>> {
+    let (tx, rx) = mpsc::channel::<notify::Result<Event>>();
+    let mut watcher = notify::recommended_watcher(tx)?;
+    watcher.watch(dir, RecursiveMode::NonRecursive)?;
+
+    Ok(thread::spawn(move || {
+        // 监听器随线程一起释放
+        let _watcher = watcher;
+        let mut pending: Vec<String> = Vec::new();
+        let mut failed = false;
+        let mut deadline: Option<Instant> = None;
+
+        while running.load(Ordering::SeqCst) {
+            let timeout = deadline
+                .map(|d| d.saturating_duration_since(Instant::now()))
+                .unwrap_or(T_SEC_1_2)
+                .min(T_SEC_1_2);
+            match rx.recv_timeout(timeout) {
+                Ok(Ok(event)) => {
+                    if matches!(event.kind, EventKind::Access(_)) {
+                        continue;
+                    }
+                    for name in event.paths.iter().filter_map(|p| matched_name(p, &files)) {
+                        if !pending.contains(&name) {
+                            pending.push(name);
+                        }
+                        deadline = Some(Instant::now() + debounce);
+                    }
+                }
+                Ok(Err(e)) => {
+                    eprintln!("File monitor error: {}", e);
+                    failed = true;
+                    deadline = Some(Instant::now() + debounce);
+                }
+                Err(mpsc::RecvTimeoutError::Timeout) => {}
+                Err(mpsc::RecvTimeoutError::Disconnected) => break,
+            }
+
+            if deadline.is_some_and(|d| Instant::now() >= d) {
+                deadline = None;
+                let changed = std::mem::take(&mut pending);
+                on_change(if std::mem::take(&mut failed) {
+                    Vec::new()
+                } else {
+                    changed
+                });
+            }
+        }
+    }))
+}
+
+/// 启动核心文件监控
+///
+/// ### 参数
+/// - `paths`: 包含所有需要保护的文件路径
+/// - `resource`: 资源文件配置，恢复时按其策略处理exe
+/// - `running`: 程序运行状态标志，程序退出时监控随之停止
+///
+/// ### 返回值
+/// - `Option<JoinHandle<()>>`: 无法建立监听时返回 `None`
+///
+/// ### 功能
+/// - exe或配置文件被删除、重命名或修改时立即（防抖后）重新校验
+/// - exe按资源策略校验哈希值与版本，不符合时恢复为内置版本
+/// - 配置文件被删除时按恢复方式从备份还原，没有备份时释放默认配置
+/// - 配置文件修改后仍可正常解析时加入备份
+/// - 使用外部 ScreenCapture 时只保护配置文件
+pub fn spawn_file_monitor(
+    paths: &PathInfos,
+    resource: &ResourceConfig,
+    running: Arc<AtomicBool>,
+) -> Option<JoinHandle<()>> {
+    let conf_name = file_name(&paths.conf_path);
+    let mut files = vec![conf_name.clone()];
+    if paths.engine_path.is_none() {
+        files.push(file_name(&paths.exe_path));
+    }
+
+    let paths_clone = paths.clone();
+    let resource = resource.clone();
+    let result = watch_files(&paths.dir_path, files, DEBOUNCE, running, move |changed| {
+        if changed.is_empty() {
+            println!("File monitor: verifying all files...");
+        } else {
+            println!("File monitor: {} changed, verifying...", changed.join(", "));
+        }
+        verify_and_recover(&paths_clone, &resource);
+        // 修改后仍可正常解析的配置文件加入备份
+        if changed.contains(&conf_name)
+            && let Err(e) = backup_config(&paths_clone, resource.config_backups)
+        {
+            eprintln!("Failed to back up config: {}", e);
+        }
+    });
+    match result {
+        Ok(handle) => Some(handle),
+        Err(e) => {
+            eprintln!("Failed to watch {}: {}", paths.dir_path.display(), e);
+            None
+        }
+    }
+}
+
+/// 重新校验核心文件，必要时恢复
+///
+/// ### 说明
+/// - 恢复失败只记录日志，之后的文件变化会再次尝试
+fn verify_and_recover(paths: &PathInfos, resource: &ResourceConfig) {
+    // 运行中只处理被删除的配置文件；无法解析多为正在编辑，留待下次启动时处理
+    if !paths.conf_path.exists() {
+        recover_config(paths, ConfigDamage::Missing);
+    }
+    let status = check_res_exist(paths);
+    let exe_ok = paths.engine_path.is_some() || !should_release_exe(&status, resource.policy);
+    if status.conf_exist && exe_ok {
+        return;
+    }
+
+    println!("Attempting to recover files...");
+    match unzip_res(paths, &status, resource) {
+        Ok(_) => println!("Files recovered, resuming monitoring..."),
+        Err(e) => eprintln!("Recovery failed: {}", e),
+    }
+}
+
+/// 取得文件名
+fn file_name(path: &Path) -> String {
+    path.file_name()
+        .map(|n| n.to_string_lossy().to_string())
+        .unwrap_or_default()
+}
+
+/// 事件路径是否为关注的文件，是则返回该文件名
+fn matched_name(path: &Path, files: &[String]) -> Option<String> {
+    let name = file_name(path);
+    files
+        .iter()
+        .find(|f| f.eq_ignore_ascii_case(&name))
+        .cloned()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::{fs, path::PathBuf};
+
+    const TEST_DEBOUNCE: Duration = Duration::from_millis(200);
+    const WAIT: Duration = Duration::from_secs(5);
+
+    /// 在临时目录中准备被监听的文件并开始监听
+    struct Fixture {
+        dir: PathBuf,
+        running: Arc<AtomicBool>,
+        handle: Option<JoinHandle<()>>,
+        changes: mpsc::Receiver<Vec<String>>,
+    }
+    impl Fixture {
+        fn new(name: &str) -> Self {
+            let dir = std::env::temp_dir().join(format!(
+                "sc_starter_monitor_{}_{}",
+                std::process::id(),
+                name
+            ));
+            let _ = fs::remove_dir_all(&dir);
+            fs::create_dir_all(&dir).unwrap();
+            fs::write(dir.join("config.toml"), "[sundry]\n").unwrap();
+            fs::write(dir.join("ScreenCapture.exe"), b"MZ").unwrap();
+
+            let running = Arc::new(AtomicBool::new(true));
+            let (tx, changes) = mpsc::channel();
+            let files = vec!["config.toml".to_string(), "ScreenCapture.exe".to_string()];
+            let handle = watch_files(
+                &dir,
+                files,
+                TEST_DEBOUNCE,
+                running.clone(),
+                move |mut changed| {
+                    changed.sort();
+                    tx.send(changed).ok();
+                },
+            )
+            .unwrap();
+            Fixture {
+                dir,
+                running,
+                handle: Some(handle),
+                changes,
+            }
+        }
+
+        fn path(&self, name: &str) -> PathBuf {
+            self.dir.join(name)
+        }
+
+        /// 等待下一次回调
+        fn next(&self) -> Vec<String> {
+            self.changes.recv_timeout(WAIT).expect("no change reported")
+        }
+
+        /// 防抖时间过后不应再有回调
+        fn assert_quiet(&self) {
+            let extra = self.changes.recv_timeout(TEST_DEBOUNCE * 4);
+            assert!(extra.is_err(), "unexpected callback: {:?}", extra);
+        }
+    }
+    impl Drop for Fixture {
+        fn drop(&mut self) {
+            self.running.store(false, Ordering::SeqCst);
+            if let Some(handle) = self.handle.take() {
+                handle.join().ok();
+            }
+            let _ = fs::remove_dir_all(&self.dir);
+        }
+    }
+
+    #[test]
+    fn reports_deleted_file() {
+        let fixture = Fixture::new("delete");
+        fs::remove_file(fixture.path("config.toml")).unwrap();
+        assert_eq!(fixture.next(), vec!["config.toml"]);
+    }
+
+    #[test]
+    fn reports_renamed_file() {
+        let fixture = Fixture::new("rename");
+        fs::rename(
+            fixture.path("ScreenCapture.exe"),
+            fixture.path("ScreenCapture.exe.old"),
+        )
+        .unwrap();
+        assert_eq!(fixture.next(), vec!["ScreenCapture.exe"]);
+    }
+
+    #[test]
+    fn reports_modified_file() {
+        let fixture = Fixture::new("modify");
+        fs::write(fixture.path("config.toml"), "[sundry]\nlang = true\n").unwrap();
+        assert_eq!(fixture.next(), vec!["config.toml"]);
+    }
+
+    #[test]
+    fn merges_burst_into_one_callback() {
+        let fixture = Fixture::new("burst");
+        for i in 0..5 {
+            fs::write(fixture.path("config.toml"), format!("# edit {}\n", i)).unwrap();
+        }
+        fs::remove_file(fixture.path("ScreenCapture.exe")).unwrap();
+        assert_eq!(fixture.next(), vec!["ScreenCapture.exe", "config.toml"]);
+        fixture.assert_quiet();
+    }
+
+    #[test]
+    fn ignores_unrelated_files() {
+        let fixture = Fixture::new("unrelated");
+        fs::write(fixture.path("notes.txt"), "unrelated").unwrap();
+        fs::rename(fixture.path("notes.txt"), fixture.path("notes.md")).unwrap();
+        fs::remove_file(fixture.path("notes.md")).unwrap();
+        fixture.assert_quiet();
+    }
+}
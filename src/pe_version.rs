@@ -0,0 +1,59 @@
+//! PE 文件版本信息解析模块
+//!
+//! 本模块负责：
+//! - 从 exe 文件数据中找到 VS_VERSION_INFO 资源
+//! - 读取其中 VS_FIXEDFILEINFO 的文件版本号
+//!
+//! 不依赖 Windows API，编译脚本（build.rs）通过 `#[path]` 引用本文件，
+//! 与启动时比较数据目录中 exe 的版本共用
+
+/// VS_FIXEDFILEINFO 的签名
+const FIXED_FILE_INFO_SIGNATURE: u32 = 0xFEEF04BD;
+
+/// 读取 exe 的文件版本号
+///
+/// ### 参数
+/// - `data`: exe 文件数据
+///
+/// ### 返回值
+/// - `Option<[u16; 4]>`: 主版本、次版本、生成号、修订号，没有版本资源时返回 `None`
+///
+/// ### 说明
+/// - 查找 UTF-16 编码的 `VS_VERSION_INFO` 键，其后按 4 字节对齐即为 VS_FIXEDFILEINFO
+/// - 资源数据在文件中按 4 字节对齐，因此以文件偏移对齐即可
+pub fn file_version(data: &[u8]) -> Option<[u16; 4]> {
+    let key: Vec<u8> = "VS_VERSION_INFO\0"
+        .encode_utf16()
+        .flat_map(u16::to_le_bytes)
+        .collect();
+    let read_u32 = |pos: usize| -> Option<u32> {
+        Some(u32::from_le_bytes(data.get(pos..pos + 4)?.try_into().ok()?))
+    };
+
+    let mut from = 0;
+    while let Some(found) = data[from..].windows(key.len()).position(|w| w == key) {
+        let start = from + found;
+        let info = (start + key.len() + 3) & !3;
+        if read_u32(info) == Some(FIXED_FILE_INFO_SIGNATURE) {
+            // 签名、结构版本之后依次为 FileVersionMS、FileVersionLS
+            let ms = read_u32(info + 8)?;
+            let ls = read_u32(info + 12)?;
+            return Some([(ms >> 16) as u16, ms as u16, (ls >> 16) as u16, ls as u16]);
+        }
+        from = start + 1;
+    }
+    None
+}
+
+/// 格式化版本号
+///
+/// ### 说明
+/// - 修订号为 0 时省略，如 `2.3.3.0` 显示为 `2.3.3`
+pub fn format_version(version: [u16; 4]) -> String {
+    let [major, minor, build, revision] = version;
+    if revision == 0 {
+        format!("{}.{}.{}", major, minor, build)
+    } else {
+        format!("{}.{}.{}.{}", major, minor, build, revision)
+    }
+}
@@ -0,0 +1,482 @@
+//! 截图后处理模块
+//!
+//! 本模块负责：
+//! - 在进程内将保存的截图转换为 PNG、JPEG 或 WebP
+//! - 按最长边等比缩小截图
+//! - 添加文字与图标水印
+//! - 去除或保留 EXIF、ICC 等元数据
+//! - 按需将原图保留到 originals 子目录
+
+use crate::capture::{CaptureJob, move_no_clobber};
+use crate::types::*;
+use crate::watermark::{apply_watermark, render_watermark_text};
+use image::{
+    DynamicImage, ImageDecoder, ImageEncoder, ImageError, ImageFormat, ImageReader, Rgb, RgbImage,
+    codecs::{
+        bmp::BmpEncoder,
+        jpeg::JpegEncoder,
+        png::{CompressionType, FilterType as PngFilter, PngEncoder},
+        webp::WebPEncoder,
+    },
+    error::{EncodingError, ImageFormatHint},
+    imageops::FilterType,
+};
+use std::{
+    fs, io,
+    path::{Path, PathBuf},
+};
+
+/// 原图保留子目录名称
+pub const ORIGINALS_DIR: &str = "originals";
+
+/// 对已保存的截图执行后处理
+///
+/// ### 参数
+/// - `ctx`: 截图任务上下文
+/// - `job`: 截图任务
+/// - `file`: 已保存的截图
+///
+/// ### 返回值
+/// - `PathBuf`: 处理后的文件，未启用或处理失败时为原文件
+///
+/// ### 说明
+/// - 按截图模式、配置名选择 `[convert]` 与 `[watermark]` 设置
+/// - 只调用于保存到文件的截图，剪贴板与钉图不做处理
+/// - 只添加水印时保持原格式与元数据
+/// - 处理失败时保留原文件不变，不影响后续流程；水印无法绘制时仍按转换设置保存
+pub fn postprocess_capture(ctx: &CaptureContext, job: &CaptureJob, file: &Path) -> PathBuf {
+    let profile = &ctx.settings.sundry.profile;
+    let convert = ctx.settings.convert.select(job.mode, profile);
+    let watermark = ctx.settings.watermark.select(job.mode, profile);
+    if !convert.enabled && !watermark.enabled {
+        return file.to_path_buf();
+    }
+
+    let passthrough = ConvertConfig {
+        enabled: true,
+        format: ConvertFormat::Keep,
+        max_dimension: 0,
+        strip_metadata: false,
+        keep_original: false,
+        ..convert.clone()
+    };
+    let config = if convert.enabled {
+        convert
+    } else {
+        &passthrough
+    };
+    let text = if watermark.enabled {
+        render_watermark_text(ctx, job, &watermark.text)
+    } else {
+        String::new()
+    };
+    let edit = |img: &mut DynamicImage| {
+        if watermark.enabled
+            && let Err(e) = apply_watermark(img, watermark, &text)
+        {
+            eprintln!("Failed to apply watermark: {}", e);
+        }
+    };
+
+    match convert_image(file, config, edit) {
+        Ok(output) => {
+            println!("Converted: {}", output.display());
+            output
+        }
+        Err(e) => {
+            eprintln!("Failed to convert {}: {}", file.display(), e);
+            file.to_path_buf()
+        }
+    }
+}
+
+/// 转换单张图片
+///
+/// ### 参数
+/// - `file`: 源图片
+/// - `config`: 格式转换配置
+/// - `edit`: 缩放后、编码前对图片的额外处理（如添加水印）
+///
+/// ### 返回值
+/// - `io::Result<PathBuf>`: 转换后的文件
+///
+/// ### 功能
+/// - 解码源图片，按 max_dimension 等比缩小，再执行 `edit`
+/// - 按目标格式重新编码，先写入临时文件
+/// - 保留原图时移入 originals 子目录，否则删除（扩展名相同时直接替换）
+/// - 扩展名改变且目标文件已存在时自动追加序号
+pub fn convert_image(
+    file: &Path,
+    config: &ConvertConfig,
+    edit: impl FnOnce(&mut DynamicImage),
+) -> io::Result<PathBuf> {
+    let dir = file.parent().unwrap_or(Path::new(""));
+    let stem = file
+        .file_stem()
+        .map(|s| s.to_string_lossy().to_string())
+        .unwrap_or_default();
+    let src_ext = file
+        .extension()
+        .map(|s| s.to_string_lossy().to_string())
+        .unwrap_or_default();
+    let ext = config
+        .format
+        .ext()
+        .map(String::from)
+        .unwrap_or_else(|| src_ext.to_ascii_lowercase());
+
+    let mut decoder = ImageReader::open(file)?
+        .with_guessed_format()?
+        .into_decoder()
+        .map_err(io::Error::other)?;
+    let (icc, exif) = if config.strip_metadata {
+        (None, None)
+    } else {
+        (
+            decoder.icc_profile().ok().flatten(),
+            decoder.exif_metadata().ok().flatten(),
+        )
+    };
+    let mut img = DynamicImage::from_decoder(decoder).map_err(io::Error::other)?;
+
+    let max = config.max_dimension;
+    if max > 0 && (img.width() > max || img.height() > max) {
+        img = img.resize(max, max, FilterType::Lanczos3);
+    }
+    edit(&mut img);
+
+    let buffer = encode(&img, &ext, config.quality, icc, exif).map_err(io::Error::other)?;
+    let temp = dir.join(format!(".{}.{}.part", stem, ext));
+    fs::write(&temp, buffer)?;
+
+    if config.keep_original {
+        let originals = dir.join(ORIGINALS_DIR);
+        let kept = fs::create_dir_all(&originals)
+            .and_then(|_| move_no_clobber(file, &originals, &stem, &src_ext));
+        if let Err(e) = kept {
+            let _ = fs::remove_file(&temp);
+            return Err(e);
+        }
+    }
+
+    if src_ext.eq_ignore_ascii_case(&ext) {
+        fs::rename(&temp, file).inspect_err(|_| {
+            let _ = fs::remove_file(&temp);
+        })?;
+        return Ok(file.to_path_buf());
+    }
+    let output = move_no_clobber(&temp, dir, &stem, &ext).inspect_err(|_| {
+        let _ = fs::remove_file(&temp);
+    })?;
+    if !config.keep_original
+        && let Err(e) = fs::remove_file(file)
+    {
+        eprintln!("Failed to remove {}: {}", file.display(), e);
+    }
+    Ok(output)
+}
+
+/// 按扩展名编码图片
+///
+/// ### 参数
+/// - `img`: 图片
+/// - `ext`: 目标扩展名（小写）
+/// - `quality`: JPEG / WebP 质量
+/// - `icc` / `exif`: 需要保留的元数据，编码器不支持时忽略
+///
+/// ### 说明
+/// - PNG 使用最高压缩等级与自适应过滤
+/// - WebP 在 quality 低于 100 时有损编码，等于 100 时无损编码
+/// - 未知扩展名按 PNG 编码
+fn encode(
+    img: &DynamicImage,
+    ext: &str,
+    quality: u8,
+    icc: Option<Vec<u8>>,
+    exif: Option<Vec<u8>>,
+) -> image::ImageResult<Vec<u8>> {
+    let mut buffer = Vec::new();
+    match ext {
+        "jpg" | "jpeg" => {
+            let mut encoder = JpegEncoder::new_with_quality(&mut buffer, quality);
+            attach_metadata(&mut encoder, icc, exif);
+            flatten_alpha(img).write_with_encoder(encoder)?;
+        }
+        "webp" => {
+            let img = if img.color().has_alpha() {
+                DynamicImage::ImageRgba8(img.to_rgba8())
+            } else {
+                DynamicImage::ImageRgb8(img.to_rgb8())
+            };
+            if quality < 100 {
+                return encode_lossy_webp(&img, quality, icc, exif);
+            }
+            let mut encoder = WebPEncoder::new_lossless(&mut buffer);
+            attach_metadata(&mut encoder, icc, exif);
+            img.write_with_encoder(encoder)?;
+        }
+        "bmp" => {
+            let mut encoder = BmpEncoder::new(&mut buffer);
+            attach_metadata(&mut encoder, icc, exif);
+            img.write_with_encoder(encoder)?;
+        }
+        _ => {
+            let mut encoder = PngEncoder::new_with_quality(
+                &mut buffer,
+                CompressionType::Best,
+                PngFilter::Adaptive,
+            );
+            attach_metadata(&mut encoder, icc, exif);
+            img.write_with_encoder(encoder)?;
+        }
+    }
+    Ok(buffer)
+}
+
+/// 使用 libwebp 有损编码 WebP
+///
+/// ### 参数
+/// - `img`: RGB 或 RGBA 图片
+/// - `quality`: 质量（1-99）
+/// - `icc` / `exif`: 需要保留的元数据
+///
+/// ### 说明
+/// - 透明通道单独压缩为 ALPH 块
+/// - libwebp 的简易编码接口不写入元数据，需要保留时重新封装为扩展格式（VP8X）
+fn encode_lossy_webp(
+    img: &DynamicImage,
+    quality: u8,
+    icc: Option<Vec<u8>>,
+    exif: Option<Vec<u8>>,
+) -> image::ImageResult<Vec<u8>> {
+    let (width, height) = (img.width(), img.height());
+    let encoder = if img.color().has_alpha() {
+        webp::Encoder::from_rgba(img.as_bytes(), width, height)
+    } else {
+        webp::Encoder::from_rgb(img.as_bytes(), width, height)
+    };
+    let memory = encoder.encode_simple(false, quality as f32).map_err(|e| {
+        ImageError::Encoding(EncodingError::new(
+            ImageFormatHint::Exact(ImageFormat::WebP),
+            format!("{:?}", e),
+        ))
+    })?;
+    if icc.is_none() && exif.is_none() {
+        return Ok(memory.to_vec());
+    }
+    Ok(wrap_webp_metadata(&memory, width, height, icc, exif))
+}
+
+/// 将 ICC、EXIF 写入 WebP 文件
+///
+/// ### 参数
+/// - `data`: 编码后的 WebP 文件
+/// - `width` / `height`: 画布尺寸
+/// - `icc` / `exif`: 需要写入的元数据
+///
+/// ### 返回值
+/// - `Vec<u8>`: 按 VP8X、ICCP、图像数据、EXIF 顺序重新封装的文件
+fn wrap_webp_metadata(
+    data: &[u8],
+    width: u32,
+    height: u32,
+    icc: Option<Vec<u8>>,
+    exif: Option<Vec<u8>>,
+) -> Vec<u8> {
+    let mut image_chunks = Vec::new();
+    let mut has_alpha = false;
+    let mut pos = 12;
+    while pos + 8 <= data.len() {
+        let fourcc = &data[pos..pos + 4];
+        let size = u32::from_le_bytes(data[pos + 4..pos + 8].try_into().unwrap()) as usize;
+        let end = (pos + 8 + size).min(data.len());
+        has_alpha |= fourcc == b"ALPH";
+        if fourcc != b"VP8X" {
+            push_riff_chunk(&mut image_chunks, fourcc, &data[pos + 8..end]);
+        }
+        pos = end + (size & 1);
+    }
+
+    let mut flags = 0u8;
+    if icc.is_some() {
+        flags |= 0x20;
+    }
+    if has_alpha {
+        flags |= 0x10;
+    }
+    if exif.is_some() {
+        flags |= 0x08;
+    }
+    let mut vp8x = vec![flags, 0, 0, 0];
+    vp8x.extend_from_slice(&(width - 1).to_le_bytes()[..3]);
+    vp8x.extend_from_slice(&(height - 1).to_le_bytes()[..3]);
+
+    let mut body = b"WEBP".to_vec();
+    push_riff_chunk(&mut body, b"VP8X", &vp8x);
+    if let Some(icc) = icc {
+        push_riff_chunk(&mut body, b"ICCP", &icc);
+    }
+    body.extend_from_slice(&image_chunks);
+    if let Some(exif) = exif {
+        push_riff_chunk(&mut body, b"EXIF", &exif);
+    }
+
+    let mut output = b"RIFF".to_vec();
+    output.extend_from_slice(&(body.len() as u32).to_le_bytes());
+    output.extend_from_slice(&body);
+    output
+}
+
+/// 写入一个 RIFF 块，奇数长度时补齐一个字节
+fn push_riff_chunk(out: &mut Vec<u8>, fourcc: &[u8], payload: &[u8]) {
+    out.extend_from_slice(fourcc);
+    out.extend_from_slice(&(payload.len() as u32).to_le_bytes());
+    out.extend_from_slice(payload);
+    if payload.len() % 2 == 1 {
+        out.push(0);
+    }
+}
+
+/// 将元数据附加到编码器，不支持的类型直接忽略
+fn attach_metadata(encoder: &mut impl ImageEncoder, icc: Option<Vec<u8>>, exif: Option<Vec<u8>>) {
+    if let Some(icc) = icc {
+        let _ = encoder.set_icc_profile(icc);
+    }
+    if let Some(exif) = exif {
+        let _ = encoder.set_exif_metadata(exif);
+    }
+}
+
+/// 将透明部分以白色填充，转换为 RGB 图片（JPEG 不支持透明通道）
+fn flatten_alpha(img: &DynamicImage) -> DynamicImage {
+    if !img.color().has_alpha() {
+        return DynamicImage::ImageRgb8(img.to_rgb8());
+    }
+    let rgba = img.to_rgba8();
+    let rgb = RgbImage::from_fn(rgba.width(), rgba.height(), |x, y| {
+        let [r, g, b, a] = rgba.get_pixel(x, y).0;
+        let blend = |c: u8| ((c as u32 * a as u32 + 255 * (255 - a as u32) + 127) / 255) as u8;
+        Rgb([blend(r), blend(g), blend(b)])
+    });
+    DynamicImage::ImageRgb8(rgb)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::capture::tests::temp_dir;
+
+    /// 写入一张 4x3 的 PNG 截图
+    fn write_png(path: &Path) {
+        RgbImage::from_fn(4, 3, |x, y| Rgb([x as u8 * 60, y as u8 * 80, 200]))
+            .save(path)
+            .unwrap();
+    }
+
+    fn config(format: ConvertFormat, keep_original: bool) -> ConvertConfig {
+        ConvertConfig {
+            enabled: true,
+            format,
+            keep_original,
+            ..ConvertConfig::default()
+        }
+    }
+
+    fn file_names(dir: &Path) -> Vec<String> {
+        let mut names: Vec<String> = fs::read_dir(dir)
+            .unwrap()
+            .map(|e| e.unwrap().file_name().to_string_lossy().to_string())
+            .collect();
+        names.sort();
+        names
+    }
+
+    #[test]
+    fn same_extension_replaces_in_place() {
+        let dir = temp_dir("convert_same");
+        let file = dir.join("shot.png");
+        write_png(&file);
+
+        let output = convert_image(&file, &config(ConvertFormat::Png, false), |_| {}).unwrap();
+        assert_eq!(output, file);
+        assert_eq!(file_names(&dir), ["shot.png"]);
+        assert_eq!(image::open(&output).unwrap().width(), 4);
+    }
+
+    #[test]
+    fn new_extension_avoids_existing_file() {
+        let dir = temp_dir("convert_collision");
+        let file = dir.join("shot.png");
+        write_png(&file);
+        fs::write(dir.join("shot.jpg"), "existing").unwrap();
+
+        let output = convert_image(&file, &config(ConvertFormat::Jpeg, false), |_| {}).unwrap();
+        assert_eq!(output, dir.join("shot_1.jpg"));
+        assert_eq!(file_names(&dir), ["shot.jpg", "shot_1.jpg"]);
+        assert_eq!(
+            fs::read_to_string(dir.join("shot.jpg")).unwrap(),
+            "existing"
+        );
+        assert_eq!(image::open(&output).unwrap().height(), 3);
+    }
+
+    #[test]
+    fn keep_original_moves_source() {
+        let dir = temp_dir("convert_keep");
+        let file = dir.join("shot.png");
+        write_png(&file);
+        let original = fs::read(&file).unwrap();
+
+        let output = convert_image(&file, &config(ConvertFormat::Webp, true), |_| {}).unwrap();
+        assert_eq!(output, dir.join("shot.webp"));
+        assert_eq!(file_names(&dir), [ORIGINALS_DIR, "shot.webp"]);
+        assert_eq!(
+            fs::read(dir.join(ORIGINALS_DIR).join("shot.png")).unwrap(),
+            original
+        );
+        assert_eq!(image::open(&output).unwrap().width(), 4);
+    }
+
+    #[test]
+    fn webp_quality_selects_lossy_or_lossless() {
+        let img = DynamicImage::ImageRgb8(RgbImage::from_fn(16, 16, |x, y| {
+            Rgb([(x * 16) as u8, (y * 16) as u8, ((x + y) * 8) as u8])
+        }));
+        let lossy = encode(&img, "webp", 80, None, None).unwrap();
+        assert_eq!(&lossy[12..16], b"VP8 ");
+        let lossless = encode(&img, "webp", 100, None, None).unwrap();
+        assert_eq!(&lossless[12..16], b"VP8L");
+        let decoded = image::load_from_memory(&lossless).unwrap();
+        assert_eq!(decoded.to_rgb8(), img.to_rgb8());
+    }
+
+    #[test]
+    fn lossy_webp_keeps_metadata() {
+        let img =
+            DynamicImage::ImageRgba8(image::RgbaImage::from_pixel(5, 7, [10, 20, 30, 128].into()));
+        let icc = b"icc profile".to_vec();
+        let exif = b"MM\0*exif".to_vec();
+        let data = encode(&img, "webp", 75, Some(icc.clone()), Some(exif.clone())).unwrap();
+        assert_eq!(&data[12..16], b"VP8X");
+        assert_eq!(data[20], 0x20 | 0x10 | 0x08);
+        assert_eq!(
+            u32::from_le_bytes(data[4..8].try_into().unwrap()) as usize,
+            data.len() - 8
+        );
+
+        let mut decoder = ImageReader::new(io::Cursor::new(&data))
+            .with_guessed_format()
+            .unwrap()
+            .into_decoder()
+            .unwrap();
+        assert_eq!(decoder.icc_profile().unwrap(), Some(icc));
+        assert_eq!(decoder.exif_metadata().unwrap(), Some(exif));
+        assert_eq!(decoder.dimensions(), (5, 7));
+        assert!(
+            DynamicImage::from_decoder(decoder)
+                .unwrap()
+                .color()
+                .has_alpha()
+        );
+    }
+}
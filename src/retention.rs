@@ -0,0 +1,406 @@
+//! 截图目录清理模块
+//!
+//! 本模块负责：
+//! - 按保留天数、总大小、文件数量筛选需要清理的截图
+//! - 根据通配符排除需要保留的文件
+//! - 将过期截图移入 trash 子目录或直接删除
+//! - 定时执行清理并支持仅预览（dry run）
+
+use crate::capture::{is_image, move_no_clobber};
+use crate::metadata::sidecar_path;
+use crate::types::*;
+use std::{
+    fs,
+    path::{Path, PathBuf},
+    sync::{
+        Arc,
+        atomic::{AtomicBool, Ordering},
+    },
+    thread::{self, JoinHandle},
+    time::{Duration, Instant, SystemTime},
+};
+use win_msgbox_timeout::info_msgbox;
+
+/// 回收子目录名称
+pub const TRASH_DIR: &str = "trash";
+
+/// 清理结果
+#[derive(Clone, Debug, Default)]
+pub struct RetentionReport {
+    /// 已清理（或预览模式下将被清理）的文件
+    pub removed: Vec<PathBuf>,
+    /// 释放的空间（字节）
+    pub freed_bytes: u64,
+    /// 处理失败的文件及原因
+    pub errors: Vec<String>,
+    /// 是否为预览模式
+    pub dry_run: bool,
+}
+impl RetentionReport {
+    /// 生成适合在消息框中显示的摘要
+    ///
+    /// ### 参数
+    /// - `lang`: 语言（true: CN, false: EN）
+    /// - `max_lines`: 最多列出的文件数
+    pub fn summary(&self, lang: bool, max_lines: usize) -> String {
+        let mb = self.freed_bytes as f64 / 1024.0 / 1024.0;
+        let mut text = match (self.dry_run, lang) {
+            (true, true) => format!("将清理 {} 个文件，共 {:.1} MB", self.removed.len(), mb),
+            (true, false) => format!(
+                "{} file(s) would be removed, {:.1} MB",
+                self.removed.len(),
+                mb
+            ),
+            (false, true) => format!("已清理 {} 个文件，释放 {:.1} MB", self.removed.len(), mb),
+            (false, false) => format!("Removed {} file(s), freed {:.1} MB", self.removed.len(), mb),
+        };
+        for path in self.removed.iter().take(max_lines) {
+            text.push_str(&format!("\n{}", path.display()));
+        }
+        if self.removed.len() > max_lines {
+            text.push_str(&format!("\n... (+{})", self.removed.len() - max_lines));
+        }
+        for error in &self.errors {
+            text.push_str(&format!("\n[!] {}", error));
+        }
+        text
+    }
+}
+
+/// 待清理的候选文件
+struct Candidate {
+    path: PathBuf,
+    size: u64,
+    modified: SystemTime,
+}
+
+/// 按保留策略清理截图目录
+///
+/// ### 参数
+/// - `save_dir`: 截图保存目录，为空（手动选择）时不做任何事
+/// - `config`: 保留策略
+/// - `dry_run`: 是否仅预览，为 true 时不移动或删除任何文件
+///
+/// ### 返回值
+/// - `RetentionReport`: 清理结果
+///
+/// ### 功能
+/// - 递归扫描保存目录中的截图文件（跳过 trash 子目录与排除规则匹配的文件）
+/// - 按修改时间从新到旧排序，依次累计数量与大小
+/// - 超过保留天数、数量上限或大小上限的文件会被清理
+/// - 保存目录为用户的系统文件夹（桌面、图片等）或其上级目录时不做任何清理，只记录原因
+pub fn run_retention(save_dir: &Path, config: &RetentionConfig, dry_run: bool) -> RetentionReport {
+    let mut report = RetentionReport {
+        dry_run,
+        ..RetentionReport::default()
+    };
+    if save_dir.as_os_str().is_empty() || !save_dir.is_dir() {
+        return report;
+    }
+    // 桌面、图片等系统文件夹中混有用户自己的图片，不能按目录整体清理
+    if is_shell_folder(save_dir) {
+        report.errors.push(format!(
+            "{}: Desktop, Pictures and other user folders are never cleaned up, save captures to a dedicated folder to use retention.",
+            path_display(save_dir, "")
+        ));
+        return report;
+    }
+
+    let excludes: Vec<glob::Pattern> = config
+        .exclude
+        .iter()
+        .filter_map(|pattern| match glob::Pattern::new(pattern) {
+            Ok(p) => Some(p),
+            Err(e) => {
+                eprintln!("Invalid retention exclude pattern {}: {}", pattern, e);
+                None
+            }
+        })
+        .collect();
+
+    let mut candidates = Vec::new();
+    collect_candidates(save_dir, save_dir, &excludes, &mut candidates);
+    candidates.sort_by_key(|c| std::cmp::Reverse(c.modified));
+
+    let now = SystemTime::now();
+    // 数值直接来自配置文件，溢出时按不限制处理，不能回绕为 0 而清理全部文件
+    let max_age = Duration::from_secs((config.max_age_days as u64).saturating_mul(24 * 60 * 60));
+    let max_bytes = config.max_total_mb.saturating_mul(1024 * 1024);
+    let mut kept_bytes: u64 = 0;
+    let mut kept_count: usize = 0;
+
+    for candidate in candidates {
+        let too_old = config.max_age_days > 0
+            && now
+                .duration_since(candidate.modified)
+                .map(|age| age > max_age)
+                .unwrap_or(false);
+        let too_many = config.max_files > 0 && kept_count >= config.max_files;
+        let too_large =
+            config.max_total_mb > 0 && kept_bytes.saturating_add(candidate.size) > max_bytes;
+
+        if !(too_old || too_many || too_large) {
+            kept_count += 1;
+            kept_bytes = kept_bytes.saturating_add(candidate.size);
+            continue;
+        }
+
+        if !dry_run && let Err(e) = remove_file(save_dir, &candidate.path, config.action) {
+            report
+                .errors
+                .push(format!("{}: {}", candidate.path.display(), e));
+            continue;
+        }
+        report.freed_bytes += candidate.size;
+        report.removed.push(candidate.path);
+    }
+    report
+}
+
+/// 列出需要清理的截图目录
+///
+/// ### 参数
+/// - `config`: 路径配置
+///
+/// ### 返回值
+/// - `Vec<PathBuf>`: 当前保存目录与 dir、fallback_dirs 中存在的目录
+///
+/// ### 说明
+/// - 保存目录不可用时截图会转存到备用目录，因此备用目录同样需要清理
+/// - 相同或互相包含的目录只保留外层的一个，避免重复统计
+pub fn retention_dirs(config: &PathConfig) -> Vec<PathBuf> {
+    let mut dirs: Vec<PathBuf> = Vec::new();
+    let configured = std::iter::once(&config.save_path_str)
+        .chain(config.fallback_dirs.iter())
+        .filter_map(|raw| expand_path(raw));
+    for dir in std::iter::once(config.save_path.clone()).chain(configured) {
+        if dir.as_os_str().is_empty() || !dir.is_dir() {
+            continue;
+        }
+        let dir = dir.canonicalize().unwrap_or(dir);
+        if dirs.iter().any(|d| dir.starts_with(d)) {
+            continue;
+        }
+        dirs.retain(|d| !d.starts_with(&dir));
+        dirs.push(dir);
+    }
+    dirs
+}
+
+/// 判断目录是否为用户的系统文件夹或其上级目录
+///
+/// ### 说明
+/// - 包括用户目录、桌面、文档、下载、图片及 Windows 截图保存的 `图片\Screenshots`
+/// - 这些目录中的图片大多不是 SC_Starter 保存的截图
+fn is_shell_folder(dir: &Path) -> bool {
+    let Some(user) = directories::UserDirs::new() else {
+        return false;
+    };
+    let canonical = |path: &Path| path.canonicalize().unwrap_or_else(|_| path.to_path_buf());
+    let dir = canonical(dir);
+    let pictures = user.picture_dir().map(Path::to_path_buf);
+    [
+        Some(user.home_dir().to_path_buf()),
+        user.desktop_dir().map(Path::to_path_buf),
+        user.document_dir().map(Path::to_path_buf),
+        user.download_dir().map(Path::to_path_buf),
+        pictures.as_ref().map(|p| p.join("Screenshots")),
+        pictures,
+    ]
+    .into_iter()
+    .flatten()
+    .any(|folder| canonical(&folder).starts_with(&dir))
+}
+
+/// 依次清理多个目录并合并结果
+///
+/// ### 说明
+/// - 数量与大小上限对每个目录分别计算
+fn run_retention_dirs(
+    dirs: &[PathBuf],
+    config: &RetentionConfig,
+    dry_run: bool,
+) -> RetentionReport {
+    let mut report = RetentionReport {
+        dry_run,
+        ..RetentionReport::default()
+    };
+    for dir in dirs {
+        let part = run_retention(dir, config, dry_run);
+        report.freed_bytes = report.freed_bytes.saturating_add(part.freed_bytes);
+        report.removed.extend(part.removed);
+        report.errors.extend(part.errors);
+    }
+    report
+}
+
+/// 递归收集候选截图文件
+fn collect_candidates(
+    root: &Path,
+    dir: &Path,
+    excludes: &[glob::Pattern],
+    output: &mut Vec<Candidate>,
+) {
+    let entries = match fs::read_dir(dir) {
+        Ok(entries) => entries,
+        Err(e) => {
+            eprintln!("Failed to read {}: {}", dir.display(), e);
+            return;
+        }
+    };
+
+    for entry in entries.filter_map(|e| e.ok()) {
+        let path = entry.path();
+        let relative = path
+            .strip_prefix(root)
+            .map(|p| p.to_string_lossy().replace('\\', "/"))
+            .unwrap_or_default();
+        let options = glob::MatchOptions {
+            case_sensitive: false,
+            ..glob::MatchOptions::new()
+        };
+        if excludes
+            .iter()
+            .any(|pattern| pattern.matches_with(&relative, options))
+        {
+            continue;
+        }
+
+        let Ok(meta) = entry.metadata() else {
+            continue;
+        };
+        if meta.is_dir() {
+            if dir == root && entry.file_name().eq_ignore_ascii_case(TRASH_DIR) {
+                continue;
+            }
+            collect_candidates(root, &path, excludes, output);
+        } else if meta.is_file() && is_image(&path) {
+            output.push(Candidate {
+                path,
+                size: meta.len(),
+                modified: meta.modified().unwrap_or(SystemTime::UNIX_EPOCH),
+            });
+        }
+    }
+}
+
+/// 清理单个文件
+///
+/// ### 参数
+/// - `root`: 截图保存目录
+/// - `path`: 要清理的文件
+/// - `action`: 清理方式
+///
+/// ### 说明
+/// - 移入回收目录时保留相对保存目录的子目录结构，重名时自动追加序号
+/// - 截图的 .json 元数据附属文件随截图一起处理
+fn remove_file(root: &Path, path: &Path, action: RetentionAction) -> std::io::Result<()> {
+    let sidecar = sidecar_path(path);
+    let sidecar = sidecar.is_file().then_some(sidecar);
+    match action {
+        RetentionAction::Delete => {
+            fs::remove_file(path)?;
+            if let Some(sidecar) = sidecar {
+                fs::remove_file(sidecar)?;
+            }
+            Ok(())
+        }
+        RetentionAction::Trash => {
+            let relative_dir = path
+                .parent()
+                .and_then(|p| p.strip_prefix(root).ok())
+                .unwrap_or(Path::new(""));
+            let target_dir = root.join(TRASH_DIR).join(relative_dir);
+            fs::create_dir_all(&target_dir)?;
+            let stem = path
+                .file_stem()
+                .map(|s| s.to_string_lossy().to_string())
+                .unwrap_or_default();
+            let ext = path
+                .extension()
+                .map(|s| s.to_string_lossy().to_string())
+                .unwrap_or_default();
+            let moved = move_no_clobber(path, &target_dir, &stem, &ext)?;
+            if let Some(sidecar) = sidecar {
+                fs::rename(sidecar, sidecar_path(&moved))?;
+            }
+            Ok(())
+        }
+    }
+}
+
+/// 启动定时清理线程
+///
+/// ### 参数
+/// - `ctx`: 截图任务上下文
+/// - `running`: 程序运行状态，变为 false 时线程退出
+///
+/// ### 返回值
+/// - `Option<JoinHandle<()>>`: 未启用保留策略或没有可清理的目录时返回 `None`
+///
+/// ### 功能
+/// - 启动后立即清理一次，之后按 interval_hours 定时清理
+/// - interval_hours 为 0 时只在启动时清理一次
+/// - 保存目录与备用目录都会清理，每次清理前重新检查目录是否存在
+pub fn spawn_retention_scheduler(
+    ctx: &CaptureContext,
+    running: Arc<AtomicBool>,
+) -> Option<JoinHandle<()>> {
+    let config = ctx.settings.retention.clone();
+    let path_config = ctx.settings.path.clone();
+    if !config.enabled || retention_dirs(&path_config).is_empty() {
+        return None;
+    }
+
+    Some(thread::spawn(move || {
+        let interval = Duration::from_secs(config.interval_hours.saturating_mul(60 * 60));
+        loop {
+            let report = run_retention_dirs(&retention_dirs(&path_config), &config, config.dry_run);
+            println!("Retention:\n{}", report.summary(false, 50));
+
+            // 间隔过大（无法表示）时视为不再定时清理
+            let Some(next_run) = Instant::now()
+                .checked_add(interval)
+                .filter(|_| config.interval_hours > 0)
+            else {
+                break;
+            };
+            while Instant::now() < next_run {
+                if !running.load(Ordering::SeqCst) {
+                    return;
+                }
+                thread::sleep(T_SEC_1_2);
+            }
+        }
+    }))
+}
+
+/// 从托盘菜单手动清理
+///
+/// ### 参数
+/// - `ctx`: 截图任务上下文
+/// - `dry_run`: 是否仅预览
+///
+/// ### 功能
+/// - 在新线程中执行清理，不阻塞主事件循环
+/// - 配置中启用 dry_run 时手动清理同样只预览
+/// - 完成后以消息框显示清理结果（预览模式列出将被清理的文件）
+pub fn spawn_manual_cleanup(ctx: &CaptureContext, dry_run: bool) {
+    let ctx = ctx.clone();
+    let dry_run = dry_run || ctx.settings.retention.dry_run;
+    thread::spawn(move || {
+        let report = run_retention_dirs(
+            &retention_dirs(&ctx.settings.path),
+            &ctx.settings.retention,
+            dry_run,
+        );
+        let lang = ctx.settings.sundry.lang;
+        let title = match (dry_run, lang) {
+            (true, true) => "SC_Starter 预览清理",
+            (true, false) => "SC_Starter Clean Up Preview",
+            (false, true) => "SC_Starter 清理截图",
+            (false, false) => "SC_Starter Clean Up",
+        };
+        info_msgbox(report.summary(lang, 20), title, 0);
+    });
+}
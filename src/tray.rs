@@ -2,14 +2,27 @@
 //!
 //! 本模块负责：
 //! - 创建和管理系统托盘图标
-//! - 创建右键上下文菜单（截图、长截图、退出）
+//! - 创建右键上下文菜单（截图、长截图、最近截图、复制为、清理截图、镜像队列、退出）
 //! - 处理托盘图标事件
 //! - 显示程序版本信息
 
-use tray_icon::menu::{Menu, MenuId, MenuItem, PredefinedMenuItem};
+use std::{collections::HashMap, path::PathBuf};
+use tray_icon::menu::{Menu, MenuId, MenuItem, PredefinedMenuItem, Submenu};
 use tray_icon::{Icon, TrayIconBuilder};
 
-use crate::types::{PKG_VERSION, RES_VERSION};
+use crate::manifest::RES_VERSION;
+use crate::types::{PKG_VERSION, SettingsCollection};
+
+/// 最近截图菜单中的文件操作
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum RecentAction {
+    /// 打开文件
+    Open,
+    /// 在文件夹中显示
+    Reveal,
+    /// 复制文件路径
+    CopyPath,
+}
 
 /// 系统托盘管理器
 ///
@@ -25,10 +38,26 @@ pub struct TrayManager {
     pub long_capture_id: MenuId,
     /// 全屏截图 ID
     pub full_capture_id: MenuId,
+    /// 立即清理菜单项 ID
+    pub cleanup_id: MenuId,
+    /// 预览清理菜单项 ID
+    pub cleanup_preview_id: MenuId,
+    /// 镜像队列状态菜单项 ID（点击立即重试）
+    pub mirror_queue_id: MenuId,
     /// 设置菜单项 ID
     pub open_config_id: MenuId,
     /// 退出菜单项 ID
     pub exit_id: MenuId,
+    /// 最近截图子菜单
+    recent_menu: Submenu,
+    /// 镜像队列状态菜单项
+    mirror_queue_item: MenuItem,
+    /// 最近截图菜单项 ID 与对应的操作和文件
+    recent_actions: HashMap<MenuId, (RecentAction, PathBuf)>,
+    /// "复制为"菜单项 ID 与对应的格式名
+    copy_as_actions: HashMap<MenuId, String>,
+    /// 语言（true: CN, false: EN）
+    lang: bool,
 }
 
 impl TrayManager {
@@ -39,14 +68,23 @@ impl TrayManager {
     ///
     /// ### 说明
     /// - 从嵌入的图标数据创建256x256的RGBA图标
-    /// - 创建包含"截图"、"长截图"、"退出"的右键菜单
+    /// - 创建包含"截图"、"长截图"、"最近截图"、"退出"的右键菜单
+    /// - 未启用历史记录或 recent_count 为 0 时不显示"最近截图"子菜单
+    /// - "复制为"子菜单列出 `[copy_as]` 中的所有模板，作用于最近一次保存的截图
+    /// - 启用 `[retention]` 时显示"立即清理"与"预览清理"菜单项
+    /// - 配置了镜像目录时显示镜像队列状态
     /// - 设置包含程序版本和资源版本的提示文本
     /// - 自动构建并显示托盘图标
     ///
     /// ### Panics
     /// - 如果嵌入的图标数据无效，会panic
     /// - 如果构建托盘图标失败，会panic
-    pub fn new(lang: bool) -> Self {
+    pub fn new(settings: &SettingsCollection) -> Self {
+        let lang = settings.sundry.lang;
+        let show_recent = settings.history.enabled && settings.history.recent_count > 0;
+        let show_cleanup = settings.retention.enabled;
+        let show_mirror = !settings.path.mirrors.is_empty();
+
         // 创建托盘图标
         let icon_data = include_bytes!("../logo_raw") as &[u8];
         let icon = Icon::from_rgba(icon_data.to_vec(), 256, 256).expect("Embedded icon is invalid");
@@ -65,6 +103,41 @@ impl TrayManager {
             true,
             None,
         );
+        let recent_menu = Submenu::new(if lang { "最近截图" } else { "Recent" }, true);
+        let copy_as_menu = Submenu::new(
+            if lang {
+                "复制最近截图为"
+            } else {
+                "Copy Last Capture As"
+            },
+            true,
+        );
+        let mut copy_as_actions = HashMap::new();
+        for (name, _) in &settings.copy_as.templates {
+            let item = MenuItem::new(name, true, None);
+            copy_as_actions.insert(item.id().clone(), name.clone());
+            copy_as_menu.append(&item).unwrap();
+        }
+        let show_copy_as = !copy_as_actions.is_empty();
+        let menu_cleanup = MenuItem::new(
+            if lang {
+                "立即清理截图"
+            } else {
+                "Clean Up Now"
+            },
+            true,
+            None,
+        );
+        let menu_cleanup_preview = MenuItem::new(
+            if lang {
+                "预览清理"
+            } else {
+                "Preview Clean Up"
+            },
+            true,
+            None,
+        );
+        let mirror_queue_item = MenuItem::new("", true, None);
         let menu_open_config = MenuItem::new(if lang { "设置" } else { "Settings" }, true, None);
         let menu_exit = MenuItem::new(if lang { "退出" } else { "Exit" }, true, None);
 
@@ -72,6 +145,24 @@ impl TrayManager {
         menu.append(&menu_long_capture).unwrap();
         menu.append(&menu_full_capture).unwrap();
         menu.append(&PredefinedMenuItem::separator()).unwrap();
+        if show_recent {
+            menu.append(&recent_menu).unwrap();
+        }
+        if show_copy_as {
+            menu.append(&copy_as_menu).unwrap();
+        }
+        if show_recent || show_copy_as {
+            menu.append(&PredefinedMenuItem::separator()).unwrap();
+        }
+        if show_cleanup {
+            menu.append(&menu_cleanup).unwrap();
+            menu.append(&menu_cleanup_preview).unwrap();
+            menu.append(&PredefinedMenuItem::separator()).unwrap();
+        }
+        if show_mirror {
+            menu.append(&mirror_queue_item).unwrap();
+            menu.append(&PredefinedMenuItem::separator()).unwrap();
+        }
         menu.append(&menu_open_config).unwrap();
         menu.append(&PredefinedMenuItem::separator()).unwrap();
         menu.append(&menu_exit).unwrap();
@@ -80,6 +171,9 @@ impl TrayManager {
         let capture_id = menu_capture.id().clone();
         let long_capture_id = menu_long_capture.id().clone();
         let full_capture_id = menu_full_capture.id().clone();
+        let cleanup_id = menu_cleanup.id().clone();
+        let cleanup_preview_id = menu_cleanup_preview.id().clone();
+        let mirror_queue_id = mirror_queue_item.id().clone();
         let open_config_id = menu_open_config.id().clone();
         let exit_id = menu_exit.id().clone();
 
@@ -91,17 +185,114 @@ impl TrayManager {
             .build()
             .unwrap();
 
-        Self {
+        let mut manager = Self {
             tray_icon,
             menu,
             capture_id,
             long_capture_id,
             full_capture_id,
+            cleanup_id,
+            cleanup_preview_id,
+            mirror_queue_id,
             open_config_id,
             exit_id,
+            recent_menu,
+            mirror_queue_item,
+            recent_actions: HashMap::new(),
+            copy_as_actions,
+            lang,
+        };
+        manager.refresh_recent(&[]);
+        manager.set_mirror_pending(0);
+        manager
+    }
+
+    /// 重建"最近截图"子菜单
+    ///
+    /// ### 参数
+    /// - `files`: 最近的截图文件，按时间从新到旧排列
+    ///
+    /// ### 说明
+    /// - 每个文件对应一个子菜单，包含"打开"、"在文件夹中显示"、"复制路径"
+    /// - 没有文件时显示一个不可用的"（空）"菜单项
+    pub fn refresh_recent(&mut self, files: &[PathBuf]) {
+        while self.recent_menu.remove_at(0).is_some() {}
+        self.recent_actions.clear();
+
+        let lang = self.lang;
+        if files.is_empty() {
+            let empty = MenuItem::new(if lang { "（空）" } else { "(Empty)" }, false, None);
+            self.recent_menu.append(&empty).unwrap();
+            return;
+        }
+
+        for file in files {
+            let name = file
+                .file_name()
+                .map(|n| n.to_string_lossy().to_string())
+                .unwrap_or_else(|| file.display().to_string());
+            let entry = Submenu::new(name, true);
+            let actions = [
+                (RecentAction::Open, if lang { "打开" } else { "Open" }),
+                (
+                    RecentAction::Reveal,
+                    if lang {
+                        "在文件夹中显示"
+                    } else {
+                        "Show in Folder"
+                    },
+                ),
+                (
+                    RecentAction::CopyPath,
+                    if lang { "复制路径" } else { "Copy Path" },
+                ),
+            ];
+            for (action, text) in actions {
+                let item = MenuItem::new(text, true, None);
+                self.recent_actions
+                    .insert(item.id().clone(), (action, file.clone()));
+                entry.append(&item).unwrap();
+            }
+            self.recent_menu.append(&entry).unwrap();
         }
     }
 
+    /// 查找"最近截图"菜单项对应的操作
+    ///
+    /// ### 参数
+    /// - `id`: 菜单事件 ID
+    ///
+    /// ### 返回值
+    /// - `Option<(RecentAction, PathBuf)>`: 操作类型与目标文件，非最近截图菜单项时返回 `None`
+    pub fn recent_action(&self, id: &MenuId) -> Option<(RecentAction, PathBuf)> {
+        self.recent_actions.get(id).cloned()
+    }
+
+    /// 查找"复制为"菜单项对应的格式名
+    ///
+    /// ### 参数
+    /// - `id`: 菜单事件 ID
+    ///
+    /// ### 返回值
+    /// - `Option<String>`: 格式名，非"复制为"菜单项时返回 `None`
+    pub fn copy_as_action(&self, id: &MenuId) -> Option<String> {
+        self.copy_as_actions.get(id).cloned()
+    }
+
+    /// 更新镜像队列状态
+    ///
+    /// ### 参数
+    /// - `pending`: 待重试的镜像任务数
+    pub fn set_mirror_pending(&self, pending: usize) {
+        let text = match (pending, self.lang) {
+            (0, true) => "镜像队列：已全部同步".to_string(),
+            (0, false) => "Mirror queue: all synced".to_string(),
+            (n, true) => format!("镜像队列：{} 个待同步（点击重试）", n),
+            (n, false) => format!("Mirror queue: {} pending (click to retry)", n),
+        };
+        self.mirror_queue_item.set_text(text);
+    }
+
     /// 临时隐藏菜单以避免被截图
     pub fn hide_menu(&self) {
         self.tray_icon.set_menu(None);
@@ -1,15 +1,16 @@
 use std::{
     collections::HashMap,
-    env,
-    path::{Component, PathBuf, Prefix},
+    env, fs,
+    path::{Component, Path, PathBuf, Prefix},
+    sync::Arc,
 };
 use windows_hotkeys::keys::{ModKey, VKey};
 
+use crate::engine::CaptureEngine;
+
 use win_msgbox_timeout::warn_msgbox;
 
-/// 嵌入式 ScreenCapture 程序的相关信息与程序信息
-pub static RES_HASH_SHA1: &str = "5857D9E31E9B29739FA051DF537F36E8C1986528";
-pub static RES_VERSION: &str = "2.3.3";
+/// 程序信息（嵌入式 ScreenCapture 的信息见 manifest 模块）
 pub static PKG_VERSION: &str = env!("CARGO_PKG_VERSION");
 pub static PKG_BUILD_TIME: &str = env!("VERGEN_BUILD_TIMESTAMP");
 pub static DEBUG: bool = cfg!(debug_assertions);
@@ -17,18 +18,216 @@ pub static DEBUG: bool = cfg!(debug_assertions);
 pub const T_SEC_1_100: std::time::Duration = std::time::Duration::from_millis(10);
 pub const T_SEC_1_2: std::time::Duration = std::time::Duration::from_millis(500);
 
+/// 是/否对话框点击“是”时的返回值
+pub const IDYES: i32 = 6;
+
+/// ScreenCapture 退出码约定
+pub const EXIT_CANCELLED: i32 = 0;
+pub const EXIT_PINNED: i32 = 7;
+pub const EXIT_SAVED_FILE: i32 = 8;
+pub const EXIT_CLIPBOARD: i32 = 9;
+
+/// 截图模式
+/// 对应托盘菜单与快捷键触发的各项操作
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum CaptureMode {
+    /// 区域截图
+    Region,
+    /// 长截图
+    Long,
+    /// 全屏截图
+    Fullscreen,
+    /// 钉图（将剪贴板图像钉到屏幕）
+    Pin,
+}
+impl CaptureMode {
+    /// 全部截图模式
+    pub const ALL: [CaptureMode; 4] = [
+        CaptureMode::Region,
+        CaptureMode::Long,
+        CaptureMode::Fullscreen,
+        CaptureMode::Pin,
+    ];
+
+    /// 模式名称，用于模板占位符与日志
+    pub fn key(&self) -> &'static str {
+        match self {
+            CaptureMode::Region => "region",
+            CaptureMode::Long => "long",
+            CaptureMode::Fullscreen => "fullscreen",
+            CaptureMode::Pin => "pin",
+        }
+    }
+}
+impl std::fmt::Display for CaptureMode {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.key())
+    }
+}
+
+/// 截图结果
+/// 由 ScreenCapture 的退出状态转换而来，供通知及后续处理使用
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum CaptureOutcome {
+    /// 已保存到文件（退出码 8）
+    SavedToFile,
+    /// 已复制到剪贴板（退出码 9）
+    CopiedToClipboard,
+    /// 已钉到屏幕（退出码 7）
+    Pinned,
+    /// 用户取消（退出码 0）
+    Cancelled,
+    /// 异常退出（无退出码、NTSTATUS 错误码或启动失败）
+    Crashed(Option<i32>),
+    /// 未知退出码
+    Unknown(i32),
+}
+impl CaptureOutcome {
+    /// 根据进程退出码生成截图结果
+    ///
+    /// ### 参数
+    /// - `code`: 进程退出码，进程被强制终止时为 `None`
+    ///
+    /// ### 返回值
+    /// - `CaptureOutcome`: 对应的截图结果
+    pub fn from_code(code: Option<i32>) -> Self {
+        match code {
+            Some(EXIT_SAVED_FILE) => CaptureOutcome::SavedToFile,
+            Some(EXIT_CLIPBOARD) => CaptureOutcome::CopiedToClipboard,
+            Some(EXIT_PINNED) => CaptureOutcome::Pinned,
+            Some(EXIT_CANCELLED) => CaptureOutcome::Cancelled,
+            // 0xC0000000 以上的 NTSTATUS 错误码按 i32 解释为负数
+            Some(x) if x < 0 => CaptureOutcome::Crashed(Some(x)),
+            Some(x) => CaptureOutcome::Unknown(x),
+            None => CaptureOutcome::Crashed(None),
+        }
+    }
+
+    /// 原始退出码
+    pub fn code(&self) -> Option<i32> {
+        match self {
+            CaptureOutcome::SavedToFile => Some(EXIT_SAVED_FILE),
+            CaptureOutcome::CopiedToClipboard => Some(EXIT_CLIPBOARD),
+            CaptureOutcome::Pinned => Some(EXIT_PINNED),
+            CaptureOutcome::Cancelled => Some(EXIT_CANCELLED),
+            CaptureOutcome::Crashed(code) => *code,
+            CaptureOutcome::Unknown(code) => Some(*code),
+        }
+    }
+
+    /// 结果名称，同时也是配置文件 notification 段中的键名
+    pub fn key(&self) -> &'static str {
+        match self {
+            CaptureOutcome::SavedToFile => "saved",
+            CaptureOutcome::CopiedToClipboard => "clipboard",
+            CaptureOutcome::Pinned => "pinned",
+            CaptureOutcome::Cancelled => "cancelled",
+            CaptureOutcome::Crashed(_) => "crashed",
+            CaptureOutcome::Unknown(_) => "unknown",
+        }
+    }
+}
+impl std::fmt::Display for CaptureOutcome {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.key())
+    }
+}
+
+/// 截图结果通知模板
+/// 支持占位符 `{code}`（退出码），模板为空时不显示该结果的通知
+#[derive(Clone, Debug)]
+pub struct NotifyTemplates {
+    pub saved: String,
+    pub clipboard: String,
+    pub pinned: String,
+    pub cancelled: String,
+    pub crashed: String,
+    pub unknown: String,
+}
+impl NotifyTemplates {
+    /// 根据语言生成默认模板（true: CN, false: EN）
+    pub fn localized(lang: bool) -> Self {
+        let pick = |cn: &str, en: &str| if lang { cn.to_owned() } else { en.to_owned() };
+        NotifyTemplates {
+            saved: pick("已保存到文件", "Saved to file"),
+            clipboard: pick("已保存到剪贴板", "Copied to clipboard"),
+            pinned: pick("已钉到屏幕", "Pinned to screen"),
+            // 取消截图（如按 Esc）默认不通知，需要时在配置中填写
+            cancelled: String::new(),
+            crashed: pick(
+                "截图程序异常退出（{code}）",
+                "ScreenCapture exited abnormally ({code})",
+            ),
+            unknown: pick("未知的退出码：{code}", "Unknown exit code: {code}"),
+        }
+    }
+
+    /// 获取指定结果对应的模板
+    pub fn get(&self, outcome: &CaptureOutcome) -> &str {
+        match outcome {
+            CaptureOutcome::SavedToFile => &self.saved,
+            CaptureOutcome::CopiedToClipboard => &self.clipboard,
+            CaptureOutcome::Pinned => &self.pinned,
+            CaptureOutcome::Cancelled => &self.cancelled,
+            CaptureOutcome::Crashed(_) => &self.crashed,
+            CaptureOutcome::Unknown(_) => &self.unknown,
+        }
+    }
+
+    /// 获取指定结果对应的模板（可变）
+    pub fn get_mut(&mut self, key: &str) -> Option<&mut String> {
+        match key {
+            "saved" => Some(&mut self.saved),
+            "clipboard" => Some(&mut self.clipboard),
+            "pinned" => Some(&mut self.pinned),
+            "cancelled" => Some(&mut self.cancelled),
+            "crashed" => Some(&mut self.crashed),
+            "unknown" => Some(&mut self.unknown),
+            _ => None,
+        }
+    }
+
+    /// 渲染通知文本
+    ///
+    /// ### 参数
+    /// - `outcome`: 截图结果
+    ///
+    /// ### 返回值
+    /// - `Option<String>`: 渲染后的文本，模板为空时返回 `None`
+    pub fn render(&self, outcome: &CaptureOutcome) -> Option<String> {
+        let template = self.get(outcome).trim();
+        if template.is_empty() {
+            return None;
+        }
+        let code = outcome
+            .code()
+            .map(|c| c.to_string())
+            .unwrap_or_else(|| "N/A".to_owned());
+        Some(template.replace("{code}", &code))
+    }
+}
+impl Default for NotifyTemplates {
+    fn default() -> Self {
+        NotifyTemplates::localized(Sundry::default().lang)
+    }
+}
+
 /// 文件存在状态结构体
 /// 用于跟踪主程序所需的关键文件状态和版本信息
 #[derive(Clone, Copy, Debug, Default)]
 pub struct FileExist {
     /// ScreenCapture 可执行文件是否存在
     pub exe_exist: bool,
-    /// ScreenCapture 可执行文件是否为最新版本
+    /// ScreenCapture 可执行文件是否与嵌入的版本一致
     pub exe_latest: bool,
+    /// ScreenCapture 可执行文件的文件版本（无法读取时为 `None`）
+    pub exe_version: Option<[u16; 4]>,
     /// 配置文件是否存在
     pub conf_exist: bool,
     /// 示例配置文件是否存在
     pub conf_example_exist: bool,
+    /// 示例配置文件是否与嵌入的版本一致
+    pub conf_example_latest: bool,
 }
 
 /// 杂项设置结构体
@@ -45,6 +244,8 @@ pub struct Sundry {
     pub lang: bool,
     /// 通知
     pub notification: bool,
+    /// 当前配置名（用于文件名模板等）
+    pub profile: String,
 }
 impl Default for Sundry {
     fn default() -> Self {
@@ -54,6 +255,543 @@ impl Default for Sundry {
             scale_level: 100,
             lang: true,
             notification: false,
+            profile: "default".to_owned(),
+        }
+    }
+}
+
+/// 截图后钩子配置结构体
+/// 对应配置文件中的一个 `[[hooks.post_capture]]` 条目
+#[derive(Clone, Debug)]
+pub struct HookConfig {
+    /// 要执行的程序（可为 PATH 中的命令名）
+    pub command: String,
+    /// 命令行参数，支持 `{file}`、`{dir}`、`{mode}`、`{outcome}`、`{code}` 占位符
+    pub args: Vec<String>,
+    /// 工作目录模板，为空时使用截图所在目录
+    pub cwd: String,
+    /// 超时时间，超时后终止钩子进程
+    pub timeout: std::time::Duration,
+    /// 仅在这些退出码时执行，为空时在保存到文件或剪贴板后执行
+    pub exit_codes: Vec<i32>,
+}
+impl Default for HookConfig {
+    fn default() -> Self {
+        HookConfig {
+            command: String::new(),
+            args: Vec::new(),
+            cwd: String::new(),
+            timeout: std::time::Duration::from_secs(30),
+            exit_codes: Vec::new(),
+        }
+    }
+}
+impl HookConfig {
+    /// 判断指定截图结果是否需要执行该钩子
+    pub fn matches(&self, outcome: &CaptureOutcome) -> bool {
+        if self.exit_codes.is_empty() {
+            matches!(
+                outcome,
+                CaptureOutcome::SavedToFile | CaptureOutcome::CopiedToClipboard
+            )
+        } else {
+            outcome
+                .code()
+                .map(|code| self.exit_codes.contains(&code))
+                .unwrap_or(false)
+        }
+    }
+}
+
+/// 截图历史记录配置结构体
+#[derive(Clone, Debug)]
+pub struct HistoryConfig {
+    /// 是否记录截图历史
+    pub enabled: bool,
+    /// 最多保留的记录条数
+    pub max_entries: usize,
+    /// 记录保留天数，0 表示不按时间清理
+    pub retention_days: u32,
+    /// 托盘“最近截图”菜单显示的文件数
+    pub recent_count: usize,
+}
+impl Default for HistoryConfig {
+    fn default() -> Self {
+        HistoryConfig {
+            enabled: true,
+            max_entries: 1000,
+            retention_days: 90,
+            recent_count: 10,
+        }
+    }
+}
+
+/// 清理截图时对文件的处理方式
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum RetentionAction {
+    /// 移动到保存目录下的 trash 子目录
+    Trash,
+    /// 直接删除
+    Delete,
+}
+
+/// 截图目录保留策略配置结构体
+#[derive(Clone, Debug)]
+pub struct RetentionConfig {
+    /// 是否启用定时清理
+    pub enabled: bool,
+    /// 文件最长保留天数，0 表示不限制
+    pub max_age_days: u32,
+    /// 目录内截图总大小上限（MB），0 表示不限制
+    pub max_total_mb: u64,
+    /// 目录内截图数量上限，0 表示不限制
+    pub max_files: usize,
+    /// 不参与清理的文件通配符（相对保存目录，如 `*_keep.*`、`pinned/**`）
+    pub exclude: Vec<String>,
+    /// 清理方式
+    pub action: RetentionAction,
+    /// 定时清理间隔（小时），0 表示仅在启动时与手动触发时清理
+    pub interval_hours: u64,
+    /// 是否只列出将被清理的文件而不实际处理
+    pub dry_run: bool,
+}
+impl Default for RetentionConfig {
+    fn default() -> Self {
+        RetentionConfig {
+            enabled: false,
+            max_age_days: 0,
+            max_total_mb: 0,
+            max_files: 0,
+            exclude: vec!["*keep*".to_owned()],
+            action: RetentionAction::Trash,
+            interval_hours: 24,
+            dry_run: false,
+        }
+    }
+}
+
+/// 发现重复截图时的处理方式
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum DedupeAction {
+    /// 仅通知
+    Warn,
+    /// 删除新截图，以先前的截图作为本次输出
+    Delete,
+    /// 在历史记录中关联先前的截图，完全相同时以硬链接替换新截图
+    Link,
+}
+
+/// 重复截图检测配置结构体
+#[derive(Clone, Debug)]
+pub struct DedupeConfig {
+    /// 是否检测重复截图
+    pub enabled: bool,
+    /// 发现重复时的处理方式
+    pub action: DedupeAction,
+    /// 是否启用感知哈希检测相似截图
+    pub perceptual: bool,
+    /// 感知哈希最大汉明距离（0-64），不超过该值视为相似
+    pub threshold: u32,
+    /// 与最近多少条历史记录比较
+    pub window: usize,
+}
+impl Default for DedupeConfig {
+    fn default() -> Self {
+        DedupeConfig {
+            enabled: false,
+            action: DedupeAction::Warn,
+            perceptual: true,
+            threshold: 4,
+            window: 50,
+        }
+    }
+}
+
+/// 上传请求体格式
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum UploadBody {
+    /// multipart/form-data，文件放在 field 指定的字段中
+    Multipart,
+    /// 请求体直接为文件内容
+    Raw,
+}
+
+/// HTTP 上传配置结构体
+#[derive(Clone, Debug)]
+pub struct UploadConfig {
+    /// 是否在截图保存后上传
+    pub enabled: bool,
+    /// 上传地址
+    pub url: String,
+    /// 请求方法（POST 或 PUT）
+    pub method: String,
+    /// 请求体格式
+    pub body: UploadBody,
+    /// multipart 中文件字段名
+    pub field: String,
+    /// 附加请求头
+    pub headers: Vec<(String, String)>,
+    /// 访问令牌，`env:变量名` 表示从环境变量读取
+    pub token: String,
+    /// 携带令牌的请求头
+    pub token_header: String,
+    /// 令牌前缀
+    pub token_prefix: String,
+    /// 响应 JSON 中链接所在的字段路径，如 `data.url` 或 `files.0.url`
+    pub url_field: String,
+    /// 是否将链接复制到剪贴板
+    pub copy_url: bool,
+    /// 失败后的最大重试次数
+    pub retries: u32,
+    /// 首次重试前的等待时间，之后每次加倍
+    pub backoff: std::time::Duration,
+    /// 单次请求超时
+    pub timeout: std::time::Duration,
+}
+impl Default for UploadConfig {
+    fn default() -> Self {
+        UploadConfig {
+            enabled: false,
+            url: String::new(),
+            method: "POST".to_string(),
+            body: UploadBody::Multipart,
+            field: "file".to_string(),
+            headers: Vec::new(),
+            token: String::new(),
+            token_header: "Authorization".to_string(),
+            token_prefix: "Bearer ".to_string(),
+            url_field: "url".to_string(),
+            copy_url: true,
+            retries: 3,
+            backoff: std::time::Duration::from_secs(1),
+            timeout: std::time::Duration::from_secs(30),
+        }
+    }
+}
+
+/// 复制为文本片段的配置结构体
+#[derive(Clone, Debug)]
+pub struct CopyAsConfig {
+    /// 文档根目录（未解析），`{path}` 相对于该目录生成，为空时使用绝对路径
+    pub docs_root: String,
+    /// 快捷键与自动复制使用的格式名
+    pub default_format: String,
+    /// 是否在截图保存后自动复制
+    pub auto: bool,
+    /// 格式名与模板，按菜单显示顺序排列
+    pub templates: Vec<(String, String)>,
+}
+impl CopyAsConfig {
+    /// 按名称查找模板（不区分大小写）
+    pub fn template(&self, name: &str) -> Option<&str> {
+        self.templates
+            .iter()
+            .find(|(key, _)| key.eq_ignore_ascii_case(name))
+            .map(|(_, template)| template.as_str())
+    }
+}
+impl Default for CopyAsConfig {
+    fn default() -> Self {
+        CopyAsConfig {
+            docs_root: String::new(),
+            default_format: "markdown".to_string(),
+            auto: false,
+            templates: vec![
+                ("markdown".to_string(), "![{stem}]({path})".to_string()),
+                (
+                    "html".to_string(),
+                    "<img src=\"{path}\" alt=\"{stem}\">".to_string(),
+                ),
+                ("uri".to_string(), "{uri}".to_string()),
+                ("path".to_string(), "{abs}".to_string()),
+            ],
+        }
+    }
+}
+
+/// 可按截图模式或配置名覆盖的设置
+///
+/// ### 说明
+/// - 对应配置文件中的一个段（如 `[convert]`）及其子表（如 `[convert.long]`、`[convert.projectX]`）
+/// - 子表继承段内的基础设置，只需填写需要修改的键
+#[derive(Clone, Debug, Default)]
+pub struct Profiled<T> {
+    /// 基础设置
+    pub base: T,
+    /// 子表名称（截图模式或配置名）与覆盖后的设置
+    pub overrides: Vec<(String, T)>,
+}
+impl<T> Profiled<T> {
+    /// 选择本次截图使用的设置
+    ///
+    /// ### 参数
+    /// - `mode`: 截图模式
+    /// - `profile`: 当前配置名
+    ///
+    /// ### 返回值
+    /// - `&T`: 依次匹配截图模式、配置名，均未匹配时返回基础设置
+    pub fn select(&self, mode: CaptureMode, profile: &str) -> &T {
+        let find = |name: &str| {
+            self.overrides
+                .iter()
+                .find(|(key, _)| key.eq_ignore_ascii_case(name))
+                .map(|(_, value)| value)
+        };
+        find(mode.key())
+            .or_else(|| find(profile))
+            .unwrap_or(&self.base)
+    }
+}
+
+/// 截图转换的目标格式
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ConvertFormat {
+    /// 保持原格式（仍会缩放、去除元数据并重新压缩）
+    Keep,
+    /// PNG（最高压缩等级与自适应过滤）
+    Png,
+    /// JPEG（按 quality 有损压缩，透明部分以白色填充）
+    Jpeg,
+    /// WebP（quality 低于 100 时有损压缩，等于 100 时无损）
+    Webp,
+}
+impl ConvertFormat {
+    /// 目标扩展名，保持原格式时返回 `None`
+    pub fn ext(&self) -> Option<&'static str> {
+        match self {
+            ConvertFormat::Keep => None,
+            ConvertFormat::Png => Some("png"),
+            ConvertFormat::Jpeg => Some("jpg"),
+            ConvertFormat::Webp => Some("webp"),
+        }
+    }
+}
+
+/// 截图格式转换配置结构体
+#[derive(Clone, Debug)]
+pub struct ConvertConfig {
+    /// 是否在保存后转换截图
+    pub enabled: bool,
+    /// 目标格式
+    pub format: ConvertFormat,
+    /// JPEG / WebP 质量（1-100），WebP 为 100 时无损编码
+    pub quality: u8,
+    /// 最长边像素上限，超过时等比缩小，0 表示不限制
+    pub max_dimension: u32,
+    /// 是否去除 EXIF 与 ICC 等元数据
+    pub strip_metadata: bool,
+    /// 是否将原图保留到 originals 子目录
+    pub keep_original: bool,
+}
+impl Default for ConvertConfig {
+    fn default() -> Self {
+        ConvertConfig {
+            enabled: false,
+            format: ConvertFormat::Keep,
+            quality: 90,
+            max_dimension: 0,
+            strip_metadata: true,
+            keep_original: false,
+        }
+    }
+}
+
+/// 水印所在的角落
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum WatermarkCorner {
+    /// 左上角
+    TopLeft,
+    /// 右上角
+    TopRight,
+    /// 左下角
+    BottomLeft,
+    /// 右下角
+    BottomRight,
+}
+
+/// 水印配置结构体
+#[derive(Clone, Debug)]
+pub struct WatermarkConfig {
+    /// 是否为保存到文件的截图添加水印
+    pub enabled: bool,
+    /// 文字模板，可包含换行，为空时只绘制图标
+    pub text: String,
+    /// 字体文件路径，为空时使用系统字体
+    pub font: String,
+    /// 字号（像素）
+    pub font_size: f32,
+    /// 文字颜色（RGBA）
+    pub color: [u8; 4],
+    /// 背景颜色（RGBA），透明度为 0 时不绘制背景
+    pub background: [u8; 4],
+    /// 图标图片路径，为空时不绘制图标
+    pub logo: String,
+    /// 图标高度（像素），0 表示与文字等高（无文字时为原始大小）
+    pub logo_height: u32,
+    /// 所在角落
+    pub corner: WatermarkCorner,
+    /// 整体不透明度（0.0-1.0）
+    pub opacity: f32,
+    /// 与图片边缘的距离（像素）
+    pub padding: u32,
+}
+impl Default for WatermarkConfig {
+    fn default() -> Self {
+        WatermarkConfig {
+            enabled: false,
+            text: "{date} {hostname}".to_string(),
+            font: String::new(),
+            font_size: 18.0,
+            color: [255, 255, 255, 255],
+            background: [0, 0, 0, 160],
+            logo: String::new(),
+            logo_height: 0,
+            corner: WatermarkCorner::BottomRight,
+            opacity: 0.85,
+            padding: 12,
+        }
+    }
+}
+
+/// 截图元数据配置结构体
+#[derive(Clone, Debug)]
+pub struct MetadataConfig {
+    /// 是否为保存的截图写入元数据
+    pub enabled: bool,
+    /// 是否写入 PNG 文本块（tEXt / iTXt，仅 PNG 文件）
+    pub png_chunks: bool,
+    /// 是否写入同名 .json 附属文件（如 `shot.png.json`）
+    pub sidecar: bool,
+}
+impl Default for MetadataConfig {
+    fn default() -> Self {
+        MetadataConfig {
+            enabled: false,
+            png_chunks: true,
+            sidecar: false,
+        }
+    }
+}
+
+/// 截图引擎类型
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum EngineKind {
+    /// 内置或 `engine_path` 指定的 ScreenCapture
+    ScreenCapture,
+    /// 按 `[capture.command]` 模板调用的其它截图工具
+    Command,
+}
+
+/// 命令模板截图引擎配置结构体
+#[derive(Clone, Debug)]
+pub struct CommandEngineConfig {
+    /// 各截图模式的命令（首项为程序，其余为参数），未设置的模式视为不支持
+    pub commands: Vec<(CaptureMode, Vec<String>)>,
+    /// 视为取消的退出码
+    pub cancel_codes: Vec<i32>,
+    /// 退出码为 0 但没有生成文件时的结果（取消或已复制到剪贴板）
+    pub no_output: CaptureOutcome,
+    /// 输出文件扩展名
+    pub format: String,
+}
+impl CommandEngineConfig {
+    /// 获取指定截图模式的命令
+    pub fn command(&self, mode: CaptureMode) -> Option<&[String]> {
+        self.commands
+            .iter()
+            .find(|(m, cmd)| *m == mode && !cmd.is_empty())
+            .map(|(_, cmd)| cmd.as_slice())
+    }
+}
+impl Default for CommandEngineConfig {
+    fn default() -> Self {
+        CommandEngineConfig {
+            commands: Vec::new(),
+            cancel_codes: Vec::new(),
+            no_output: CaptureOutcome::Cancelled,
+            format: "png".to_string(),
+        }
+    }
+}
+
+/// 截图引擎配置结构体
+#[derive(Clone, Debug)]
+pub struct CaptureConfig {
+    /// 截图引擎类型
+    pub engine: EngineKind,
+    /// 命令模板引擎配置
+    pub command: CommandEngineConfig,
+}
+impl Default for CaptureConfig {
+    fn default() -> Self {
+        CaptureConfig {
+            engine: EngineKind::ScreenCapture,
+            command: CommandEngineConfig::default(),
+        }
+    }
+}
+
+/// ScreenCapture 替换策略
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ResourcePolicy {
+    /// 始终使用嵌入的版本，与之不同即替换
+    Embedded,
+    /// 数据目录中的版本更新时保留，否则替换为嵌入的版本
+    PreferNewer,
+    /// 保留数据目录中已有的 exe，仅在缺失时释放
+    PinnedExternal,
+}
+impl ResourcePolicy {
+    /// 配置文件中使用的名称
+    pub fn key(&self) -> &'static str {
+        match self {
+            ResourcePolicy::Embedded => "embedded",
+            ResourcePolicy::PreferNewer => "prefer-newer",
+            ResourcePolicy::PinnedExternal => "pinned-external",
+        }
+    }
+}
+
+/// 配置文件缺失或损坏时的恢复方式
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ConfigRecovery {
+    /// 直接恢复最近一次可用的备份并通知
+    Restore,
+    /// 询问是否恢复备份，否则释放默认配置
+    Ask,
+    /// 询问是否退出程序，否则恢复备份
+    Exit,
+}
+impl ConfigRecovery {
+    /// 配置文件中使用的名称
+    pub fn key(&self) -> &'static str {
+        match self {
+            ConfigRecovery::Restore => "restore",
+            ConfigRecovery::Ask => "ask",
+            ConfigRecovery::Exit => "exit",
+        }
+    }
+}
+
+/// 资源文件配置结构体
+#[derive(Clone, Debug)]
+pub struct ResourceConfig {
+    /// ScreenCapture 替换策略
+    pub policy: ResourcePolicy,
+    /// 替换前是否将原 exe 备份到 backup 子目录
+    pub backup: bool,
+    /// 保留的配置文件备份数量，0 表示不再备份
+    pub config_backups: usize,
+    /// 配置文件缺失或损坏时的恢复方式
+    pub config_recovery: ConfigRecovery,
+}
+impl Default for ResourceConfig {
+    fn default() -> Self {
+        ResourceConfig {
+            policy: ResourcePolicy::PreferNewer,
+            backup: true,
+            config_backups: 5,
+            config_recovery: ConfigRecovery::Restore,
         }
     }
 }
@@ -80,16 +818,56 @@ impl Default for LaunchAppConfig {
 /// 存储截图保存路径和启动应用程序配置
 #[derive(Clone, Debug)]
 pub struct PathConfig {
-    /// 截图文件保存路径
+    /// 截图文件保存路径（启动时解析，截图时会重新检查）
     pub save_path: PathBuf,
+    /// 配置中的保存路径字符串（未解析）
+    pub save_path_str: String,
+    /// 保存路径不可用时依次尝试的备用目录（未解析）
+    pub fallback_dirs: Vec<String>,
+    /// 保存目录不存在时是否自动创建
+    pub create_dir: bool,
+    /// 截图保存后同步复制到的镜像目录（未解析）
+    pub mirrors: Vec<String>,
     /// 外部启动应用程序配置
     pub launch_app: LaunchAppConfig,
+    /// 截图文件名模板，为空时保留 ScreenCapture 生成的文件名
+    pub filename_template: String,
+    /// 按日期划分的子目录模板，为空时直接保存到 save_path
+    pub subfolder: String,
+    /// 子目录创建失败时的处理方式
+    pub subfolder_fallback: SubfolderFallback,
 }
 impl Default for PathConfig {
     fn default() -> Self {
         PathConfig {
             save_path: PathBuf::new(),
+            save_path_str: "&".to_string(),
+            fallback_dirs: Vec::new(),
+            create_dir: false,
+            mirrors: Vec::new(),
             launch_app: LaunchAppConfig::default(),
+            filename_template: String::new(),
+            subfolder: String::new(),
+            subfolder_fallback: SubfolderFallback::Base,
+        }
+    }
+}
+
+/// 子目录创建失败时的处理方式
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum SubfolderFallback {
+    /// 保存到 save_path 根目录
+    Base,
+    /// 改为截图时手动选择
+    Manual,
+}
+impl SubfolderFallback {
+    /// 从配置字符串解析，无法识别时返回 `None`
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name.trim().to_ascii_lowercase().as_str() {
+            "base" => Some(SubfolderFallback::Base),
+            "manual" => Some(SubfolderFallback::Manual),
+            _ => None,
         }
     }
 }
@@ -113,6 +891,30 @@ pub struct SettingsCollection {
     pub sundry: Sundry,
     /// GUI工具栏配置参数，包含normal和long两种模式
     pub gui: HashMap<String, String>,
+    /// 截图结果通知模板
+    pub notify: NotifyTemplates,
+    /// 截图完成后执行的钩子列表
+    pub hooks: Vec<HookConfig>,
+    /// 截图历史记录配置
+    pub history: HistoryConfig,
+    /// 截图目录保留与清理策略
+    pub retention: RetentionConfig,
+    /// 重复截图检测配置
+    pub dedupe: DedupeConfig,
+    /// HTTP 上传配置
+    pub upload: UploadConfig,
+    /// 复制为文本片段配置
+    pub copy_as: CopyAsConfig,
+    /// 截图格式转换配置
+    pub convert: Profiled<ConvertConfig>,
+    /// 水印配置
+    pub watermark: Profiled<WatermarkConfig>,
+    /// 截图元数据配置
+    pub metadata: MetadataConfig,
+    /// 资源文件配置
+    pub resource: ResourceConfig,
+    /// 截图引擎配置
+    pub capture: CaptureConfig,
 }
 impl Default for SettingsCollection {
     fn default() -> Self {
@@ -173,6 +975,15 @@ impl Default for SettingsCollection {
             },
         );
 
+        // 添加复制最近截图快捷键
+        default_kvs.insert(
+            "copy_as",
+            HotkeyValue {
+                mod_keys: vec![ModKey::Win, ModKey::Alt, ModKey::Ctrl],
+                vkey: VKey::C,
+            },
+        );
+
         let mut default_gui: HashMap<String, String> = HashMap::new();
         default_gui.insert(
         "normal".to_owned(),
@@ -185,6 +996,18 @@ impl Default for SettingsCollection {
             path: PathConfig::default(),
             sundry: Sundry::default(),
             gui: default_gui,
+            notify: NotifyTemplates::default(),
+            hooks: Vec::new(),
+            history: HistoryConfig::default(),
+            retention: RetentionConfig::default(),
+            dedupe: DedupeConfig::default(),
+            upload: UploadConfig::default(),
+            copy_as: CopyAsConfig::default(),
+            convert: Profiled::default(),
+            watermark: Profiled::default(),
+            metadata: MetadataConfig::default(),
+            resource: ResourceConfig::default(),
+            capture: CaptureConfig::default(),
         }
     }
 }
@@ -213,11 +1036,13 @@ impl SettingsCollection {
     Screenshot:       {}
     Long Screenshot:  {}
     Pin Image:        {}
+    Copy As:          {}
     Exit:             {}
     Config:           {}{}"#,
             self.key_str("screen_capture"),
             self.key_str("screen_capture_long"),
             self.key_str("pin_to_screen"),
+            self.key_str("copy_as"),
             self.key_str("exit"),
             self.key_str("open_conf"),
             launch_line,
@@ -252,18 +1077,127 @@ impl SettingsCollection {
         };
         format!(
             r#"Sundry:
-    Save Path:         {}{}
+    Save Path:         {}
+    Subfolder:         {}
+    File Name:         {}{}
+    Profile:           {}
     Auto Startup:      {}
     Show Notification: {}
+    History:           {}
+    Retention:         {}
+    Dedupe:            {}
+    Upload:            {}
+    Convert:           {}
+    Watermark:         {}
+    Metadata:          {}
+    Resource:          {}{}
+    Config Backups:    {} (on loss: {})
+    Engine:            {}
     Comp Level:        {}
     Scale Level:       {}
     GUI:
         Normal: {}
         Long:   {}"#,
             path_display(&self.path.save_path, "Manual Select"),
+            if self.path.subfolder.is_empty() {
+                "None".to_string()
+            } else {
+                format!(
+                    "{} (fallback: {:?})",
+                    self.path.subfolder, self.path.subfolder_fallback
+                )
+            },
+            if self.path.filename_template.is_empty() {
+                "<ScreenCapture>"
+            } else {
+                self.path.filename_template.as_str()
+            },
             launch_str,
+            self.sundry.profile,
             self.sundry.auto_start,
             self.sundry.notification,
+            if self.history.enabled {
+                format!(
+                    "max {} entries, {} days",
+                    self.history.max_entries, self.history.retention_days
+                )
+            } else {
+                "Disabled".to_string()
+            },
+            if self.retention.enabled {
+                format!(
+                    "{:?} after {} days / {} MB / {} files{}",
+                    self.retention.action,
+                    self.retention.max_age_days,
+                    self.retention.max_total_mb,
+                    self.retention.max_files,
+                    if self.retention.dry_run {
+                        " (dry run)"
+                    } else {
+                        ""
+                    }
+                )
+            } else {
+                "Disabled".to_string()
+            },
+            if self.dedupe.enabled {
+                format!(
+                    "{:?}, {}",
+                    self.dedupe.action,
+                    if self.dedupe.perceptual {
+                        format!("distance <= {}", self.dedupe.threshold)
+                    } else {
+                        "exact only".to_string()
+                    }
+                )
+            } else {
+                "Disabled".to_string()
+            },
+            if self.upload.enabled {
+                format!("{} {}", self.upload.method, self.upload.url)
+            } else {
+                "Disabled".to_string()
+            },
+            if self.convert.base.enabled {
+                format!(
+                    "{:?}, max {} px{}",
+                    self.convert.base.format,
+                    self.convert.base.max_dimension,
+                    if self.convert.overrides.is_empty() {
+                        String::new()
+                    } else {
+                        format!(" ({} overrides)", self.convert.overrides.len())
+                    }
+                )
+            } else if self.convert.overrides.is_empty() {
+                "Disabled".to_string()
+            } else {
+                format!("{} overrides", self.convert.overrides.len())
+            },
+            match (self.watermark.base.enabled, self.watermark.overrides.len(),) {
+                (true, 0) => format!("{:?}", self.watermark.base.corner),
+                (true, n) => format!("{:?} ({} overrides)", self.watermark.base.corner, n),
+                (false, 0) => "Disabled".to_string(),
+                (false, n) => format!("{} overrides", n),
+            },
+            match (
+                self.metadata.enabled,
+                self.metadata.png_chunks,
+                self.metadata.sidecar,
+            ) {
+                (false, _, _) | (true, false, false) => "Disabled",
+                (true, true, false) => "PNG chunks",
+                (true, false, true) => "JSON sidecar",
+                (true, true, true) => "PNG chunks + JSON sidecar",
+            },
+            self.resource.policy.key(),
+            if self.resource.backup { ", backup" } else { "" },
+            self.resource.config_backups,
+            self.resource.config_recovery.key(),
+            match self.capture.engine {
+                EngineKind::ScreenCapture => "ScreenCapture",
+                EngineKind::Command => "Command template",
+            },
             self.sundry.comp_level,
             self.sundry.scale_level,
             self.gui
@@ -300,19 +1234,116 @@ pub struct PathInfos {
     pub conf_path: PathBuf,
     /// 示例配置文件（config_example.toml）路径
     pub conf_example_path: PathBuf,
+    /// 外部 ScreenCapture 路径（`[capture] engine_path`），设置后不释放也不保护内置的 exe
+    pub engine_path: Option<PathBuf>,
+}
+impl PathInfos {
+    /// 实际使用的 ScreenCapture 路径
+    pub fn engine(&self) -> &Path {
+        self.engine_path.as_deref().unwrap_or(&self.exe_path)
+    }
 }
 impl std::fmt::Display for PathInfos {
     fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
         write!(
             f,
-            "\n************\nRoot Dir:\t{}\nProcess Path:\t{}\nConf Path:\t{}\n************\n",
+            "\n************\nRoot Dir:\t{}\nProcess Path:\t{}{}\nConf Path:\t{}\n************\n",
             self.dir_path.display(),
-            self.exe_path.display(),
+            self.engine().display(),
+            if self.engine_path.is_some() {
+                " (external)"
+            } else {
+                ""
+            },
             self.conf_path.display()
         )
     }
 }
 
+/// 截图引擎支持的命令行参数
+#[derive(Clone, Debug)]
+pub struct EngineCapabilities {
+    /// 文件版本
+    pub version: Option<String>,
+    /// 长截图 `--cap:long`
+    pub long: bool,
+    /// 全屏截图 `--cap:fullscreen`
+    pub fullscreen: bool,
+    /// 钉图 `--pin:clipboard`
+    pub pin: bool,
+    /// 压缩与缩放 `--comp:`
+    pub comp: bool,
+    /// 保存路径 `--path:`
+    pub save_path: bool,
+    /// 工具栏 `--tool:`
+    pub tool: bool,
+    /// 界面语言 `--lang:`
+    pub lang: bool,
+}
+impl EngineCapabilities {
+    /// 是否支持指定的截图模式（区域截图总是支持）
+    pub fn supports(&self, mode: CaptureMode) -> bool {
+        match mode {
+            CaptureMode::Region => true,
+            CaptureMode::Long => self.long,
+            CaptureMode::Fullscreen => self.fullscreen,
+            CaptureMode::Pin => self.pin,
+        }
+    }
+}
+impl Default for EngineCapabilities {
+    fn default() -> Self {
+        EngineCapabilities {
+            version: None,
+            long: true,
+            fullscreen: true,
+            pin: true,
+            comp: true,
+            save_path: true,
+            tool: true,
+            lang: true,
+        }
+    }
+}
+impl std::fmt::Display for EngineCapabilities {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let flags = [
+            ("long", self.long),
+            ("fullscreen", self.fullscreen),
+            ("pin", self.pin),
+            ("comp", self.comp),
+            ("path", self.save_path),
+            ("tool", self.tool),
+            ("lang", self.lang),
+        ];
+        let list = |supported: bool| {
+            let names: Vec<&str> = flags
+                .iter()
+                .filter(|(_, s)| *s == supported)
+                .map(|(name, _)| *name)
+                .collect();
+            if names.is_empty() {
+                "none".to_string()
+            } else {
+                names.join(", ")
+            }
+        };
+        write!(f, "Supported: {}; Unsupported: {}", list(true), list(false))
+    }
+}
+
+/// 截图任务上下文
+/// 在托盘与快捷键线程间共享，包含执行截图所需的路径与配置
+#[derive(Clone, Debug)]
+pub struct CaptureContext {
+    /// 程序路径信息
+    pub paths: PathInfos,
+    /// 完整配置集合
+    pub settings: Arc<SettingsCollection>,
+    /// 截图引擎
+    pub engine: Arc<dyn CaptureEngine>,
+}
+
 /// 字符串格式的按键组合结构体
 /// 用于从配置文件中读取和解析热键设置
 #[derive(Clone)]
@@ -456,6 +1487,71 @@ pub fn handle_str_path(str_path: impl ToString) -> String {
         .to_string()
 }
 
+/// 展开模板中的占位符
+///
+/// ### 参数
+/// - `template`: 模板字符串，占位符形如 `{name}` 或 `{name:spec}`
+/// - `resolve`: 根据占位符名称与格式说明返回替换值，返回 `None` 时保留原样
+///
+/// ### 返回值
+/// - `String`: 展开后的字符串
+///
+/// ### 功能
+/// - 依次扫描 `{...}` 并交由 `resolve` 处理
+/// - 以第一个 `:` 分隔名称与格式说明，如 `{date:%Y-%m-%d}`
+/// - 无法识别或未闭合的占位符原样保留
+pub fn expand_placeholders(
+    template: &str,
+    mut resolve: impl FnMut(&str, Option<&str>) -> Option<String>,
+) -> String {
+    let mut output = String::with_capacity(template.len());
+    let mut rest = template;
+    while let Some(start) = rest.find('{') {
+        output.push_str(&rest[..start]);
+        let after = &rest[start + 1..];
+        let Some(end) = after.find('}') else {
+            output.push_str(&rest[start..]);
+            rest = "";
+            break;
+        };
+        let inner = &after[..end];
+        let (name, spec) = match inner.split_once(':') {
+            Some((name, spec)) => (name.trim(), Some(spec)),
+            None => (inner.trim(), None),
+        };
+        match resolve(name, spec) {
+            Some(value) => output.push_str(&value),
+            None => {
+                output.push('{');
+                output.push_str(inner);
+                output.push('}');
+            }
+        }
+        rest = &after[end + 1..];
+    }
+    output.push_str(rest);
+    output
+}
+
+/// 将任意字符串转换为合法的 Windows 文件名
+///
+/// ### 参数
+/// - `name`: 原始字符串
+///
+/// ### 返回值
+/// - `String`: 替换非法字符并去除首尾空格和点号后的文件名
+pub fn sanitize_file_name(name: &str) -> String {
+    let cleaned: String = name
+        .chars()
+        .map(|c| match c {
+            '<' | '>' | ':' | '"' | '/' | '\\' | '|' | '?' | '*' => '_',
+            c if c.is_control() => '_',
+            c => c,
+        })
+        .collect();
+    cleaned.trim().trim_matches('.').to_string()
+}
+
 /// 解析路径字符串为PathBuf，支持特殊符号
 ///
 /// ### 特殊路径符号
@@ -512,64 +1608,7 @@ pub fn resolve_path(path: impl ToString, should_dir: bool) -> PathBuf {
             }
         }
         x => {
-            let mut path = PathBuf::from(x.replace("/", "\\"));
-            let base_dir = env::current_dir().unwrap_or_default();
-
-            let is_absolute = {
-                let mut components = path.components();
-                if let Some(Component::Prefix(prefix_component)) = components.next() {
-                    let has_root_dir = matches!(components.next(), Some(Component::RootDir));
-                    if DEBUG {
-                        dbg!(has_root_dir);
-                    }
-                    if !has_root_dir {
-                        false
-                    } else {
-                        if DEBUG {
-                            dbg!(prefix_component.kind());
-                        }
-
-                        matches!(
-                            prefix_component.kind(),
-                            Prefix::VerbatimUNC(..)
-                                | Prefix::UNC(..)
-                                | Prefix::VerbatimDisk(..)
-                                | Prefix::Disk(_)
-                                | Prefix::DeviceNS(..)
-                                | Prefix::Verbatim(_)
-                        )
-                    }
-                } else {
-                    path.is_absolute()
-                }
-            };
-
-            if !is_absolute {
-                if path.starts_with("~") {
-                    if let Ok(home_dir) = std::env::var("USERPROFILE") {
-                        let mut new_path = PathBuf::from(home_dir);
-                        let remaining = path.strip_prefix("~/").ok();
-                        if let Some(rem) = remaining {
-                            new_path.push(rem);
-                            path = new_path;
-                        } else if *path == *"~" {
-                            path = new_path;
-                        } else {
-                            // "~something"
-                            path = base_dir.join(path);
-                        }
-                    }
-                } else if path.starts_with(".") {
-                    if let Ok(remaining) = path.strip_prefix(".\\") {
-                        path = base_dir.join(remaining);
-                    } else {
-                        path = base_dir.join(&path);
-                    }
-                } else {
-                    path = base_dir.join(path);
-                }
-            }
-
+            let path = absolute_path(x);
             let canonical = path.canonicalize().unwrap_or_default();
             if canonical.exists() {
                 canonical
@@ -587,6 +1626,215 @@ pub fn resolve_path(path: impl ToString, should_dir: bool) -> PathBuf {
     }
 }
 
+/// 展开路径字符串（含特殊符号），不弹出提示也不检查路径是否存在
+///
+/// ### 参数
+/// - `path`: 规范化后的路径字符串（参见 `resolve_path` 的特殊符号）
+///
+/// ### 返回值
+/// - `Option<PathBuf>`: `&` 返回空路径（手动选择），无法解析桌面或图片目录时返回 `None`
+pub fn expand_path(path: &str) -> Option<PathBuf> {
+    match path {
+        "&" => Some(PathBuf::new()),
+        "@" => directories::UserDirs::new().and_then(|u| u.desktop_dir().map(|d| d.to_path_buf())),
+        "*" => directories::UserDirs::new().and_then(|u| u.picture_dir().map(|d| d.to_path_buf())),
+        x => Some(absolute_path(x)),
+    }
+}
+
+/// 检查目录是否可写
+///
+/// ### 参数
+/// - `dir`: 要检查的目录
+///
+/// ### 返回值
+/// - `io::Result<()>`: 能在目录中创建并删除临时文件时返回 `Ok`
+///
+/// ### 说明
+/// - 只读介质、权限不足或已断开的网络共享都会返回错误，而不仅是判断目录是否存在
+pub fn check_writable(dir: &Path) -> std::io::Result<()> {
+    // 同一进程中可能同时有多次截图在检查同一目录，探测文件名需各不相同
+    static PROBE_COUNTER: std::sync::atomic::AtomicU64 = std::sync::atomic::AtomicU64::new(0);
+    let probe = dir.join(format!(
+        ".sc_starter_{}_{}.tmp",
+        std::process::id(),
+        PROBE_COUNTER.fetch_add(1, std::sync::atomic::Ordering::Relaxed)
+    ));
+    fs::OpenOptions::new()
+        .write(true)
+        .create(true)
+        .truncate(true)
+        .open(&probe)?;
+    fs::remove_file(&probe)
+}
+
+/// 按顺序选择第一个可用的截图保存目录
+///
+/// ### 参数
+/// - `config`: 路径配置
+///
+/// ### 返回值
+/// - `Result<PathBuf, String>`: 可用的目录（为空表示手动选择），全部不可用时返回各目录的失败原因
+///
+/// ### 功能
+/// - 依次检查 dir 与 fallback_dirs 中的目录
+/// - 启用 create_dir 时自动创建不存在的目录
+/// - 目录必须存在且可写才会被选中
+/// - 遇到 `&` 时直接改为手动选择
+pub fn select_save_dir(config: &PathConfig) -> Result<PathBuf, String> {
+    let mut errors: Vec<String> = Vec::new();
+    for raw in std::iter::once(&config.save_path_str).chain(config.fallback_dirs.iter()) {
+        let Some(dir) = expand_path(raw) else {
+            errors.push(format!("{}: cannot resolve system directory", raw));
+            continue;
+        };
+        if dir.as_os_str().is_empty() {
+            return Ok(dir);
+        }
+        if !dir.exists()
+            && config.create_dir
+            && let Err(e) = fs::create_dir_all(&dir)
+        {
+            errors.push(format!("{}: {}", dir.display(), e));
+            continue;
+        }
+        if !dir.is_dir() {
+            errors.push(format!("{}: directory not found", dir.display()));
+            continue;
+        }
+        match check_writable(&dir) {
+            Ok(_) => return Ok(dir.canonicalize().unwrap_or(dir)),
+            Err(e) => errors.push(format!("{}: not writable ({})", dir.display(), e)),
+        }
+    }
+    Err(errors.join("\n"))
+}
+
+/// 按顺序查找第一个已存在的截图保存目录，不做任何修改
+///
+/// ### 参数
+/// - `config`: 路径配置
+///
+/// ### 返回值
+/// - `Result<PathBuf, String>`: 已存在的目录（为空表示手动选择），全部不存在时返回各目录的失败原因
+///
+/// ### 说明
+/// - 与 `select_save_dir` 顺序相同，但不创建目录、不写入探测文件，供只读的命令行子命令使用
+pub fn find_save_dir(config: &PathConfig) -> Result<PathBuf, String> {
+    let mut errors: Vec<String> = Vec::new();
+    for raw in std::iter::once(&config.save_path_str).chain(config.fallback_dirs.iter()) {
+        let Some(dir) = expand_path(raw) else {
+            errors.push(format!("{}: cannot resolve system directory", raw));
+            continue;
+        };
+        if dir.as_os_str().is_empty() {
+            return Ok(dir);
+        }
+        if dir.is_dir() {
+            return Ok(dir.canonicalize().unwrap_or(dir));
+        }
+        errors.push(format!("{}: directory not found", dir.display()));
+    }
+    Err(errors.join("\n"))
+}
+
+/// 将自定义路径字符串转换为绝对路径
+///
+/// ### 说明
+/// - 支持 `~`（用户目录）与 `.`（当前目录）开头的相对路径
+/// - 不检查路径是否存在
+fn absolute_path(x: &str) -> PathBuf {
+    let mut path = PathBuf::from(x.replace("/", "\\"));
+    let base_dir = env::current_dir().unwrap_or_default();
+
+    let is_absolute = {
+        let mut components = path.components();
+        if let Some(Component::Prefix(prefix_component)) = components.next() {
+            let has_root_dir = matches!(components.next(), Some(Component::RootDir));
+            if DEBUG {
+                dbg!(has_root_dir);
+            }
+            if !has_root_dir {
+                false
+            } else {
+                if DEBUG {
+                    dbg!(prefix_component.kind());
+                }
+
+                matches!(
+                    prefix_component.kind(),
+                    Prefix::VerbatimUNC(..)
+                        | Prefix::UNC(..)
+                        | Prefix::VerbatimDisk(..)
+                        | Prefix::Disk(_)
+                        | Prefix::DeviceNS(..)
+                        | Prefix::Verbatim(_)
+                )
+            }
+        } else {
+            path.is_absolute()
+        }
+    };
+
+    if !is_absolute {
+        if path.starts_with("~") {
+            if let Ok(home_dir) = std::env::var("USERPROFILE") {
+                let mut new_path = PathBuf::from(home_dir);
+                let remaining = path.strip_prefix("~/").ok();
+                if let Some(rem) = remaining {
+                    new_path.push(rem);
+                    path = new_path;
+                } else if *path == *"~" {
+                    path = new_path;
+                } else {
+                    // "~something"
+                    path = base_dir.join(path);
+                }
+            }
+        } else if path.starts_with(".") {
+            if let Ok(remaining) = path.strip_prefix(".\\") {
+                path = base_dir.join(remaining);
+            } else {
+                path = base_dir.join(&path);
+            }
+        } else {
+            path = base_dir.join(path);
+        }
+    }
+    path
+}
+
+/// 去除 canonicalize 产生的 `\\?\` 前缀，得到外部程序可用的普通路径
+///
+/// ### 参数
+/// - `path`: 路径
+///
+/// ### 返回值
+/// - `PathBuf`: 普通形式的路径
+///
+/// ### 说明
+/// - `\\?\UNC\server\share` 还原为 `\\server\share`
+/// - `\\?\C:\...` 还原为 `C:\...`
+/// - 其它 `\\?\` 路径（如卷 GUID）没有普通形式，保持不变
+pub fn strip_verbatim(path: &std::path::Path) -> PathBuf {
+    let Some(text) = path.to_str() else {
+        return path.to_path_buf();
+    };
+    if let Some(rest) = text.strip_prefix(r"\\?\UNC\") {
+        return PathBuf::from(format!(r"\\{}", rest));
+    }
+    match text.strip_prefix(r"\\?\") {
+        Some(rest)
+            if rest.len() >= 2
+                && rest.as_bytes()[0].is_ascii_alphabetic()
+                && rest.as_bytes()[1] == b':' =>
+        {
+            PathBuf::from(rest)
+        }
+        _ => path.to_path_buf(),
+    }
+}
+
 /// 格式化路径显示字符串
 ///
 /// ### 参数
@@ -598,14 +1846,29 @@ pub fn resolve_path(path: impl ToString, should_dir: bool) -> PathBuf {
 ///
 /// ### 功能
 /// - 当路径为空时返回指定的替代信息
-/// - 否则返回路径的字符串表示
-fn path_display(path: &PathBuf, info: impl ToString) -> String {
-    if path == &PathBuf::new() {
+/// - 否则返回路径的字符串表示（去除 `\\?\` 前缀，见 `strip_verbatim`）
+pub fn path_display(path: &std::path::Path, info: impl ToString) -> String {
+    if path.as_os_str().is_empty() {
         info.to_string()
     } else {
-        path.display()
-            .to_string()
-            .trim_start_matches(r"\\?\")
-            .into()
+        strip_verbatim(path).display().to_string()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn strip_verbatim_prefixes() {
+        let strip = |path: &str| strip_verbatim(Path::new(path)).display().to_string();
+        assert_eq!(strip(r"\\?\C:\Shots"), r"C:\Shots");
+        assert_eq!(
+            strip(r"\\?\UNC\server\share\Shots"),
+            r"\\server\share\Shots"
+        );
+        assert_eq!(strip(r"\\?\Volume{1234}\Shots"), r"\\?\Volume{1234}\Shots");
+        assert_eq!(strip(r"\\server\share"), r"\\server\share");
+        assert_eq!(strip(r"D:\Shots"), r"D:\Shots");
     }
 }
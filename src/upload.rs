@@ -0,0 +1,508 @@
+//! HTTP 上传模块
+//!
+//! 本模块负责：
+//! - 以 multipart 或原始请求体上传截图
+//! - 附加自定义请求头与访问令牌
+//! - 按字段路径从响应 JSON 中提取链接并复制到剪贴板
+//! - 失败时按指数退避重试
+
+use crate::types::*;
+use serde_json::Value;
+use std::{
+    fs,
+    path::Path,
+    thread,
+    time::{Duration, SystemTime, UNIX_EPOCH},
+};
+use ureq::tls::{RootCerts, TlsConfig, TlsProvider};
+use win_msgbox_timeout::notify_msgbox_standalone;
+
+/// 上传结果
+#[derive(Clone, Debug, Default)]
+pub struct UploadResult {
+    /// 服务器返回的链接
+    pub url: Option<String>,
+    /// 最终失败原因
+    pub error: Option<String>,
+    /// 实际请求次数
+    pub attempts: u32,
+}
+
+/// 单次请求失败原因
+struct AttemptError {
+    message: String,
+    /// 是否值得重试（网络错误、5xx、429）
+    retryable: bool,
+}
+
+/// 上传截图并按配置复制链接、显示通知
+///
+/// ### 参数
+/// - `ctx`: 截图任务上下文
+/// - `file`: 已保存的截图
+///
+/// ### 返回值
+/// - `Option<UploadResult>`: 未启用上传时返回 `None`
+pub fn upload_capture(ctx: &CaptureContext, file: &Path) -> Option<UploadResult> {
+    let config = &ctx.settings.upload;
+    if !config.enabled {
+        return None;
+    }
+
+    let result = upload_file(config, file);
+    let lang = ctx.settings.sundry.lang;
+    match (&result.url, &result.error) {
+        (Some(url), _) => {
+            println!("Uploaded: {}", url);
+            if config.copy_url {
+                if let Err(e) = clipboard_win::set_clipboard_string(url) {
+                    eprintln!("Failed to copy to clipboard: {}", e);
+                }
+                let title = if lang {
+                    "SC_Starter 链接已复制"
+                } else {
+                    "SC_Starter Link Copied"
+                };
+                notify_msgbox_standalone(title, url.clone(), 3000);
+            }
+        }
+        (None, error) => {
+            let error = error.clone().unwrap_or_default();
+            eprintln!("Upload failed: {}", error);
+            let title = if lang {
+                "SC_Starter 上传失败"
+            } else {
+                "SC_Starter Upload Failed"
+            };
+            notify_msgbox_standalone(title, error, 5000);
+        }
+    }
+    Some(result)
+}
+
+/// 上传文件，失败时按指数退避重试
+///
+/// ### 参数
+/// - `config`: 上传配置
+/// - `file`: 要上传的文件
+///
+/// ### 返回值
+/// - `UploadResult`: 链接或最终失败原因
+///
+/// ### 说明
+/// - 网络错误、5xx 与 429 会重试，其余 4xx 与无法解析的响应直接失败
+/// - 可将 url 指向本地的测试服务器（如 `http://127.0.0.1:8080/upload`）验证配置
+pub fn upload_file(config: &UploadConfig, file: &Path) -> UploadResult {
+    let mut result = UploadResult::default();
+    let content = match fs::read(file) {
+        Ok(content) => content,
+        Err(e) => {
+            result.error = Some(format!("{}: {}", file.display(), e));
+            return result;
+        }
+    };
+    let file_name = file
+        .file_name()
+        .map(|n| n.to_string_lossy().to_string())
+        .unwrap_or_else(|| "capture.png".to_string());
+
+    let agent: ureq::Agent = ureq::Agent::config_builder()
+        .timeout_global(Some(config.timeout))
+        .http_status_as_error(false)
+        .tls_config(
+            TlsConfig::builder()
+                .provider(TlsProvider::NativeTls)
+                .root_certs(RootCerts::PlatformVerifier)
+                .build(),
+        )
+        .build()
+        .into();
+
+    let mut delay = config.backoff;
+    loop {
+        result.attempts += 1;
+        match send_once(&agent, config, &file_name, &content) {
+            Ok(url) => {
+                result.url = Some(url);
+                result.error = None;
+                return result;
+            }
+            Err(e) => {
+                eprintln!("Upload attempt {} failed: {}", result.attempts, e.message);
+                result.error = Some(e.message);
+                if !e.retryable || result.attempts > config.retries {
+                    return result;
+                }
+            }
+        }
+        thread::sleep(delay);
+        delay = delay.saturating_mul(2).min(Duration::from_secs(60));
+    }
+}
+
+/// 发送一次上传请求
+///
+/// ### 返回值
+/// - `Result<String, AttemptError>`: 从响应中提取的链接
+fn send_once(
+    agent: &ureq::Agent,
+    config: &UploadConfig,
+    file_name: &str,
+    content: &[u8],
+) -> Result<String, AttemptError> {
+    let mut request = if config.method == "PUT" {
+        agent.put(&config.url)
+    } else {
+        agent.post(&config.url)
+    };
+    for (name, value) in &config.headers {
+        request = request.header(name.as_str(), value.as_str());
+    }
+    let token = resolve_token(&config.token);
+    if !token.is_empty() {
+        request = request.header(
+            config.token_header.as_str(),
+            format!("{}{}", config.token_prefix, token),
+        );
+    }
+
+    let response = match config.body {
+        UploadBody::Raw => request
+            .header("Content-Type", mime_type(file_name))
+            .send(content),
+        UploadBody::Multipart => {
+            let boundary = format!(
+                "----SC_Starter{:x}",
+                SystemTime::now()
+                    .duration_since(UNIX_EPOCH)
+                    .map(|d| d.as_nanos())
+                    .unwrap_or_default()
+            );
+            request
+                .header(
+                    "Content-Type",
+                    format!("multipart/form-data; boundary={}", boundary),
+                )
+                .send(&multipart_body(&boundary, &config.field, file_name, content)[..])
+        }
+    };
+    let mut response = response.map_err(|e| AttemptError {
+        message: e.to_string(),
+        retryable: is_retryable(&e),
+    })?;
+
+    let status = response.status().as_u16();
+    let text = response
+        .body_mut()
+        .read_to_string()
+        .map_err(|e| AttemptError {
+            message: format!("HTTP {}: {}", status, e),
+            retryable: is_retryable(&e),
+        })?;
+    if !(200..300).contains(&status) {
+        return Err(AttemptError {
+            message: format!(
+                "HTTP {}: {}",
+                status,
+                text.chars().take(200).collect::<String>()
+            ),
+            retryable: status >= 500 || status == 429,
+        });
+    }
+
+    let invalid = |reason: &str| AttemptError {
+        message: format!(
+            "{} ({})",
+            reason,
+            text.chars().take(200).collect::<String>()
+        ),
+        retryable: false,
+    };
+    // 未设置字段路径时，响应体本身就是链接
+    if config.url_field.is_empty() {
+        let url = text.trim();
+        return if url.is_empty() {
+            Err(invalid("Empty response"))
+        } else {
+            Ok(url.to_string())
+        };
+    }
+    let value: Value = serde_json::from_str(&text).map_err(|_| invalid("Response is not JSON"))?;
+    extract_field(&value, &config.url_field)
+        .ok_or_else(|| invalid(&format!("Field \"{}\" not found", config.url_field)))
+}
+
+/// 请求错误是否值得重试
+///
+/// ### 说明
+/// - 只有网络层的暂时性错误会重试
+/// - 无效的地址、请求头、TLS 配置等错误重试也不会成功
+fn is_retryable(error: &ureq::Error) -> bool {
+    matches!(
+        error,
+        ureq::Error::Io(_)
+            | ureq::Error::Timeout(_)
+            | ureq::Error::HostNotFound
+            | ureq::Error::ConnectionFailed
+            | ureq::Error::BodyStalled
+    )
+}
+
+/// 按字段路径从 JSON 中提取字符串
+///
+/// ### 参数
+/// - `value`: JSON 值
+/// - `path`: 以 `.` 分隔的字段路径，数字表示数组下标，如 `data.files.0.url`
+///
+/// ### 返回值
+/// - `Option<String>`: 字段为字符串或数字时返回其文本
+pub fn extract_field(value: &Value, path: &str) -> Option<String> {
+    let mut current = value;
+    for key in path.split('.').filter(|k| !k.is_empty()) {
+        current = match current {
+            Value::Array(items) => items.get(key.parse::<usize>().ok()?)?,
+            Value::Object(map) => map.get(key)?,
+            _ => return None,
+        };
+    }
+    match current {
+        Value::String(s) if !s.is_empty() => Some(s.clone()),
+        Value::Number(n) => Some(n.to_string()),
+        _ => None,
+    }
+}
+
+/// 解析访问令牌，`env:变量名` 从环境变量读取
+fn resolve_token(token: &str) -> String {
+    match token.strip_prefix("env:") {
+        Some(name) => std::env::var(name.trim()).unwrap_or_else(|_| {
+            eprintln!("Upload token variable {} is not set", name.trim());
+            String::new()
+        }),
+        None => token.to_string(),
+    }
+}
+
+/// 根据扩展名推断图片 MIME 类型
+fn mime_type(file_name: &str) -> &'static str {
+    let ext = Path::new(file_name)
+        .extension()
+        .map(|e| e.to_string_lossy().to_ascii_lowercase())
+        .unwrap_or_default();
+    match ext.as_str() {
+        "png" => "image/png",
+        "jpg" | "jpeg" => "image/jpeg",
+        "bmp" => "image/bmp",
+        "webp" => "image/webp",
+        _ => "application/octet-stream",
+    }
+}
+
+/// 构建 multipart/form-data 请求体
+fn multipart_body(boundary: &str, field: &str, file_name: &str, content: &[u8]) -> Vec<u8> {
+    let escape = |s: &str| s.replace('"', "%22").replace(['\r', '\n'], " ");
+    let mut body = Vec::with_capacity(content.len() + 256);
+    body.extend_from_slice(
+        format!(
+            "--{}\r\nContent-Disposition: form-data; name=\"{}\"; filename=\"{}\"\r\nContent-Type: {}\r\n\r\n",
+            boundary,
+            escape(field),
+            escape(file_name),
+            mime_type(file_name)
+        )
+        .as_bytes(),
+    );
+    body.extend_from_slice(content);
+    body.extend_from_slice(format!("\r\n--{}--\r\n", boundary).as_bytes());
+    body
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::{
+        io::{BufRead, BufReader, Read, Write},
+        net::TcpListener,
+        sync::mpsc,
+    };
+
+    /// 依次以给定的状态码与响应体应答，返回收到的请求（请求头小写）
+    fn serve(responses: Vec<(u16, &'static str)>) -> (String, mpsc::Receiver<(String, Vec<u8>)>) {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let url = format!("http://{}/upload", listener.local_addr().unwrap());
+        let (tx, rx) = mpsc::channel();
+        thread::spawn(move || {
+            for (status, body) in responses {
+                let (stream, _) = listener.accept().unwrap();
+                let mut reader = BufReader::new(stream);
+                let mut head = String::new();
+                loop {
+                    let mut line = String::new();
+                    reader.read_line(&mut line).unwrap();
+                    if line == "\r\n" || line.is_empty() {
+                        break;
+                    }
+                    head.push_str(&line.to_ascii_lowercase());
+                }
+                let length = head
+                    .lines()
+                    .find_map(|l| l.strip_prefix("content-length:"))
+                    .and_then(|v| v.trim().parse::<usize>().ok())
+                    .unwrap_or(0);
+                let mut content = vec![0; length];
+                reader.read_exact(&mut content).unwrap();
+                let mut stream = reader.into_inner();
+                write!(
+                    stream,
+                    "HTTP/1.1 {} Test\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+                    status,
+                    body.len(),
+                    body
+                )
+                .unwrap();
+                tx.send((head, content)).unwrap();
+            }
+        });
+        (url, rx)
+    }
+
+    /// 在临时目录中写入待上传的文件
+    fn temp_file(name: &str, content: &[u8]) -> std::path::PathBuf {
+        let dir = std::env::temp_dir().join(format!("sc_starter_upload_{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        let file = dir.join(name);
+        fs::write(&file, content).unwrap();
+        file
+    }
+
+    fn config(url: String) -> UploadConfig {
+        UploadConfig {
+            enabled: true,
+            url,
+            retries: 3,
+            backoff: Duration::from_millis(1),
+            timeout: Duration::from_secs(5),
+            ..UploadConfig::default()
+        }
+    }
+
+    #[test]
+    fn multipart_body_with_token() {
+        let (url, rx) = serve(vec![(
+            200,
+            r#"{"data":{"files":[{"url":"https://x/1.png"}]}}"#,
+        )]);
+        let file = temp_file("multipart.png", b"PNGDATA");
+        let config = UploadConfig {
+            field: "image".to_string(),
+            token: "secret".to_string(),
+            token_header: "X-Token".to_string(),
+            token_prefix: "Key ".to_string(),
+            url_field: "data.files.0.url".to_string(),
+            ..config(url)
+        };
+
+        let result = upload_file(&config, &file);
+        assert_eq!(result.url.as_deref(), Some("https://x/1.png"));
+        assert_eq!(result.attempts, 1);
+
+        let (head, content) = rx.recv().unwrap();
+        assert!(head.starts_with("post /upload "));
+        assert!(head.contains("x-token: key secret"));
+        let boundary = head
+            .lines()
+            .find_map(|l| l.strip_prefix("content-type: multipart/form-data; boundary="))
+            .unwrap()
+            .trim()
+            .to_string();
+        let content = String::from_utf8(content).unwrap();
+        // 请求头已转为小写，boundary 按不区分大小写比较
+        assert!(
+            content
+                .to_ascii_lowercase()
+                .starts_with(&format!("--{}\r\n", boundary))
+        );
+        assert!(content.contains("name=\"image\"; filename=\"multipart.png\""));
+        assert!(content.contains("Content-Type: image/png\r\n\r\nPNGDATA\r\n"));
+        assert!(
+            content
+                .to_ascii_lowercase()
+                .ends_with(&format!("--{}--\r\n", boundary))
+        );
+    }
+
+    #[test]
+    fn raw_body_with_plain_response() {
+        let (url, rx) = serve(vec![(201, "https://x/2.jpg\n")]);
+        let file = temp_file("raw.jpg", b"JPEGDATA");
+        let config = UploadConfig {
+            method: "PUT".to_string(),
+            body: UploadBody::Raw,
+            url_field: String::new(),
+            ..config(url)
+        };
+
+        let result = upload_file(&config, &file);
+        assert_eq!(result.url.as_deref(), Some("https://x/2.jpg"));
+
+        let (head, content) = rx.recv().unwrap();
+        assert!(head.starts_with("put /upload "));
+        assert!(head.contains("content-type: image/jpeg"));
+        assert!(!head.contains("authorization:"));
+        assert_eq!(content, b"JPEGDATA");
+    }
+
+    #[test]
+    fn missing_url_field_fails_without_retry() {
+        let (url, _rx) = serve(vec![(200, r#"{"link":"https://x/3.png"}"#)]);
+        let file = temp_file("field.png", b"PNG");
+
+        let result = upload_file(&config(url), &file);
+        assert_eq!(result.url, None);
+        assert_eq!(result.attempts, 1);
+        assert!(result.error.unwrap().contains("Field \"url\" not found"));
+    }
+
+    #[test]
+    fn retries_server_errors_and_rate_limits() {
+        let (url, rx) = serve(vec![
+            (503, "busy"),
+            (429, "slow down"),
+            (200, r#"{"url":"https://x/4.png"}"#),
+        ]);
+        let file = temp_file("retry.png", b"PNG");
+
+        let result = upload_file(&config(url), &file);
+        assert_eq!(result.url.as_deref(), Some("https://x/4.png"));
+        assert_eq!(result.attempts, 3);
+        assert_eq!(rx.iter().count(), 3);
+    }
+
+    #[test]
+    fn client_errors_are_not_retried() {
+        let (url, rx) = serve(vec![(404, "not found"), (200, r#"{"url":"unused"}"#)]);
+        let file = temp_file("client.png", b"PNG");
+
+        let result = upload_file(&config(url), &file);
+        assert_eq!(result.url, None);
+        assert_eq!(result.attempts, 1);
+        assert!(result.error.unwrap().starts_with("HTTP 404"));
+        assert_eq!(
+            rx.recv().map(|(head, _)| head.starts_with("post ")),
+            Ok(true)
+        );
+    }
+
+    #[test]
+    fn extract_nested_fields() {
+        let value: Value =
+            serde_json::from_str(r#"{"data":{"id":7,"files":[{"url":"a"}],"empty":""}}"#).unwrap();
+        assert_eq!(
+            extract_field(&value, "data.files.0.url").as_deref(),
+            Some("a")
+        );
+        assert_eq!(extract_field(&value, "data.id").as_deref(), Some("7"));
+        assert_eq!(extract_field(&value, "data.empty"), None);
+        assert_eq!(extract_field(&value, "data.files.1.url"), None);
+    }
+}
@@ -0,0 +1,272 @@
+//! 截图水印模块
+//!
+//! 本模块负责：
+//! - 渲染水印文字模板（时间、计算机名、标签等）
+//! - 加载字体与图标
+//! - 将文字和/或图标按角落、不透明度与边距绘制到截图上
+
+use crate::capture::{CaptureJob, format_time};
+use crate::types::*;
+use ab_glyph::{Font, FontVec, PxScale, ScaleFont, point};
+use image::{DynamicImage, Rgba, RgbaImage, imageops::FilterType};
+use std::{fs, path::PathBuf};
+
+/// 未指定字体时依次尝试的系统字体（位于 Windows 字体目录）
+const FALLBACK_FONTS: [&str; 4] = ["msyh.ttc", "msyh.ttf", "segoeui.ttf", "arial.ttf"];
+
+/// 渲染水印文字
+///
+/// ### 参数
+/// - `ctx`: 截图任务上下文
+/// - `job`: 截图任务
+/// - `template`: 文字模板
+///
+/// ### 支持的占位符
+/// - `{date}` / `{date:格式}`: 截图时间，默认 `%Y-%m-%d %H:%M:%S`
+/// - `{hostname}` / `{user}`: 计算机名 / 用户名
+/// - `{profile}` / `{project}`: 当前配置名
+/// - `{title}` / `{title:长度}`: 截图前的前台窗口标题
+/// - `{mode}`: 截图模式
+/// - `{version}`: SC_Starter 版本
+pub fn render_watermark_text(ctx: &CaptureContext, job: &CaptureJob, template: &str) -> String {
+    expand_placeholders(template, |name, spec| match name {
+        "date" => format_time(&job.started, spec.unwrap_or("%Y-%m-%d %H:%M:%S")),
+        "hostname" => Some(std::env::var("COMPUTERNAME").unwrap_or_default()),
+        "user" => Some(std::env::var("USERNAME").unwrap_or_default()),
+        "profile" | "project" => Some(ctx.settings.sundry.profile.clone()),
+        "title" => {
+            let max_len = spec.and_then(|s| s.parse::<usize>().ok()).unwrap_or(60);
+            Some(job.window_title.chars().take(max_len).collect())
+        }
+        "mode" => Some(job.mode.key().to_string()),
+        "version" => Some(PKG_VERSION.to_string()),
+        _ => None,
+    })
+}
+
+/// 将水印绘制到图片上
+///
+/// ### 参数
+/// - `img`: 图片
+/// - `config`: 水印配置
+/// - `text`: 已渲染的水印文字
+///
+/// ### 返回值
+/// - `Result<(), String>`: 字体或图标无法加载时返回失败原因，图片保持不变
+///
+/// ### 功能
+/// - 图标在左、文字在右，整体带可选背景
+/// - 按 corner 与 padding 定位，按 opacity 与原图混合
+/// - 水印超出图片时被裁剪
+pub fn apply_watermark(
+    img: &mut DynamicImage,
+    config: &WatermarkConfig,
+    text: &str,
+) -> Result<(), String> {
+    let lines: Vec<&str> = text.lines().filter(|l| !l.trim().is_empty()).collect();
+    let font = if lines.is_empty() {
+        None
+    } else {
+        Some(load_font(&config.font)?)
+    };
+
+    // 文字排版
+    let size = config.font_size;
+    let (text_w, text_h, line_height) = match &font {
+        Some(font) => {
+            let scaled = font.as_scaled(PxScale::from(size));
+            let line_height = scaled.height() + scaled.line_gap();
+            let width = lines
+                .iter()
+                .map(|line| line_width(font, size, line))
+                .fold(0.0f32, f32::max);
+            (
+                width.ceil() as u32,
+                (line_height * lines.len() as f32).ceil() as u32,
+                line_height,
+            )
+        }
+        None => (0, 0, 0.0),
+    };
+
+    let logo = if config.logo.is_empty() {
+        None
+    } else {
+        let path = expand_path(&config.logo).unwrap_or_else(|| PathBuf::from(&config.logo));
+        let logo = image::open(&path)
+            .map_err(|e| format!("{}: {}", path.display(), e))?
+            .to_rgba8();
+        let height = match (config.logo_height, text_h) {
+            (0, 0) => logo.height(),
+            (0, h) => h,
+            (h, _) => h,
+        };
+        let width = (logo.width() as u64 * height as u64 / logo.height().max(1) as u64) as u32;
+        Some(image::imageops::resize(
+            &logo,
+            width.max(1),
+            height.max(1),
+            FilterType::Lanczos3,
+        ))
+    };
+    if font.is_none() && logo.is_none() {
+        return Ok(());
+    }
+
+    // 水印画布：背景、图标、文字
+    let inner = (size / 3.0).round() as u32;
+    let (logo_w, logo_h) = logo.as_ref().map(|l| l.dimensions()).unwrap_or((0, 0));
+    let gap = if logo.is_some() && font.is_some() {
+        inner
+    } else {
+        0
+    };
+    let box_w = inner * 2 + logo_w + gap + text_w;
+    let box_h = inner * 2 + logo_h.max(text_h);
+    let mut overlay = RgbaImage::from_pixel(box_w, box_h, Rgba(config.background));
+
+    if let Some(logo) = &logo {
+        let top = (box_h - logo_h) / 2;
+        for (x, y, pixel) in logo.enumerate_pixels() {
+            blend(overlay.get_pixel_mut(inner + x, top + y), pixel.0, 1.0);
+        }
+    }
+    if let Some(font) = &font {
+        let scaled = font.as_scaled(PxScale::from(size));
+        let left = (inner + logo_w + gap) as f32;
+        let top = ((box_h - text_h) / 2) as f32;
+        for (i, line) in lines.iter().enumerate() {
+            let baseline = top + line_height * i as f32 + scaled.ascent();
+            let mut caret = left;
+            let mut previous = None;
+            for c in line.chars() {
+                let id = scaled.glyph_id(c);
+                if let Some(prev) = previous {
+                    caret += scaled.kern(prev, id);
+                }
+                let glyph = id.with_scale_and_position(size, point(caret, baseline));
+                caret += scaled.h_advance(id);
+                previous = Some(id);
+
+                let Some(outlined) = font.outline_glyph(glyph) else {
+                    continue;
+                };
+                let bounds = outlined.px_bounds();
+                outlined.draw(|gx, gy, coverage| {
+                    let x = bounds.min.x as i64 + gx as i64;
+                    let y = bounds.min.y as i64 + gy as i64;
+                    if (0..box_w as i64).contains(&x) && (0..box_h as i64).contains(&y) {
+                        blend(
+                            overlay.get_pixel_mut(x as u32, y as u32),
+                            config.color,
+                            coverage.clamp(0.0, 1.0),
+                        );
+                    }
+                });
+            }
+        }
+    }
+
+    // 按角落定位并与原图混合
+    let had_alpha = img.color().has_alpha();
+    let mut canvas = img.to_rgba8();
+    let (width, height) = canvas.dimensions();
+    let pad = config.padding as i64;
+    let (ox, oy) = match config.corner {
+        WatermarkCorner::TopLeft => (pad, pad),
+        WatermarkCorner::TopRight => (width as i64 - box_w as i64 - pad, pad),
+        WatermarkCorner::BottomLeft => (pad, height as i64 - box_h as i64 - pad),
+        WatermarkCorner::BottomRight => (
+            width as i64 - box_w as i64 - pad,
+            height as i64 - box_h as i64 - pad,
+        ),
+    };
+    // 图片小于水印时贴边显示
+    let (ox, oy) = (ox.max(0), oy.max(0));
+    for (x, y, pixel) in overlay.enumerate_pixels() {
+        let (tx, ty) = (ox + x as i64, oy + y as i64);
+        if tx < width as i64 && ty < height as i64 {
+            blend(
+                canvas.get_pixel_mut(tx as u32, ty as u32),
+                pixel.0,
+                config.opacity,
+            );
+        }
+    }
+
+    *img = if had_alpha {
+        DynamicImage::ImageRgba8(canvas)
+    } else {
+        DynamicImage::ImageRgb8(DynamicImage::ImageRgba8(canvas).to_rgb8())
+    };
+    Ok(())
+}
+
+/// 加载字体
+///
+/// ### 参数
+/// - `font`: 字体文件路径，为空时依次尝试系统字体
+///
+/// ### 说明
+/// - 字体集（.ttc）使用其中的第一个字体
+fn load_font(font: &str) -> Result<FontVec, String> {
+    let candidates: Vec<PathBuf> = if font.is_empty() {
+        let fonts_dir = PathBuf::from(std::env::var("WINDIR").unwrap_or("C:/Windows".to_string()))
+            .join("Fonts");
+        FALLBACK_FONTS
+            .iter()
+            .map(|name| fonts_dir.join(name))
+            .collect()
+    } else {
+        vec![expand_path(font).unwrap_or_else(|| PathBuf::from(font))]
+    };
+
+    let mut errors = Vec::new();
+    for path in candidates {
+        match fs::read(&path)
+            .map_err(|e| e.to_string())
+            .and_then(|data| FontVec::try_from_vec_and_index(data, 0).map_err(|e| e.to_string()))
+        {
+            Ok(font) => return Ok(font),
+            Err(e) => errors.push(format!("{}: {}", path.display(), e)),
+        }
+    }
+    Err(errors.join("; "))
+}
+
+/// 计算单行文字宽度（像素）
+fn line_width(font: &FontVec, size: f32, line: &str) -> f32 {
+    let scaled = font.as_scaled(PxScale::from(size));
+    let mut width = 0.0;
+    let mut previous = None;
+    for c in line.chars() {
+        let id = scaled.glyph_id(c);
+        if let Some(prev) = previous {
+            width += scaled.kern(prev, id);
+        }
+        width += scaled.h_advance(id);
+        previous = Some(id);
+    }
+    width
+}
+
+/// 以 alpha 混合将颜色叠加到像素上
+///
+/// ### 参数
+/// - `dst`: 目标像素
+/// - `src`: 源颜色（RGBA）
+/// - `opacity`: 额外的不透明度系数（0.0-1.0）
+fn blend(dst: &mut Rgba<u8>, src: [u8; 4], opacity: f32) {
+    let alpha = src[3] as f32 / 255.0 * opacity;
+    if alpha <= 0.0 {
+        return;
+    }
+    let dst_alpha = dst.0[3] as f32 / 255.0;
+    let out_alpha = alpha + dst_alpha * (1.0 - alpha);
+    for (channel, value) in dst.0.iter_mut().zip(src).take(3) {
+        let mixed = (value as f32 * alpha + *channel as f32 * dst_alpha * (1.0 - alpha))
+            / out_alpha.max(f32::EPSILON);
+        *channel = mixed.round().clamp(0.0, 255.0) as u8;
+    }
+    dst.0[3] = (out_alpha * 255.0).round() as u8;
+}
@@ -3,6 +3,8 @@
 //! 本模块提供了以下功能：
 //! - 窗口置顶操作
 //! - 进程检测和查找
+//! - 前台窗口信息获取（标题、所属程序）
+//! - 控制台附加
 //! - Windows API 调用封装
 
 // Windows API 声明
@@ -17,6 +19,8 @@ const SWP_NOSIZE: u32 = 0x0001;
 const INVALID_HANDLE_VALUE: Handle = -1isize as Handle;
 const TH32CS_SNAPPROCESS: Dword = 0x00000002;
 const MAX_PATH: usize = 260;
+const ATTACH_PARENT_PROCESS: Dword = -1i32 as Dword;
+const PROCESS_QUERY_LIMITED_INFORMATION: Dword = 0x1000;
 
 #[repr(C)]
 struct PROCESSENTRY32W {
@@ -49,14 +53,24 @@ unsafe extern "system" {
         lParam: isize,
     ) -> Bool;
     fn IsWindowVisible(hWnd: Hwnd) -> Bool;
+    fn GetForegroundWindow() -> Hwnd;
+    fn GetWindowTextW(hWnd: Hwnd, lpString: *mut u16, nMaxCount: i32) -> i32;
 }
 
 #[link(name = "kernel32")]
 unsafe extern "system" {
+    fn AttachConsole(dwProcessId: Dword) -> Bool;
     fn CreateToolhelp32Snapshot(dw_flags: Dword, th32_process_id: Dword) -> Handle;
     fn Process32FirstW(hSnapshot: Handle, lppe: *mut PROCESSENTRY32W) -> Bool;
     fn Process32NextW(hSnapshot: Handle, lppe: *mut PROCESSENTRY32W) -> Bool;
     fn CloseHandle(hObject: Handle) -> Bool;
+    fn OpenProcess(dwDesiredAccess: Dword, bInheritHandle: Bool, dwProcessId: Dword) -> Handle;
+    fn QueryFullProcessImageNameW(
+        hProcess: Handle,
+        dwFlags: Dword,
+        lpExeName: *mut u16,
+        lpdwSize: *mut Dword,
+    ) -> Bool;
 }
 
 /// 根据进程ID查找并置顶窗口
@@ -147,3 +161,74 @@ pub unsafe fn is_process_running(process_name: impl ToString) -> bool {
     unsafe { CloseHandle(snapshot) };
     found
 }
+
+/// 获取当前前台窗口的标题
+///
+/// ### 返回值
+/// - `String`: 前台窗口标题，无前台窗口或标题为空时返回空字符串
+///
+/// ### 功能
+/// - 获取当前拥有焦点的顶级窗口
+/// - 读取窗口标题文本（最多 MAX_PATH 个字符）
+pub unsafe fn foreground_window_title() -> String {
+    let hwnd = unsafe { GetForegroundWindow() };
+    if hwnd.is_null() {
+        return String::new();
+    }
+
+    let mut buffer = [0u16; MAX_PATH];
+    let len = unsafe { GetWindowTextW(hwnd, buffer.as_mut_ptr(), MAX_PATH as i32) };
+    if len <= 0 {
+        return String::new();
+    }
+    String::from_utf16_lossy(&buffer[..len as usize])
+}
+
+/// 获取当前前台窗口所属程序的文件名
+///
+/// ### 返回值
+/// - `String`: 程序文件名（如 "chrome.exe"），无前台窗口或无权访问时返回空字符串
+///
+/// ### 功能
+/// - 获取前台窗口所属的进程ID
+/// - 以最低查询权限打开进程并读取可执行文件路径
+pub unsafe fn foreground_process_name() -> String {
+    let hwnd = unsafe { GetForegroundWindow() };
+    if hwnd.is_null() {
+        return String::new();
+    }
+    let mut process_id: Dword = 0;
+    unsafe { GetWindowThreadProcessId(hwnd, &mut process_id) };
+    if process_id == 0 {
+        return String::new();
+    }
+
+    let process = unsafe { OpenProcess(PROCESS_QUERY_LIMITED_INFORMATION, 0, process_id) };
+    if process.is_null() {
+        return String::new();
+    }
+    let mut buffer = [0u16; 1024];
+    let mut size = buffer.len() as Dword;
+    let ok = unsafe { QueryFullProcessImageNameW(process, 0, buffer.as_mut_ptr(), &mut size) };
+    unsafe { CloseHandle(process) };
+    if ok == 0 {
+        return String::new();
+    }
+    let path = String::from_utf16_lossy(&buffer[..size as usize]);
+    path.rsplit(['\\', '/'])
+        .next()
+        .unwrap_or_default()
+        .to_string()
+}
+
+/// 附加到父进程的控制台
+///
+/// ### 返回值
+/// - `bool`: 附加成功返回true（如从命令行启动），否则返回false
+///
+/// ### 说明
+/// - 发布版使用 Windows 子系统，默认没有控制台
+/// - 命令行子命令需要调用此函数后才能向终端输出
+pub unsafe fn attach_parent_console() -> bool {
+    unsafe { AttachConsole(ATTACH_PARENT_PROCESS) != 0 }
+}